@@ -0,0 +1,162 @@
+use anyhow::Result;
+
+use crate::settings::CassandraStressSettings;
+
+/// Estimated volume of the data written by the write workload.
+///
+/// Only the raw size of the keys and cell values is taken into account,
+/// so the overhead of the storage format and of the CQL protocol
+/// is not included.
+#[derive(Debug, PartialEq)]
+pub struct DataVolumeEstimate {
+    /// Number of distinct rows written.
+    pub rows: u64,
+    pub row_size: u64,
+    pub replication_factor: u64,
+    /// Data sent by the client, including overwrites.
+    /// Unknown for workloads limited by duration.
+    pub wire_bytes: Option<u64>,
+    /// Data stored by the whole cluster, after compression.
+    pub disk_bytes: u64,
+}
+
+pub fn estimate_data_volume(settings: &CassandraStressSettings) -> Result<DataVolumeEstimate> {
+    let (pop_min, pop_max) = settings.population.pk_seed_distribution.bounds();
+    let population_size = pop_max.abs_diff(pop_min).saturating_add(1);
+    // Each write operation samples rows_per_request seeds.
+    let operation_count = settings
+        .command_params
+        .common
+        .operation_count
+        .map(|n| n.saturating_mul(settings.command_params.rows_per_request()));
+    let denormalize_factor = settings.population.denormalize_factor.get() as u64;
+
+    let distinct_seeds = operation_count.map_or(population_size, |n| n.min(population_size));
+    let rows = distinct_seeds.saturating_mul(denormalize_factor);
+
+    let (size_min, size_max) = settings.column.size_distribution.bounds();
+    let average_cell_size = (size_min.max(0) as u64 + size_max.max(0) as u64) / 2;
+    let row_size = settings.command_params.common.keysize.get() as u64
+        + settings.column.columns.len() as u64 * average_cell_size;
+
+    let replication_factor = settings.schema.total_replication_factor()?;
+
+    let wire_bytes = operation_count.map(|n| {
+        n.saturating_mul(denormalize_factor)
+            .saturating_mul(row_size)
+    });
+
+    let mut disk_bytes = rows
+        .saturating_mul(row_size)
+        .saturating_mul(replication_factor);
+    // The generated values compress only if the table has compression enabled.
+    if settings.schema.compression.is_some() {
+        disk_bytes = (disk_bytes as f64 / settings.column.compressibility) as u64;
+    }
+
+    Ok(DataVolumeEstimate {
+        rows,
+        row_size,
+        replication_factor,
+        wire_bytes,
+        disk_bytes,
+    })
+}
+
+impl DataVolumeEstimate {
+    pub fn print(&self) {
+        println!("Estimated data volume:");
+        println!("  Rows: {}", self.rows);
+        println!("  Average row size: {}", format_bytes(self.row_size));
+        println!("  Replication factor: {}", self.replication_factor);
+        match self.wire_bytes {
+            Some(bytes) => {
+                println!("  Sent by the client: {}", format_bytes(bytes));
+                println!(
+                    "  Received by the replicas: {}",
+                    format_bytes(bytes.saturating_mul(self.replication_factor))
+                );
+            }
+            None => println!("  Sent by the client: unknown (the run is limited by duration)"),
+        }
+        println!(
+            "  On disk (whole cluster): {}",
+            format_bytes(self.disk_bytes)
+        );
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::{parse_cassandra_stress_args, CassandraStressParsingResult};
+
+    use super::*;
+
+    fn estimate(args: &str) -> DataVolumeEstimate {
+        let args = std::iter::once("cassandra-stress").chain(args.split(' '));
+        match parse_cassandra_stress_args(args).unwrap() {
+            CassandraStressParsingResult::Workload(settings) => {
+                estimate_data_volume(&settings).unwrap()
+            }
+            _ => panic!("expected a workload"),
+        }
+    }
+
+    #[test]
+    fn estimate_default_write_test() {
+        // 5 columns of 34 bytes and a 10 byte key
+        assert_eq!(
+            estimate("write n=1000"),
+            DataVolumeEstimate {
+                rows: 1000,
+                row_size: 180,
+                replication_factor: 1,
+                wire_bytes: Some(180_000),
+                disk_bytes: 180_000,
+            }
+        );
+    }
+
+    #[test]
+    fn estimate_overwrites_test() {
+        let estimate = estimate(
+            "write n=2000 -pop seq=1..500 denormalize=2 \
+            -col n=2 size=UNIFORM(10..30) -schema replication(factor=3)",
+        );
+        assert_eq!(estimate.rows, 1000);
+        assert_eq!(estimate.row_size, 50);
+        assert_eq!(estimate.replication_factor, 3);
+        assert_eq!(estimate.wire_bytes, Some(200_000));
+        assert_eq!(estimate.disk_bytes, 150_000);
+    }
+
+    #[test]
+    fn estimate_duration_test() {
+        let estimate = estimate("write duration=10s -pop seq=1..100");
+        assert_eq!(estimate.rows, 100);
+        assert_eq!(estimate.wire_bytes, None);
+    }
+
+    #[test]
+    fn format_bytes_test() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.50 KiB");
+        assert_eq!(format_bytes(3 << 30), "3.00 GiB");
+    }
+}
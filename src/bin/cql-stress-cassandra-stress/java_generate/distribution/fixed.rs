@@ -34,6 +34,10 @@ impl DistributionFactory for FixedDistributionFactory {
     fn create(&self) -> Box<dyn Distribution> {
         Box::new(FixedDistribution::new(self.0))
     }
+
+    fn bounds(&self) -> (i64, i64) {
+        (self.0, self.0)
+    }
 }
 
 impl FixedDistributionFactory {
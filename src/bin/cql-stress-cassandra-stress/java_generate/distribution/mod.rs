@@ -19,6 +19,15 @@ pub trait Distribution: Send + Sync {
     fn next_i64(&self) -> i64;
     fn next_f64(&self) -> f64;
     fn set_seed(&self, seed: i64);
+
+    /// Samples the next value along with the number of times the distribution
+    /// has wrapped around its range before sampling it.
+    ///
+    /// Only meaningful for distributions which cycle through their range
+    /// (i.e. SEQ). Other distributions always report 0.
+    fn next_i64_with_cycle(&self) -> (i64, i64) {
+        (self.next_i64(), 0)
+    }
 }
 
 /// A thread_local wrapper for [java_random::Random].
@@ -50,4 +59,13 @@ impl ThreadLocalRandom {
 
 pub trait DistributionFactory: Send + Sync + std::fmt::Display {
     fn create(&self) -> Box<dyn Distribution>;
+
+    /// Returns the inclusive range of values produced by the distribution.
+    fn bounds(&self) -> (i64, i64);
+
+    /// Whether the distribution goes through its range in sequence,
+    /// so that it repeats a value only after producing all of them.
+    fn is_sequential(&self) -> bool {
+        false
+    }
 }
@@ -84,6 +84,10 @@ impl DistributionFactory for NormalDistributionFactory {
                 .unwrap(),
         )
     }
+
+    fn bounds(&self) -> (i64, i64) {
+        (self.min, self.max)
+    }
 }
 
 impl NormalDistributionFactory {
@@ -161,6 +165,56 @@ mod tests {
 
     use super::NormalDistributionFactory;
 
+    fn sample_mean_and_stdev(args: Vec<&str>, min: i64, max: i64) -> (f64, f64) {
+        let desc = Description {
+            name: "GAUSSIAN",
+            args,
+            inverted: false,
+        };
+        let dist = NormalDistributionFactory::parse_from_description(desc)
+            .unwrap()
+            .create();
+        dist.set_seed(0);
+
+        const SAMPLES: usize = 100_000;
+        let samples = (0..SAMPLES)
+            .map(|_| dist.next_i64())
+            .inspect(|v| assert!((min..=max).contains(v), "{} out of range", v))
+            .map(|v| v as f64)
+            .collect::<Vec<_>>();
+        let mean = samples.iter().sum::<f64>() / SAMPLES as f64;
+        let variance = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / SAMPLES as f64;
+        (mean, variance.sqrt())
+    }
+
+    fn assert_within(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {} +/- {}, got {}",
+            expected,
+            tolerance,
+            actual
+        );
+    }
+
+    #[test]
+    fn gaussian_distribution_spread_test() {
+        // mean=(min+max)/2, stdev=(mean-min)/3 by default.
+        let (mean, stdev) = sample_mean_and_stdev(vec!["0", "3000"], 0, 3000);
+        assert_within(mean, 1500.0, 10.0);
+        assert_within(stdev, 500.0, 10.0);
+
+        // stdev=(mean-min)/stdvrng.
+        let (mean, stdev) = sample_mean_and_stdev(vec!["0", "3000", "6"], 0, 3000);
+        assert_within(mean, 1500.0, 5.0);
+        assert_within(stdev, 250.0, 5.0);
+
+        // Explicit mean and stdev.
+        let (mean, stdev) = sample_mean_and_stdev(vec!["0", "3000", "1000", "100"], 0, 3000);
+        assert_within(mean, 1000.0, 2.0);
+        assert_within(stdev, 100.0, 2.0);
+    }
+
     #[test]
     fn gaussian_distribution_test() {
         // Gaussian distribution sampling values from 1 to 1_000_000_000.
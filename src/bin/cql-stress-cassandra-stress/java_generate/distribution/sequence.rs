@@ -54,6 +54,11 @@ impl Distribution for SeqDistribution {
     fn set_seed(&self, seed: i64) {
         self.seed.store(seed, Ordering::Relaxed);
     }
+
+    fn next_i64_with_cycle(&self) -> (i64, i64) {
+        let seed = self.seed.fetch_add(1, Ordering::Relaxed);
+        (self.start + seed % self.total(), seed / self.total())
+    }
 }
 
 pub struct SeqDistributionFactory {
@@ -78,6 +83,14 @@ impl DistributionFactory for SeqDistributionFactory {
     fn create(&self) -> Box<dyn Distribution> {
         Box::new(SeqDistribution::new(self.min, self.max).unwrap())
     }
+
+    fn bounds(&self) -> (i64, i64) {
+        (self.min, self.max)
+    }
+
+    fn is_sequential(&self) -> bool {
+        true
+    }
 }
 
 impl SeqDistributionFactory {
@@ -128,4 +141,14 @@ mod tests {
         seq.set_seed(103);
         assert_eq!(4, seq.next_i64());
     }
+
+    #[test]
+    fn sequence_distribution_cycle_test() {
+        let seq = SeqDistribution::new(1, 10).unwrap();
+        for cycle in 0..3 {
+            for i in 0..10 {
+                assert_eq!((i + 1, cycle), seq.next_i64_with_cycle());
+            }
+        }
+    }
 }
@@ -73,6 +73,10 @@ impl DistributionFactory for UniformDistributionFactory {
     fn create(&self) -> Box<dyn Distribution> {
         Box::new(UniformDistribution::new(self.min, self.max).unwrap())
     }
+
+    fn bounds(&self) -> (i64, i64) {
+        (self.min as i64, self.max as i64)
+    }
 }
 
 impl UniformDistributionFactory {
@@ -1,16 +1,84 @@
 use std::cmp::min;
 
 use scylla::frame::response::result::CqlValue;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
 
 use super::ValueGenerator;
 use crate::java_generate::distribution::Distribution;
 use crate::java_generate::faster_random::FasterRandom;
 
+// Size of the blocks in which the compressible blobs are generated.
+// Each block consists of random bytes followed by a run of zeroes.
+const COMPRESSIBLE_BLOCK_SIZE: usize = 64;
+
+/// The set of characters that the bytes of the generated blobs are taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[strum(ascii_case_insensitive)]
+pub enum Charset {
+    /// The full byte range. Compatible with the original c-s.
+    Bytes,
+    /// Printable ASCII characters, from the space to the tilde.
+    Printable,
+    /// ASCII letters and digits.
+    Alphanumeric,
+    /// Uppercase hexadecimal digits.
+    Hex,
+}
+
+impl Charset {
+    fn alphabet(&self) -> Option<&'static [u8]> {
+        const PRINTABLE: &[u8] = b" !\"#$%&'()*+,-./0123456789:;<=>?@\
+            ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+        match self {
+            Charset::Bytes => None,
+            Charset::Printable => Some(PRINTABLE),
+            Charset::Alphanumeric => {
+                Some(b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz")
+            }
+            Charset::Hex => Some(b"0123456789ABCDEF"),
+        }
+    }
+}
+
 /// Blob generator based on c-s Bytes generator.
 /// See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/values/Bytes.java#L41
-#[derive(Default)]
 pub struct Blob {
     rng: FasterRandom,
+    // Number of random bytes in each block, the rest of the block is zeroed.
+    random_bytes_per_block: usize,
+    charset: Charset,
+}
+
+impl Default for Blob {
+    fn default() -> Self {
+        Self {
+            rng: FasterRandom::default(),
+            random_bytes_per_block: COMPRESSIBLE_BLOCK_SIZE,
+            charset: Charset::Bytes,
+        }
+    }
+}
+
+impl Blob {
+    /// Creates a generator whose values compress approximately
+    /// with the given ratio. The ratio of 1 yields the same,
+    /// incompressible values as the c-s Bytes generator.
+    pub fn with_compressibility(ratio: f64) -> Self {
+        let random_bytes = (COMPRESSIBLE_BLOCK_SIZE as f64 / ratio).ceil() as usize;
+        Self {
+            random_bytes_per_block: random_bytes.clamp(1, COMPRESSIBLE_BLOCK_SIZE),
+            ..Default::default()
+        }
+    }
+
+    /// Makes the generator map each generated byte onto the given charset.
+    /// The zeroed tails of the compressible blocks are filled with
+    /// the first character of the charset instead.
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
 }
 
 impl ValueGenerator for Blob {
@@ -33,6 +101,20 @@ impl ValueGenerator for Blob {
             i += n;
         }
 
+        if self.random_bytes_per_block < COMPRESSIBLE_BLOCK_SIZE {
+            for block in result.chunks_mut(COMPRESSIBLE_BLOCK_SIZE) {
+                if let Some(tail) = block.get_mut(self.random_bytes_per_block..) {
+                    tail.fill(0);
+                }
+            }
+        }
+
+        if let Some(alphabet) = self.charset.alphabet() {
+            for b in result.iter_mut() {
+                *b = alphabet[*b as usize % alphabet.len()];
+            }
+        }
+
         CqlValue::Blob(result)
     }
 }
@@ -55,7 +137,7 @@ mod tests {
     };
     use scylla::frame::response::result::CqlValue;
 
-    use super::Blob;
+    use super::{Blob, Charset};
 
     /// Utility function that maps u8 vector values to i8 values.
     fn to_vec_i8(v: CqlValue) -> Vec<i8> {
@@ -111,4 +193,73 @@ mod tests {
         let row5 = gen.generate();
         assert_eq!(to_vec_i8(row5), vec![-36, -98, 27, -16, 94]);
     }
+
+    #[test]
+    fn compressible_blob_generator_test() {
+        let new_generator = |blob: Blob| {
+            let config = GeneratorConfig::new(
+                "randomstrC0",
+                None,
+                Some(Box::new(FixedDistribution::new(200))),
+            );
+            Generator::new(Box::new(blob), config, String::from("C0"))
+        };
+        let mut random_gen = new_generator(Blob::default());
+        let mut compressible_gen = new_generator(Blob::with_compressibility(4.0));
+
+        random_gen.set_seed(1338786723438483);
+        compressible_gen.set_seed(1338786723438483);
+        let random = random_gen.generate().into_blob().unwrap();
+        let compressible = compressible_gen.generate().into_blob().unwrap();
+
+        assert_eq!(compressible.len(), 200);
+        // Each block of 64 bytes starts with 16 random bytes, followed by zeroes.
+        for (block_idx, (random_block, block)) in
+            random.chunks(64).zip(compressible.chunks(64)).enumerate()
+        {
+            let random_len = block.len().min(16);
+            assert_eq!(block[..random_len], random_block[..random_len]);
+            assert!(
+                block[random_len..].iter().all(|b| *b == 0),
+                "block {} is not zero-padded",
+                block_idx
+            );
+        }
+    }
+
+    #[test]
+    fn charset_blob_generator_test() {
+        let new_generator = |blob: Blob| {
+            let config = GeneratorConfig::new(
+                "randomstrC0",
+                None,
+                Some(Box::new(FixedDistribution::new(200))),
+            );
+            Generator::new(Box::new(blob), config, String::from("C0"))
+        };
+
+        for charset in [Charset::Printable, Charset::Alphanumeric, Charset::Hex] {
+            let alphabet = charset.alphabet().unwrap();
+            for blob in [
+                Blob::default().with_charset(charset),
+                Blob::with_compressibility(4.0).with_charset(charset),
+            ] {
+                let mut gen = new_generator(blob);
+                gen.set_seed(1338786723438483);
+                let value = gen.generate().into_blob().unwrap();
+                assert_eq!(value.len(), 200);
+                assert!(
+                    value.iter().all(|b| alphabet.contains(b)),
+                    "{:?} generated a value outside of the charset",
+                    charset
+                );
+            }
+        }
+
+        // Mapping onto the charset preserves the runs of the compressible blocks.
+        let mut gen = new_generator(Blob::with_compressibility(4.0).with_charset(Charset::Hex));
+        gen.set_seed(1338786723438483);
+        let value = gen.generate().into_blob().unwrap();
+        assert!(value[16..64].iter().all(|b| *b == b'0'));
+    }
 }
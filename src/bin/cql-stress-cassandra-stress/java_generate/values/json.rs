@@ -0,0 +1,175 @@
+use scylla::frame::response::result::CqlValue;
+
+use crate::java_generate::{distribution::Distribution, faster_random::FasterRandom};
+
+use super::{ValueGenerator, ValueGeneratorFactory};
+
+const MAX_STRING_LENGTH: u64 = 16;
+
+/// Generator of JSON documents.
+///
+/// Generates objects whose fields hold numbers, booleans, strings
+/// and nested objects, up to `max_depth` levels of nesting.
+/// The size sampled from the size distribution is the approximate
+/// length of the document - fields are added until it is reached.
+///
+/// Similarly to other generators, the document is fully determined
+/// by the seed sampled from the identity distribution.
+pub struct Json {
+    rng: FasterRandom,
+    max_depth: u32,
+}
+
+impl Json {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            rng: FasterRandom::default(),
+            max_depth,
+        }
+    }
+
+    fn next_u64(&mut self, bound: u64) -> u64 {
+        (self.rng.next_i64() as u64) % bound
+    }
+
+    // Writes an object to `out`, adding fields until `out` is at least `end` bytes long.
+    fn write_object(&mut self, out: &mut String, depth: u32, end: usize) {
+        out.push('{');
+        let mut field_idx = 0;
+        while out.len() < end {
+            if field_idx > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!("\"f{}\":", field_idx));
+            self.write_value(out, depth, end);
+            field_idx += 1;
+        }
+        out.push('}');
+    }
+
+    fn write_value(&mut self, out: &mut String, depth: u32, end: usize) {
+        match self.next_u64(4) {
+            0 if depth < self.max_depth => {
+                // A nested object takes up to half of the remaining space.
+                let remaining = end.saturating_sub(out.len());
+                self.write_object(out, depth + 1, out.len() + remaining / 2);
+            }
+            1 => {
+                let b = self.next_u64(2) == 0;
+                out.push_str(if b { "true" } else { "false" });
+            }
+            2 => {
+                let len = 1 + self.next_u64(MAX_STRING_LENGTH);
+                out.push('"');
+                for _ in 0..len {
+                    out.push((b'a' + self.next_u64(26) as u8) as char);
+                }
+                out.push('"');
+            }
+            _ => {
+                let n = self.rng.next_i64() % 1_000_000;
+                out.push_str(&n.to_string());
+            }
+        }
+    }
+}
+
+impl ValueGenerator for Json {
+    fn generate(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+    ) -> CqlValue {
+        let seed = identity_distribution.next_i64();
+        size_distribution.set_seed(seed);
+        self.rng.set_seed(!seed);
+        let size = size_distribution.next_i64() as usize;
+
+        let mut result = String::with_capacity(size + 2 * MAX_STRING_LENGTH as usize);
+        self.write_object(&mut result, 0, size);
+
+        CqlValue::Text(result)
+    }
+}
+
+pub struct JsonFactory {
+    pub max_depth: u32,
+}
+
+impl ValueGeneratorFactory for JsonFactory {
+    fn create(&self) -> Box<dyn ValueGenerator> {
+        Box::new(Json::new(self.max_depth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::java_generate::{
+        distribution::fixed::FixedDistribution,
+        values::{Generator, GeneratorConfig},
+    };
+
+    use super::Json;
+
+    fn new_generator(size: i64, max_depth: u32) -> Generator {
+        let config = GeneratorConfig::new(
+            "randomstrC0",
+            None,
+            Some(Box::new(FixedDistribution::new(size))),
+        );
+        Generator::new(Box::new(Json::new(max_depth)), config, String::from("C0"))
+    }
+
+    // Checks that the document is a well-formed object
+    // and returns its maximum nesting depth.
+    fn check_structure(doc: &str) -> usize {
+        assert!(doc.starts_with('{') && doc.ends_with('}'), "{}", doc);
+        let (mut depth, mut max_depth, mut in_string) = (0usize, 0, false);
+        for c in doc.chars() {
+            match c {
+                '"' => in_string = !in_string,
+                '{' if !in_string => {
+                    depth += 1;
+                    max_depth = max_depth.max(depth);
+                }
+                '}' if !in_string => depth -= 1,
+                _ => {}
+            }
+        }
+        assert_eq!(depth, 0, "{}", doc);
+        assert!(!in_string, "{}", doc);
+        max_depth
+    }
+
+    #[test]
+    fn json_generator_test() {
+        let mut gen = new_generator(500, 3);
+
+        for seed in [0, 1, 0xdeadcafe, i64::MAX] {
+            gen.set_seed(seed);
+            let doc = gen.generate().into_string().unwrap();
+            assert!(doc.len() >= 500);
+            let depth = check_structure(&doc);
+            assert!((1..=4).contains(&depth), "{}", doc);
+
+            // The documents are deterministic.
+            gen.set_seed(seed);
+            assert_eq!(doc, gen.generate().into_string().unwrap());
+        }
+    }
+
+    #[test]
+    fn flat_json_generator_test() {
+        let mut gen = new_generator(100, 0);
+        gen.set_seed(1338786723438483);
+        let doc = gen.generate().into_string().unwrap();
+        assert_eq!(check_structure(&doc), 1);
+    }
+
+    #[test]
+    fn empty_json_generator_test() {
+        let mut gen = new_generator(0, 3);
+        gen.set_seed(0);
+        assert_eq!(gen.generate().into_string().unwrap(), "{}");
+    }
+}
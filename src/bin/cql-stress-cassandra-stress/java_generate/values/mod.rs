@@ -23,13 +23,15 @@ pub mod inet;
 #[cfg(feature = "user-profile")]
 pub mod int;
 #[cfg(feature = "user-profile")]
+pub mod json;
+#[cfg(feature = "user-profile")]
 pub mod text;
 #[cfg(feature = "user-profile")]
 pub mod uuid;
 #[cfg(feature = "user-profile")]
 pub mod varint;
 
-pub use blob::Blob;
+pub use blob::{Blob, Charset};
 pub use hex_blob::HexBlob;
 
 /// Generic generator of random values.
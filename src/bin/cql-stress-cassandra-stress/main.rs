@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate async_trait;
 
+mod estimate;
 mod java_generate;
 mod operation;
 mod settings;
@@ -28,7 +29,11 @@ use operation::{
 };
 use scylla::{ExecutionProfile, Session, SessionBuilder};
 use stats::{ShardedStats, StatsFactory, StatsPrinter};
-use std::{env, sync::Arc, time::Duration};
+use std::{
+    env,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tracing_subscriber::EnvFilter;
 
 use settings::{CassandraStressParsingResult, CassandraStressSettings};
@@ -44,6 +49,10 @@ async fn main() -> Result<()> {
         // Special commands: help, print, version
         Ok(CassandraStressParsingResult::SpecialCommand) => return Ok(()),
         Ok(CassandraStressParsingResult::Workload(payload)) => Arc::new(*payload),
+        Ok(CassandraStressParsingResult::Schema(payload)) => {
+            print!("{}", payload.schema_ddl());
+            return Ok(());
+        }
         Err(e) => {
             // For some reason cassandra-stress writes all parsing-related
             // error messages to stdout. We will follow the same approach.
@@ -54,21 +63,48 @@ async fn main() -> Result<()> {
 
     settings.print_settings();
 
+    if settings.estimate.enabled {
+        estimate::estimate_data_volume(&settings)?.print();
+        return Ok(());
+    }
+
     let stats_factory = Arc::new(StatsFactory::new(&settings));
     let sharded_stats = Arc::new(ShardedStats::new(Arc::clone(&stats_factory)));
 
-    let run_config = prepare_run(Arc::clone(&settings), Arc::clone(&sharded_stats))
+    let run_configs = prepare_run(Arc::clone(&settings), Arc::clone(&sharded_stats))
         .await
         .context("Failed to prepare benchmark")?;
 
     let mut combined_stats = stats_factory.create();
 
-    let (ctrl, run_finished) = cql_stress::run::run(run_config);
+    // All of the runs feed the same stats, so that they are summarized together.
+    let (ctrls, runs_finished): (Vec<_>, Vec<_>) =
+        run_configs.into_iter().map(cql_stress::run::run).unzip();
+    let ctrls = Arc::new(ctrls);
+    // The first failed run stops the other ones, and the result is reported
+    // once all of them finish.
+    let run_finished = futures::future::join_all(runs_finished.into_iter().map(|run_finished| {
+        let ctrls = Arc::clone(&ctrls);
+        async move {
+            let result = run_finished.await;
+            if result.is_err() {
+                ctrls.iter().for_each(RunController::ask_to_stop);
+            }
+            result
+        }
+    }));
+    let run_finished = async { run_finished.await.into_iter().collect::<Result<Vec<_>>>() };
 
     // Run a background task waiting for a stop-signal (Ctrl+C).
-    tokio::task::spawn(stop_on_signal(ctrl));
+    tokio::task::spawn(stop_on_signal(Arc::clone(&ctrls)));
 
-    let mut printer = StatsPrinter::new();
+    let mut printer = StatsPrinter::new().with_warmup(
+        settings
+            .command_params
+            .common
+            .warmup
+            .unwrap_or(Duration::ZERO),
+    );
 
     // TODO: change the interval based on -log option (when supported).
     let mut ticker = tokio::time::interval(Duration::from_secs(1));
@@ -95,26 +131,30 @@ async fn main() -> Result<()> {
                     combined_stats.combine(&partial_stats);
                     printer.print_summary(&combined_stats);
                 }
-                return result.context("An error occurred during the benchmark");
+                return result
+                    .map(|_| ())
+                    .context("An error occurred during the benchmark");
             }
         }
     }
 }
 
-async fn stop_on_signal(runner: RunController) {
+async fn stop_on_signal(runners: Arc<Vec<RunController>>) {
     // Try stopping gracefully upon receiving first signal.
     tokio::signal::ctrl_c().await.unwrap();
-    runner.ask_to_stop();
+    runners.iter().for_each(RunController::ask_to_stop);
 
     // Abort after second signal.
     tokio::signal::ctrl_c().await.unwrap();
-    runner.abort();
+    runners.iter().for_each(RunController::abort);
 }
 
+// Returns the configurations of the runs to be performed concurrently:
+// a single one, unless the mixed command uses separate pools of workers.
 async fn prepare_run(
     settings: Arc<CassandraStressSettings>,
     stats: Arc<ShardedStats>,
-) -> Result<Configuration> {
+) -> Result<Vec<Configuration>> {
     let mut builder = SessionBuilder::new()
         .known_nodes(&settings.node.nodes)
         .compression(settings.mode.compression);
@@ -135,35 +175,102 @@ async fn prepare_run(
 
     builder = builder.pool_size(settings.mode.pool_size);
 
+    // Session building includes the discovery of the topology.
+    let connect_start = Instant::now();
     let session = builder.build().await?;
     let session = Arc::new(session);
+    let connect_time = connect_start.elapsed();
 
+    let schema_start = Instant::now();
     settings
         .create_schema(&session)
         .await
         .context("Failed to create schema")?;
+    let schema_time = schema_start.elapsed();
 
     let duration = settings.command_params.common.duration;
-
-    let (concurrency, throttle) = match settings.rate.threads_info {
-        ThreadsInfo::Fixed {
-            threads, throttle, ..
-        } => (threads, throttle.map(|th| th as f64)),
-        ThreadsInfo::Auto { .. } => {
-            anyhow::bail!("Runtime not implemented for auto-adjusting rate configuration");
+    let warmup = settings.command_params.common.warmup;
+
+    let make_config =
+        |concurrency: u64, throttle: Option<f64>, operation_factory: Arc<dyn OperationFactory>| {
+            Configuration {
+                max_duration: duration,
+                warmup,
+                concurrency,
+                rate_limit_per_second: throttle,
+                rate_jitter: None,
+                arrival_schedule: None,
+                operation_factory,
+                // TODO: adjust when -errors option is supported
+                max_retries_per_op: 9,
+                worker_start_stagger: None,
+                operation_hook: None,
+                error_ratio_limit: None,
+                max_consecutive_errors: None,
+                retry_filter: None,
+            }
+        };
+
+    let prepare_start = Instant::now();
+    let pools = settings
+        .command_params
+        .mixed
+        .as_ref()
+        .and_then(|mixed| mixed.pools.as_ref());
+    let configs = match pools {
+        // The pools have their own concurrency and throttling
+        Some(pools) => {
+            anyhow::ensure!(
+                !matches!(
+                    settings.rate.threads_info,
+                    ThreadsInfo::Fixed {
+                        throttle: Some(_),
+                        ..
+                    }
+                ),
+                "-rate throttle= cannot be used with concurrency(...) of the mixed command, \
+                use throttle(...) instead"
+            );
+            let mut configs = Vec::with_capacity(pools.len());
+            for pool in pools {
+                let operation_factory = MixedOperationFactory::for_subcommand(
+                    Arc::clone(&settings),
+                    Arc::clone(&session),
+                    RowGeneratorFactory::new(Arc::clone(&settings)),
+                    Arc::clone(&stats),
+                    pool.subcommand,
+                )
+                .await?;
+                configs.push(make_config(
+                    pool.concurrency,
+                    pool.throttle,
+                    Arc::new(operation_factory),
+                ));
+            }
+            configs
+        }
+        None => {
+            let (concurrency, throttle) = match settings.rate.threads_info {
+                ThreadsInfo::Fixed {
+                    threads, throttle, ..
+                } => (threads, throttle.map(|th| th as f64)),
+                ThreadsInfo::Auto { .. } => {
+                    anyhow::bail!("Runtime not implemented for auto-adjusting rate configuration");
+                }
+            };
+            let operation_factory =
+                create_operation_factory(session, Arc::clone(&settings), stats).await?;
+            vec![make_config(concurrency, throttle, operation_factory)]
         }
     };
+    let prepare_time = prepare_start.elapsed();
 
-    let operation_factory = create_operation_factory(session, settings, stats).await?;
+    println!(
+        "Setup: connected in {:.3?}, schema created in {:.3?}, statements prepared in {:.3?}",
+        connect_time, schema_time, prepare_time,
+    );
 
-    Ok(Configuration {
-        max_duration: duration,
-        concurrency,
-        rate_limit_per_second: throttle,
-        operation_factory,
-        // TODO: adjust when -errors option is supported
-        max_retries_per_op: 9,
-    })
+    Ok(configs)
 }
 
 async fn create_operation_factory(
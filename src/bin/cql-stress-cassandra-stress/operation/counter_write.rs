@@ -9,7 +9,8 @@ use scylla::{prepared_statement::PreparedStatement, Session};
 use crate::{java_generate::distribution::Distribution, settings::CassandraStressSettings};
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
+    print_prepared_statement, row_generator::RowGenerator, CassandraStressOperation,
+    CassandraStressOperationFactory,
 };
 
 pub struct CounterWriteOperation {
@@ -88,6 +89,7 @@ impl CounterWriteOperationFactory {
             .prepare(statement_str)
             .await
             .context("Failed to prepare statement")?;
+        print_prepared_statement("counter_write", &statement);
 
         statement.set_consistency(settings.command_params.common.consistency_level);
         statement.set_serial_consistency(Some(
@@ -0,0 +1,82 @@
+use std::{ops::ControlFlow, sync::Arc};
+
+use anyhow::{Context, Result};
+use scylla::{frame::response::result::CqlValue, prepared_statement::PreparedStatement, Session};
+
+use crate::settings::CassandraStressSettings;
+
+use super::{
+    print_prepared_statement, row_generator::RowGenerator, CassandraStressOperation,
+    CassandraStressOperationFactory,
+};
+
+/// Deletes the whole partition of a generated key.
+///
+/// Available only as a part of the mixed workload.
+pub struct DeleteOperation {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+}
+
+pub struct DeleteOperationFactory {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+}
+
+impl CassandraStressOperation for DeleteOperation {
+    type Factory = DeleteOperationFactory;
+
+    async fn execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
+        // execute_unpaged, since it's a DELETE statement.
+        let result = self.session.execute_unpaged(&self.statement, row).await;
+
+        if let Err(err) = result.as_ref() {
+            tracing::error!(
+                error = %err,
+                partition_key = ?row[0],
+                "delete error",
+            );
+        }
+
+        result?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
+        vec![row_generator.generate_pk()]
+    }
+}
+
+impl CassandraStressOperationFactory for DeleteOperationFactory {
+    type Operation = DeleteOperation;
+
+    fn create(&self) -> Self::Operation {
+        DeleteOperation {
+            session: Arc::clone(&self.session),
+            statement: self.statement.clone(),
+        }
+    }
+}
+
+impl DeleteOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        table_name: &str,
+    ) -> Result<Self> {
+        let statement_str = format!("DELETE FROM {} WHERE key = ?", table_name);
+        let mut statement = session
+            .prepare(statement_str)
+            .await
+            .context("Failed to prepare statement")?;
+        print_prepared_statement("delete", &statement);
+
+        statement.set_is_idempotent(true);
+        statement.set_consistency(settings.command_params.common.consistency_level);
+        statement.set_serial_consistency(Some(
+            settings.command_params.common.serial_consistency_level,
+        ));
+
+        Ok(Self { session, statement })
+    }
+}
@@ -16,6 +16,7 @@ use crate::{
 
 use super::{
     counter_write::{CounterWriteOperation, CounterWriteOperationFactory},
+    delete::{DeleteOperation, DeleteOperationFactory},
     read::{
         CounterReadOperation, CounterReadOperationFactory, RegularReadOperation,
         RegularReadOperationFactory,
@@ -31,6 +32,7 @@ pub struct MixedOperation {
     counter_write_operation: Option<CounterWriteOperation>,
     read_operation: Option<RegularReadOperation>,
     counter_read_operation: Option<CounterReadOperation>,
+    delete_operation: Option<DeleteOperation>,
     cached_row: Option<Vec<CqlValue>>,
     workload: RowGenerator,
     max_operations: Option<u64>,
@@ -47,6 +49,7 @@ pub struct MixedOperationFactory {
     counter_write_operation_factory: Option<CounterWriteOperationFactory>,
     read_operation_factory: Option<RegularReadOperationFactory>,
     counter_read_operation_factory: Option<CounterReadOperationFactory>,
+    delete_operation_factory: Option<DeleteOperationFactory>,
     operation_ratio: Arc<OperationRatio>,
     workload_factory: RowGeneratorFactory,
     max_operations: Option<u64>,
@@ -67,12 +70,14 @@ impl OperationFactory for MixedOperationFactory {
         let counter_write_operation = create_operation_opt(&self.counter_write_operation_factory);
         let read_operation = create_operation_opt(&self.read_operation_factory);
         let counter_read_operation = create_operation_opt(&self.counter_read_operation_factory);
+        let delete_operation = create_operation_opt(&self.delete_operation_factory);
 
         Box::new(MixedOperation {
             write_operation,
             counter_write_operation,
             read_operation,
             counter_read_operation,
+            delete_operation,
             cached_row: None,
             workload: self.workload_factory.create(),
             max_operations: self.max_operations,
@@ -92,49 +97,95 @@ impl MixedOperationFactory {
         workload_factory: RowGeneratorFactory,
         stats: Arc<ShardedStats>,
     ) -> Result<Self> {
-        let mixed_params = settings.command_params.mixed.as_ref().unwrap();
+        let operation_ratio = settings
+            .command_params
+            .mixed
+            .as_ref()
+            .unwrap()
+            .operation_ratio
+            .clone();
+        Self::with_ratio(settings, session, workload_factory, stats, operation_ratio).await
+    }
+
+    /// Creates a factory of operations performing only the given suboperation,
+    /// used by the separate pools of workers.
+    pub async fn for_subcommand(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        subcommand: MixedSubcommand,
+    ) -> Result<Self> {
+        let operation_ratio = OperationRatio::new(vec![(subcommand, 1.0)])?;
+        Self::with_ratio(settings, session, workload_factory, stats, operation_ratio).await
+    }
+
+    async fn with_ratio(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        operation_ratio: OperationRatio,
+    ) -> Result<Self> {
         let max_operations = settings.command_params.common.operation_count;
-        let operation_ratio = Arc::new(mixed_params.operation_ratio.clone());
-        let write_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
-            &MixedSubcommand::Write,
-            || WriteOperationFactory::new(settings.clone(), session.clone()),
-        )
-        .await
-        .transpose()?;
+        let operation_ratio = Arc::new(operation_ratio);
+        let write_operation_factory =
+            Self::conditional_create_factory(&operation_ratio, &MixedSubcommand::Write, || {
+                WriteOperationFactory::new(settings.clone(), session.clone())
+            })
+            .await
+            .transpose()?;
         let counter_write_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
+            &operation_ratio,
             &MixedSubcommand::CounterWrite,
             || CounterWriteOperationFactory::new(settings.clone(), session.clone()),
         )
         .await
         .transpose()?;
-        let read_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
-            &MixedSubcommand::Read,
-            || {
-                RegularReadOperationFactory::new(
-                    settings.clone(),
-                    session.clone(),
-                    DEFAULT_TABLE_NAME,
-                )
-            },
-        )
-        .await
-        .transpose()?;
+        // The rows read by one pool might be deleted by another one,
+        // so the whole mixed command is checked
+        let deletes_rows = settings
+            .command_params
+            .mixed
+            .as_ref()
+            .is_some_and(|mixed| mixed.deletes_rows());
+        let read_operation_factory =
+            Self::conditional_create_factory(&operation_ratio, &MixedSubcommand::Read, || {
+                let settings = settings.clone();
+                let session = session.clone();
+                async move {
+                    let factory = RegularReadOperationFactory::new(
+                        settings,
+                        session,
+                        DEFAULT_TABLE_NAME,
+                        "read",
+                    )
+                    .await?;
+                    anyhow::Ok(factory.allow_missing_rows(deletes_rows))
+                }
+            })
+            .await
+            .transpose()?;
         let counter_read_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
+            &operation_ratio,
             &MixedSubcommand::CounterRead,
             || {
                 CounterReadOperationFactory::new(
                     settings.clone(),
                     session.clone(),
                     DEFAULT_COUNTER_TABLE_NAME,
+                    "counter_read",
                 )
             },
         )
         .await
         .transpose()?;
+        let delete_operation_factory =
+            Self::conditional_create_factory(&operation_ratio, &MixedSubcommand::Delete, || {
+                DeleteOperationFactory::new(settings.clone(), session.clone(), DEFAULT_TABLE_NAME)
+            })
+            .await
+            .transpose()?;
 
         Ok(Self {
             settings,
@@ -142,6 +193,7 @@ impl MixedOperationFactory {
             counter_write_operation_factory,
             read_operation_factory,
             counter_read_operation_factory,
+            delete_operation_factory,
             operation_ratio,
             workload_factory,
             max_operations,
@@ -212,9 +264,19 @@ impl MixedOperation {
                 });
                 counter_write_operation.execute(row).await
             }
+            MixedSubcommand::Delete => {
+                // This is safe. We create a given operation only if corresponding `MixedSubcommand` is defined in `operation_ratio` map.
+                let delete_operation = self.delete_operation.as_ref().unwrap();
+                let row = self
+                    .cached_row
+                    .get_or_insert_with(|| delete_operation.generate_row(&mut self.workload));
+                delete_operation.execute(row).await
+            }
         };
 
-        self.stats.get_shard_mut().account_operation(ctx, &result);
+        self.stats
+            .get_shard_mut_for(ctx.warmup)
+            .account_operation(ctx, &result);
 
         if result.is_ok() {
             self.current_operation_remaining -= 1;
@@ -1,4 +1,5 @@
 mod counter_write;
+mod delete;
 mod mixed;
 mod read;
 mod row_generator;
@@ -13,6 +14,7 @@ use cql_stress::configuration::OperationFactory;
 use cql_stress::make_runnable;
 #[cfg(feature = "user-profile")]
 use rand_distr::{Distribution as _, WeightedIndex};
+use scylla::prepared_statement::PreparedStatement;
 use scylla::Session;
 use std::future::Future;
 use std::num::Wrapping;
@@ -38,6 +40,16 @@ use self::row_generator::RowGenerator;
 const DEFAULT_TABLE_NAME: &str = "standard1";
 const DEFAULT_COUNTER_TABLE_NAME: &str = "counter1";
 
+/// Prints the CQL of a prepared statement, placeholders included, so that it
+/// can be compared with the statements executed by the original c-s.
+fn print_prepared_statement(operation_name: &str, statement: &PreparedStatement) {
+    println!(
+        "Prepared statement for {}: {}",
+        operation_name,
+        statement.get_statement()
+    );
+}
+
 /// A specific CassandraStress operation.
 ///
 /// The operation implementing this trait should handle
@@ -105,7 +117,7 @@ impl<O: CassandraStressOperation> GenericCassandraStressOperation<O> {
 
         let op_result = self.cs_operation.execute(row).await;
         self.stats
-            .get_shard_mut()
+            .get_shard_mut_for(ctx.warmup)
             .account_operation(ctx, &op_result);
 
         if op_result.is_ok() {
@@ -181,7 +193,8 @@ impl RegularReadOperationFactory {
     ) -> Result<Self> {
         let max_operations = settings.command_params.common.operation_count;
         let cs_operation_factory =
-            read::RegularReadOperationFactory::new(settings, session, DEFAULT_TABLE_NAME).await?;
+            read::RegularReadOperationFactory::new(settings, session, DEFAULT_TABLE_NAME, "read")
+                .await?;
 
         Ok(Self {
             cs_operation_factory,
@@ -200,9 +213,13 @@ impl CounterReadOperationFactory {
         stats: Arc<ShardedStats>,
     ) -> Result<Self> {
         let max_operations = settings.command_params.common.operation_count;
-        let cs_operation_factory =
-            read::CounterReadOperationFactory::new(settings, session, DEFAULT_COUNTER_TABLE_NAME)
-                .await?;
+        let cs_operation_factory = read::CounterReadOperationFactory::new(
+            settings,
+            session,
+            DEFAULT_COUNTER_TABLE_NAME,
+            "counter_read",
+        )
+        .await?;
 
         Ok(Self {
             cs_operation_factory,
@@ -243,71 +260,206 @@ fn recompute_seed(seed: i64, partition_key: &CqlValue) -> i64 {
     }
 }
 
-fn extract_first_row_from_query_result(query_result: &QueryResult) -> Result<&Row> {
+/// An error returned when a row read from the database doesn't match
+/// the row generated for the same partition key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// The query returned no rows for the partition key.
+    MissingRow,
+    /// The query returned more rows for the partition key than were generated.
+    /// Currently, row-generation logic generates exactly one row per partition key.
+    MultipleRows { count: usize },
+    /// The query returned fewer rows than were generated for the partition key.
+    /// `missing_positions` are the indices (in clustering order)
+    /// of the generated rows that were not found in the result.
+    ShortRead {
+        expected: usize,
+        received: usize,
+        missing_positions: Vec<usize>,
+    },
+    /// The row read from the database has a different number of columns
+    /// than the generated row.
+    ColumnCountMismatch { expected: usize, actual: usize },
+    /// The value of the column with index `column` differs from the generated one.
+    /// `actual` is `None` if the column was NULL. The values are boxed,
+    /// so that the results carrying the error stay small.
+    ValueMismatch {
+        column: usize,
+        expected: Box<CqlValue>,
+        actual: Option<Box<CqlValue>>,
+    },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::MissingRow => write!(f, "Query result doesn't contain any rows."),
+            ValidationError::MultipleRows { count } => {
+                write!(f, "Multiple rows ({}) matched the key.", count)
+            }
+            ValidationError::ShortRead {
+                expected,
+                received,
+                missing_positions,
+            } => write!(
+                f,
+                "Short read: expected {} rows, received {}. Missing rows at positions: {:?}",
+                expected, received, missing_positions,
+            ),
+            ValidationError::ColumnCountMismatch { expected, actual } => write!(
+                f,
+                "Expected row length: {}. Result row length: {}",
+                expected, actual,
+            ),
+            ValidationError::ValueMismatch {
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The data doesn't match in column {}. Result: {:?}. Expected: {:?}.",
+                column, actual, expected,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+fn extract_first_row_from_query_result(
+    query_result: &QueryResult,
+) -> Result<&Row, ValidationError> {
     let rows = match &query_result.rows {
         Some(rows) => rows,
-        None => anyhow::bail!("Query result doesn't contain any rows.",),
+        None => return Err(ValidationError::MissingRow),
     };
 
     match rows.split_first() {
         Some((first_row, remaining_rows)) => {
             // Note that row-generation logic behaves in a way that given partition_key,
             // there is exactly one row with this partition_key.
-            anyhow::ensure!(
-                remaining_rows.is_empty(),
-                "Multiple rows matched the key. Rows: {:?}",
-                rows
-            );
+            if !remaining_rows.is_empty() {
+                return Err(ValidationError::MultipleRows { count: rows.len() });
+            }
             Ok(first_row)
         }
-        None => anyhow::bail!("Query result doesn't contain any rows.",),
+        None => Err(ValidationError::MissingRow),
+    }
+}
+
+/// Validates that the rows of the query result contain exactly one row,
+/// equal to the `generated_row`.
+///
+/// NULL values are never permitted, since the row generator never generates them.
+pub fn validate_row(
+    generated_row: &[CqlValue],
+    rows: Option<&[Row]>,
+) -> Result<(), ValidationError> {
+    // The partition key is the only key column.
+    validate_rows(&[generated_row], 1, rows)
+}
+
+/// Validates that the rows of the query result are exactly the `generated_rows`
+/// of a single partition.
+///
+/// The rows are matched by their first `key_len` columns (the primary key),
+/// so that the generated rows missing from the result can be reported precisely.
+pub fn validate_rows(
+    generated_rows: &[&[CqlValue]],
+    key_len: usize,
+    rows: Option<&[Row]>,
+) -> Result<(), ValidationError> {
+    let rows = match rows {
+        Some(rows) if !rows.is_empty() => rows,
+        _ => return Err(ValidationError::MissingRow),
+    };
+    if rows.len() > generated_rows.len() {
+        return Err(ValidationError::MultipleRows { count: rows.len() });
+    }
+
+    let has_key = |row: &Row, generated_row: &[CqlValue]| {
+        row.columns.len() >= key_len
+            && row.columns[..key_len]
+                .iter()
+                .zip(&generated_row[..key_len])
+                .all(|(result, expected)| result.as_ref() == Some(expected))
+    };
+
+    let mut matched = Vec::with_capacity(generated_rows.len());
+    let mut missing_positions = Vec::new();
+    for (position, generated_row) in generated_rows.iter().enumerate() {
+        match rows.iter().find(|row| has_key(row, generated_row)) {
+            Some(row) => matched.push((row, generated_row)),
+            None => missing_positions.push(position),
+        }
+    }
+
+    if !missing_positions.is_empty() {
+        return Err(ValidationError::ShortRead {
+            expected: generated_rows.len(),
+            received: rows.len(),
+            missing_positions,
+        });
+    }
+
+    for (row, generated_row) in matched {
+        compare_row(row, generated_row)?;
+    }
+    Ok(())
+}
+
+fn compare_row(row: &Row, generated_row: &[CqlValue]) -> Result<(), ValidationError> {
+    if row.columns.len() != generated_row.len() {
+        return Err(ValidationError::ColumnCountMismatch {
+            expected: generated_row.len(),
+            actual: row.columns.len(),
+        });
+    }
+
+    let mismatch = row
+        .columns
+        .iter()
+        .zip(generated_row.iter())
+        .position(|(maybe_result, expected)| maybe_result.as_ref() != Some(expected));
+
+    match mismatch {
+        Some(column) => Err(ValidationError::ValueMismatch {
+            column,
+            expected: Box::new(generated_row[column].clone()),
+            actual: row.columns[column].clone().map(Box::new),
+        }),
+        None => Ok(()),
     }
 }
 
 pub trait RowValidator: Sync + Send + Default {
-    fn validate_row(&self, generated_row: &[CqlValue], query_result: QueryResult) -> Result<()>;
+    fn validate_row(
+        &self,
+        generated_row: &[CqlValue],
+        query_result: QueryResult,
+    ) -> Result<(), ValidationError>;
 }
 
 #[derive(Default)]
 pub struct EqualRowValidator;
 impl RowValidator for EqualRowValidator {
-    fn validate_row(&self, generated_row: &[CqlValue], query_result: QueryResult) -> Result<()> {
-        let first_row = extract_first_row_from_query_result(&query_result)?;
-
-        anyhow::ensure!(
-            first_row.columns.len() == generated_row.len(),
-            "Expected row's ({:?}) length: {}. Result row's ({:?}) length: {}",
-            generated_row,
-            generated_row.len(),
-            first_row.columns,
-            first_row.columns.len(),
-        );
-
-        let result =
-            first_row
-                .columns
-                .iter()
-                .zip(generated_row.iter())
-                .all(|(maybe_result, expected)| match maybe_result {
-                    Some(result) => result == expected,
-                    // TODO: For now, we don't permit NULLs.
-                    None => false,
-                });
-
-        anyhow::ensure!(
-            result,
-            "The data doesn't match. Result: {:?}. Expected: {:?}.",
-            first_row.columns,
-            generated_row,
-        );
-        Ok(())
+    fn validate_row(
+        &self,
+        generated_row: &[CqlValue],
+        query_result: QueryResult,
+    ) -> Result<(), ValidationError> {
+        validate_row(generated_row, query_result.rows.as_deref())
     }
 }
 
 #[derive(Default)]
 pub struct ExistsRowValidator;
 impl RowValidator for ExistsRowValidator {
-    fn validate_row(&self, _generated_row: &[CqlValue], query_result: QueryResult) -> Result<()> {
+    fn validate_row(
+        &self,
+        _generated_row: &[CqlValue],
+        query_result: QueryResult,
+    ) -> Result<(), ValidationError> {
         // We only check that the row with given PK exists, which is equivalent to
         // successfully extracting the first row from the query result.
         let _first_row = extract_first_row_from_query_result(&query_result)?;
@@ -375,3 +527,79 @@ impl<T> OperationSampler<T> {
         &self.items[self.current_item_index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use scylla::frame::response::result::{CqlValue, Row};
+
+    use super::{validate_row, validate_rows, ValidationError};
+
+    fn rows(rows: Vec<Vec<Option<CqlValue>>>) -> Option<Vec<Row>> {
+        Some(rows.into_iter().map(|columns| Row { columns }).collect())
+    }
+
+    #[test]
+    fn validate_row_test() {
+        let generated = vec![CqlValue::Blob(vec![1]), CqlValue::Blob(vec![2, 3])];
+        let matching = || generated.iter().cloned().map(Some).collect::<Vec<_>>();
+
+        assert_eq!(
+            Ok(()),
+            validate_row(&generated, rows(vec![matching()]).as_deref())
+        );
+        assert_eq!(
+            Err(ValidationError::MissingRow),
+            validate_row(&generated, rows(vec![]).as_deref())
+        );
+        assert_eq!(
+            Err(ValidationError::MissingRow),
+            validate_row(&generated, None)
+        );
+        assert_eq!(
+            Err(ValidationError::MultipleRows { count: 2 }),
+            validate_row(&generated, rows(vec![matching(), matching()]).as_deref())
+        );
+        assert_eq!(
+            Err(ValidationError::ColumnCountMismatch {
+                expected: 2,
+                actual: 1
+            }),
+            validate_row(
+                &generated,
+                rows(vec![vec![Some(CqlValue::Blob(vec![1]))]]).as_deref()
+            )
+        );
+        assert_eq!(
+            Err(ValidationError::ValueMismatch {
+                column: 1,
+                expected: Box::new(CqlValue::Blob(vec![2, 3])),
+                actual: None,
+            }),
+            validate_row(
+                &generated,
+                rows(vec![vec![Some(CqlValue::Blob(vec![1])), None]]).as_deref()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_rows_short_read_test() {
+        let row = |ck: i64| vec![CqlValue::Blob(vec![1]), CqlValue::BigInt(ck)];
+        let generated = (0..4).map(row).collect::<Vec<_>>();
+        let generated_refs = generated.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let result_row = |ck: i64| row(ck).into_iter().map(Some).collect::<Vec<_>>();
+
+        let full = rows((0..4).map(result_row).collect());
+        assert_eq!(Ok(()), validate_rows(&generated_refs, 2, full.as_deref()));
+
+        let short = rows(vec![result_row(0), result_row(2)]);
+        assert_eq!(
+            Err(ValidationError::ShortRead {
+                expected: 4,
+                received: 2,
+                missing_positions: vec![1, 3],
+            }),
+            validate_rows(&generated_refs, 2, short.as_deref())
+        );
+    }
+}
@@ -1,24 +1,34 @@
 use std::{marker::PhantomData, ops::ControlFlow, sync::Arc};
 
 use anyhow::{Context, Result};
+use futures::future;
+use rand::Rng;
 use scylla::{frame::response::result::CqlValue, prepared_statement::PreparedStatement, Session};
 
 use crate::settings::CassandraStressSettings;
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
-    EqualRowValidator, ExistsRowValidator, RowValidator,
+    print_prepared_statement, row_generator::RowGenerator, CassandraStressOperation,
+    CassandraStressOperationFactory, EqualRowValidator, ExistsRowValidator, RowValidator,
 };
 
 pub struct ReadOperation<V: RowValidator> {
     session: Arc<Session>,
     statement: PreparedStatement,
     row_validator: V,
+    row_length: usize,
+    denormalize_factor: u64,
+    validate_sample: f64,
+    allow_missing_rows: bool,
 }
 
 pub struct GenericReadOperationFactory<V: RowValidator> {
     session: Arc<Session>,
     statement: PreparedStatement,
+    row_length: usize,
+    denormalize_factor: u64,
+    validate_sample: f64,
+    allow_missing_rows: bool,
     _phantom: PhantomData<V>,
 }
 
@@ -29,7 +39,7 @@ pub type CounterReadOperation = ReadOperation<ExistsRowValidator>;
 pub type CounterReadOperationFactory = GenericReadOperationFactory<ExistsRowValidator>;
 
 impl<V: RowValidator> ReadOperation<V> {
-    async fn do_execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
+    async fn do_execute(&self, row: &[CqlValue], fully_validate: bool) -> Result<ControlFlow<()>> {
         let pk = &row[0];
 
         // The tool works in a way, that it generates one row per partition.
@@ -43,8 +53,18 @@ impl<V: RowValidator> ReadOperation<V> {
                 "read error",
             );
         }
+        let result = result?;
 
-        let validation_result = self.row_validator.validate_row(row, result?);
+        // The row might have been deleted by a concurrent delete
+        if self.allow_missing_rows && !matches!(&result.rows, Some(rows) if !rows.is_empty()) {
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        let validation_result = if fully_validate {
+            self.row_validator.validate_row(row, result)
+        } else {
+            ExistsRowValidator.validate_row(row, result)
+        };
         if let Err(err) = validation_result.as_ref() {
             tracing::error!(
                 error = %err,
@@ -57,17 +77,37 @@ impl<V: RowValidator> ReadOperation<V> {
 
         Ok(ControlFlow::Continue(()))
     }
+
+    // Decides whether the content of the rows read by the operation should be
+    // validated, or only their presence should be checked. The decision is
+    // sampled for each operation, so that every key gets fully validated
+    // once in a while, no matter how often it's read.
+    fn should_fully_validate(&self) -> bool {
+        self.validate_sample >= 1.0 || rand::thread_rng().gen_bool(self.validate_sample)
+    }
 }
 
 impl<V: RowValidator> CassandraStressOperation for ReadOperation<V> {
     type Factory = GenericReadOperationFactory<V>;
 
-    async fn execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
-        self.do_execute(row).await
+    async fn execute(&self, rows: &[CqlValue]) -> Result<ControlFlow<()>> {
+        let fully_validate = self.should_fully_validate();
+        if self.denormalize_factor == 1 {
+            return self.do_execute(rows, fully_validate).await;
+        }
+
+        // With denormalization, all copies of the row are validated.
+        future::try_join_all(
+            rows.chunks(self.row_length)
+                .map(|row| self.do_execute(row, fully_validate)),
+        )
+        .await?;
+
+        Ok(ControlFlow::Continue(()))
     }
 
     fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
-        row_generator.generate_row()
+        row_generator.generate_denormalized_rows(self.denormalize_factor)
     }
 }
 
@@ -79,6 +119,10 @@ impl<V: RowValidator> CassandraStressOperationFactory for GenericReadOperationFa
             session: Arc::clone(&self.session),
             statement: self.statement.clone(),
             row_validator: Default::default(),
+            row_length: self.row_length,
+            denormalize_factor: self.denormalize_factor,
+            validate_sample: self.validate_sample,
+            allow_missing_rows: self.allow_missing_rows,
         }
     }
 }
@@ -88,12 +132,14 @@ impl<V: RowValidator> GenericReadOperationFactory<V> {
         settings: Arc<CassandraStressSettings>,
         session: Arc<Session>,
         stressed_table_name: &'static str,
+        operation_name: &'static str,
     ) -> Result<Self> {
         let statement_str = format!("SELECT * FROM {} WHERE KEY=?", stressed_table_name);
         let mut statement = session
             .prepare(statement_str)
             .await
             .context("Failed to prepare statement")?;
+        print_prepared_statement(operation_name, &statement);
 
         statement.set_is_idempotent(true);
         statement.set_consistency(settings.command_params.common.consistency_level);
@@ -104,7 +150,20 @@ impl<V: RowValidator> GenericReadOperationFactory<V> {
         Ok(Self {
             session,
             statement,
+            // +1 for partition_key.
+            row_length: settings.column.columns.len() + 1,
+            denormalize_factor: settings.population.denormalize_factor.get() as u64,
+            validate_sample: settings.command_params.common.validate_sample,
+            allow_missing_rows: false,
             _phantom: PhantomData,
         })
     }
+
+    /// Makes the reads accept the missing rows, which might have been deleted
+    /// by the delete suboperation of the mixed workload. The rows which are
+    /// found are still validated.
+    pub fn allow_missing_rows(mut self, allow: bool) -> Self {
+        self.allow_missing_rows = allow;
+        self
+    }
 }
@@ -5,7 +5,7 @@ use crate::{
         distribution::{fixed::FixedDistribution, Distribution},
         values::{Blob, Generator, GeneratorConfig, HexBlob},
     },
-    settings::CassandraStressSettings,
+    settings::{CassandraStressSettings, ColumnSeeding, OverwriteMode},
 };
 #[cfg(feature = "user-profile")]
 use std::collections::HashMap;
@@ -22,6 +22,19 @@ use super::recompute_seed;
 /// - compute the seed for the `column_generators` based on generated pk
 /// - generate the rest of the row (seeding the `column_generators` with computed seed)
 ///
+/// With `-pop overwrite=REVISION`, the number of times the `pk_seed_distribution` wrapped around
+/// is mixed into the seed of the `column_generators`, so that each overwrite of the partition
+/// changes the row. The first pass over the population generates exactly the same rows as usual.
+///
+/// By default (`-col seed=SHARED`) all of the `column_generators` are seeded with the same seed,
+/// and the values of the columns differ only thanks to the salt derived from the column name.
+/// With `-col seed=PER_COLUMN`, the index of the column is mixed into its seed as well, so the
+/// columns of a row are generated independently of each other. This doesn't change the size nor
+/// the compressibility of the individual cells, but the generated rows are different from the ones
+/// generated by the original c-s. Since the read workload validates the rows by generating them again,
+/// it has to be run with the same `-col seed=` as the write workload which inserted them.
+/// The same goes for `-col charset=`, which maps the generated bytes onto the chosen characters.
+///
 /// I think it's a great place to address how read and write workloads cooperate.
 /// For reference, see: https://github.com/scylladb/cql-stress/pull/43#discussion_r1304274035.
 ///
@@ -38,7 +51,7 @@ use super::recompute_seed;
 ///
 /// We call a distribution non-deterministic if the values it samples in each run may differ. It's the case
 /// for all of the distributions that depend on some RNG (which is by default seeded with current time in millis)
-/// e.g. UniformDistribution, GaussianDistribution (not yet implemented).
+/// e.g. UniformDistribution, NormalDistribution (GAUSSIAN).
 ///
 /// For example, each time we execute the command:
 /// ```
@@ -81,6 +94,8 @@ pub struct RowGenerator {
     pk_seed_distribution: Arc<dyn Distribution>,
     pk_generator: Generator,
     column_generators: Vec<Generator>,
+    overwrite_mode: OverwriteMode,
+    column_seeding: ColumnSeeding,
     // Map column name to the index of generated value in resulting vector.
     #[cfg(feature = "user-profile")]
     index_map: HashMap<String, usize>,
@@ -105,11 +120,23 @@ impl RowGenerator {
             pk_seed_distribution,
             pk_generator,
             column_generators,
+            overwrite_mode: OverwriteMode::Same,
+            column_seeding: ColumnSeeding::Shared,
             #[cfg(feature = "user-profile")]
             index_map,
         }
     }
 
+    pub fn with_overwrite_mode(mut self, overwrite_mode: OverwriteMode) -> Self {
+        self.overwrite_mode = overwrite_mode;
+        self
+    }
+
+    pub fn with_column_seeding(mut self, column_seeding: ColumnSeeding) -> Self {
+        self.column_seeding = column_seeding;
+        self
+    }
+
     /// Returns the index of corresponding column's value in generated rows.
     #[cfg(feature = "user-profile")]
     pub fn row_index_of_column_with_name(&self, name: &str) -> Option<usize> {
@@ -131,21 +158,52 @@ impl RowGenerator {
     }
 
     pub fn generate_row(&mut self) -> Vec<CqlValue> {
+        self.generate_denormalized_rows(1)
+    }
+
+    /// Generates `factor` rows which share the same column values,
+    /// but have distinct partition keys. The rows are concatenated
+    /// into a single vector.
+    ///
+    /// The partition key of the `i`-th row is generated from the seed
+    /// `pk_seed * factor + i`. The column values are generated from
+    /// the first partition key, so for `factor == 1` the result is exactly
+    /// the same as the row generated by `generate_row`.
+    pub fn generate_denormalized_rows(&mut self, factor: u64) -> Vec<CqlValue> {
         // +1 for partition_key.
         let row_length = self.column_generators.len() + 1;
-        let mut result = Vec::with_capacity(row_length);
+        let mut result = Vec::with_capacity(row_length * factor as usize);
+
+        let (pk_seed, revision) = match self.overwrite_mode {
+            OverwriteMode::Same => (self.pk_seed_distribution.next_i64(), 0),
+            OverwriteMode::Revision => self.pk_seed_distribution.next_i64_with_cycle(),
+        };
+        let mut generate_key = |i: u64| {
+            let seed = pk_seed.wrapping_mul(factor as i64).wrapping_add(i as i64);
+            self.pk_generator.set_seed(seed);
+            self.pk_generator.generate()
+        };
 
-        let key = self.generate_pk();
+        let key = generate_key(0);
 
         // Compute the seed used for generating the rest of the row.
-        let columns_seed = recompute_seed(0, &key);
+        let columns_seed = recompute_seed(revision, &key);
         result.push(key);
 
-        for column_generator in self.column_generators.iter_mut() {
-            column_generator.set_seed(columns_seed);
+        for (idx, column_generator) in self.column_generators.iter_mut().enumerate() {
+            let seed = match self.column_seeding {
+                ColumnSeeding::Shared => columns_seed,
+                ColumnSeeding::PerColumn => columns_seed.wrapping_mul(31).wrapping_add(idx as i64),
+            };
+            column_generator.set_seed(seed);
             result.push(column_generator.generate());
         }
 
+        for i in 1..factor {
+            result.push(generate_key(i));
+            result.extend_from_within(1..row_length);
+        }
+
         result
     }
 }
@@ -181,7 +239,10 @@ impl RowGeneratorFactory {
             .iter()
             .map(|column| {
                 Generator::new(
-                    Box::<Blob>::default(),
+                    Box::new(
+                        Blob::with_compressibility(self.settings.column.compressibility)
+                            .with_charset(self.settings.column.charset),
+                    ),
                     GeneratorConfig::new(
                         &format!("randomstr{}", column),
                         None,
@@ -197,5 +258,48 @@ impl RowGeneratorFactory {
             pk_generator,
             column_generators,
         )
+        .with_overwrite_mode(self.settings.population.overwrite_mode)
+        .with_column_seeding(self.settings.column.seeding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_row_generator(column_seeding: ColumnSeeding) -> RowGenerator {
+        let new_generator = |identity: &str, size: i64| {
+            Generator::new(
+                Box::new(Blob::default()),
+                GeneratorConfig::new(identity, None, Some(Box::new(FixedDistribution::new(size)))),
+                identity.to_owned(),
+            )
+        };
+        // Both columns use the same generator identity, so they are salted identically.
+        RowGenerator::new(
+            Arc::new(FixedDistribution::new(42)),
+            new_generator("randomstrkey", 10),
+            vec![
+                new_generator("randomstrC", 34),
+                new_generator("randomstrC", 34),
+            ],
+        )
+        .with_column_seeding(column_seeding)
+    }
+
+    #[test]
+    fn column_seeding_test() {
+        let shared = new_row_generator(ColumnSeeding::Shared).generate_row();
+        assert_eq!(shared[1], shared[2]);
+
+        let per_column = new_row_generator(ColumnSeeding::PerColumn).generate_row();
+        assert_eq!(shared[0], per_column[0]);
+        assert_ne!(per_column[1], per_column[2]);
+
+        // The rows are generated deterministically, so they can be validated.
+        assert_eq!(
+            per_column,
+            new_row_generator(ColumnSeeding::PerColumn).generate_row()
+        );
     }
 }
@@ -5,8 +5,10 @@ use cql_stress::{
     make_runnable,
 };
 use scylla::{
-    frame::response::result::CqlValue, prepared_statement::PreparedStatement,
-    transport::topology::Table, Session,
+    frame::response::result::CqlValue,
+    prepared_statement::PreparedStatement,
+    transport::topology::{CqlType, NativeType, Table},
+    Session,
 };
 
 use anyhow::{Context, Result};
@@ -14,7 +16,7 @@ use anyhow::{Context, Result};
 use crate::{
     java_generate::{
         distribution::{Distribution, DistributionFactory},
-        values::{Generator, GeneratorConfig, ValueGeneratorFactory},
+        values::{json::JsonFactory, Generator, GeneratorConfig, ValueGeneratorFactory},
     },
     settings::{CassandraStressSettings, OpWeight, PREDEFINED_INSERT_OPERATION},
     stats::ShardedStats,
@@ -105,7 +107,7 @@ impl UserOperation {
         let op_result = op.execute(row).await;
 
         self.stats
-            .get_shard_mut()
+            .get_shard_mut_for(ctx.warmup)
             .account_operation(ctx, &op_result);
 
         if op_result.is_ok() {
@@ -126,6 +128,8 @@ pub struct UserOperationFactory {
     queries_payload: HashMap<String, (PreparedStatement, OpWeight)>,
     pk_generator_factory: Box<dyn ValueGeneratorFactory>,
     column_generator_factories: Vec<Box<dyn ValueGeneratorFactory>>,
+    // Size distributions overridden for the columns, in the same order as the generator factories.
+    column_size_distributions: Vec<Option<Arc<dyn DistributionFactory>>>,
     max_operations: Option<u64>,
     clustering: Arc<dyn DistributionFactory>,
 }
@@ -237,14 +241,42 @@ impl UserOperationFactory {
                 })?
                 .type_,
         )?;
+        for col_name in user_profile.json_columns.keys() {
+            let col_def = table_metadata.columns.get(col_name).ok_or_else(|| {
+                anyhow::anyhow!("Cannot find JSON column {} in the table.", col_name)
+            })?;
+            anyhow::ensure!(
+                matches!(col_def.type_, CqlType::Native(NativeType::Text)),
+                "JSON column {} must be of text type",
+                col_name
+            );
+        }
         let column_generator_factories = table_metadata
             .columns
             .iter()
             .filter(|&(col_name, _col_def)| (*col_name != *pk_name))
-            .map(|(_col_name, col_def)| {
-                Generator::new_generator_factory_from_cql_type(&col_def.type_)
-            })
+            .map(
+                |(col_name, col_def)| -> Result<Box<dyn ValueGeneratorFactory>> {
+                    match user_profile.json_columns.get(col_name) {
+                        Some(spec) => Ok(Box::new(JsonFactory {
+                            max_depth: spec.max_depth,
+                        })),
+                        None => Generator::new_generator_factory_from_cql_type(&col_def.type_),
+                    }
+                },
+            )
             .collect::<Result<Vec<_>, _>>()?;
+        let column_size_distributions = table_metadata
+            .columns
+            .keys()
+            .filter(|col_name| **col_name != *pk_name)
+            .map(|col_name| {
+                user_profile
+                    .json_columns
+                    .get(col_name)
+                    .and_then(|spec| spec.size.clone())
+            })
+            .collect();
 
         Ok(Self {
             session,
@@ -255,6 +287,7 @@ impl UserOperationFactory {
             max_operations,
             pk_generator_factory,
             column_generator_factories,
+            column_size_distributions,
             clustering: user_profile.clustering.clone(),
         })
     }
@@ -273,10 +306,15 @@ impl UserOperationFactory {
             .iter()
             .filter(|(col_name, _col_def)| **col_name != *pk_name)
             .zip(self.column_generator_factories.iter())
-            .map(|((col_name, _), gen_factory)| {
+            .zip(self.column_size_distributions.iter())
+            .map(|(((col_name, _), gen_factory), size_distribution)| {
                 Generator::new(
                     gen_factory.create(),
-                    GeneratorConfig::new(&format!("{}{}", SEED_STR, col_name), None, None),
+                    GeneratorConfig::new(
+                        &format!("{}{}", SEED_STR, col_name),
+                        None,
+                        size_distribution.as_ref().map(|d| d.create()),
+                    ),
                     col_name.to_owned(),
                 )
             })
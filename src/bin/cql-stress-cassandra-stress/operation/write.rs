@@ -1,28 +1,71 @@
 use std::{ops::ControlFlow, sync::Arc};
 
 use anyhow::{Context, Result};
-use scylla::{frame::response::result::CqlValue, prepared_statement::PreparedStatement, Session};
+use futures::future;
+use scylla::{
+    batch::{Batch, BatchType},
+    frame::response::result::CqlValue,
+    prepared_statement::PreparedStatement,
+    Session,
+};
 
 use crate::settings::CassandraStressSettings;
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
+    print_prepared_statement, row_generator::RowGenerator, CassandraStressOperation,
+    CassandraStressOperationFactory,
 };
 
 pub struct WriteOperation {
     session: Arc<Session>,
     statement: PreparedStatement,
+    batch: Option<Batch>,
+    row_length: usize,
+    denormalize_factor: u64,
+    rows_per_request: u64,
 }
 
 pub struct WriteOperationFactory {
     session: Arc<Session>,
     statement: PreparedStatement,
+    // Used only when more than one row is written per request.
+    batch: Option<Batch>,
+    row_length: usize,
+    denormalize_factor: u64,
+    rows_per_request: u64,
 }
 
 impl CassandraStressOperation for WriteOperation {
     type Factory = WriteOperationFactory;
 
-    async fn execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
+    async fn execute(&self, rows: &[CqlValue]) -> Result<ControlFlow<()>> {
+        if let Some(batch) = &self.batch {
+            self.write_batch(batch, rows).await?;
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        if self.denormalize_factor == 1 {
+            self.write_row(rows).await?;
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        // With denormalization, the same row is written under multiple keys.
+        future::try_join_all(rows.chunks(self.row_length).map(|row| self.write_row(row))).await?;
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
+        // Each of the rows samples its own partition key seed
+        // from the shared distribution.
+        (0..self.rows_per_request)
+            .flat_map(|_| row_generator.generate_denormalized_rows(self.denormalize_factor))
+            .collect()
+    }
+}
+
+impl WriteOperation {
+    async fn write_row(&self, row: &[CqlValue]) -> Result<()> {
         // execute_unpaged, since it's an INSERT statement.
         let result = self.session.execute_unpaged(&self.statement, &row).await;
 
@@ -35,12 +78,23 @@ impl CassandraStressOperation for WriteOperation {
         }
 
         result?;
-
-        Ok(ControlFlow::Continue(()))
+        Ok(())
     }
 
-    fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
-        row_generator.generate_row()
+    async fn write_batch(&self, batch: &Batch, rows: &[CqlValue]) -> Result<()> {
+        let values = rows.chunks(self.row_length).collect::<Vec<_>>();
+        let result = self.session.batch(batch, values).await;
+
+        if let Err(err) = result.as_ref() {
+            tracing::error!(
+                error = %err,
+                first_partition_key = ?rows[0],
+                "batch write error",
+            );
+        }
+
+        result?;
+        Ok(())
     }
 }
 
@@ -51,6 +105,10 @@ impl CassandraStressOperationFactory for WriteOperationFactory {
         WriteOperation {
             session: Arc::clone(&self.session),
             statement: self.statement.clone(),
+            batch: self.batch.clone(),
+            row_length: self.row_length,
+            denormalize_factor: self.denormalize_factor,
+            rows_per_request: self.rows_per_request,
         }
     }
 }
@@ -74,6 +132,7 @@ impl WriteOperationFactory {
             .prepare(statement_str)
             .await
             .context("Failed to prepare statement")?;
+        print_prepared_statement("write", &statement);
 
         statement.set_is_idempotent(true);
         statement.set_consistency(settings.command_params.common.consistency_level);
@@ -81,6 +140,30 @@ impl WriteOperationFactory {
             settings.command_params.common.serial_consistency_level,
         ));
 
-        Ok(Self { session, statement })
+        let denormalize_factor = settings.population.denormalize_factor.get() as u64;
+        let rows_per_request = settings.command_params.rows_per_request();
+        let batch = (rows_per_request > 1).then(|| {
+            // One statement per row, including the denormalized copies.
+            let mut batch = Batch::new(BatchType::Unlogged);
+            for _ in 0..rows_per_request * denormalize_factor {
+                batch.append_statement(statement.clone());
+            }
+            batch.set_is_idempotent(true);
+            batch.set_consistency(settings.command_params.common.consistency_level);
+            batch.set_serial_consistency(Some(
+                settings.command_params.common.serial_consistency_level,
+            ));
+            batch
+        });
+
+        Ok(Self {
+            session,
+            statement,
+            batch,
+            // +1 for partition_key.
+            row_length: settings.column.columns.len() + 1,
+            denormalize_factor,
+            rows_per_request,
+        })
     }
 }
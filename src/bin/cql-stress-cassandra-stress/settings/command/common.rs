@@ -1,6 +1,6 @@
 use crate::settings::{
     param::{
-        types::{Count, Parsable, UnitInterval},
+        types::{Count, Parsable, Probability, UnitInterval},
         ParamHandle, ParamsParser, SimpleParamHandle,
     },
     ParsePayload,
@@ -193,7 +193,9 @@ pub struct CommonParams {
     pub serial_consistency_level: SerialConsistency,
     pub operation_count: Option<u64>,
     pub duration: Option<Duration>,
+    pub warmup: Option<Duration>,
     pub keysize: NonZeroU32,
+    pub validate_sample: f64,
 }
 
 impl CommonParams {
@@ -208,6 +210,9 @@ impl CommonParams {
         if let Some(duration) = self.duration {
             println!("  Duration: {} SECONDS", duration.as_secs());
         }
+        if let Some(warmup) = self.warmup {
+            println!("  Warmup: {} SECONDS", warmup.as_secs());
+        }
         println!("  No Warmup: {}", self.no_warmup);
         println!("  Consistency Level: {}", self.consistency_level);
         println!(
@@ -221,6 +226,7 @@ impl CommonParams {
             self.uncertainty.as_ref().unwrap().print_settings();
         }
         println!("  Key Size (bytes): {}", self.keysize);
+        println!("  Validate Sample: {}", self.validate_sample);
     }
 }
 
@@ -234,7 +240,10 @@ pub struct CommonParamHandles {
     serial_cl: SimpleParamHandle<SerialConsistencyLevel>,
     n: SimpleParamHandle<Count>,
     duration: SimpleParamHandle<Duration>,
+    warmup: SimpleParamHandle<Duration>,
     keysize: SimpleParamHandle<NonZeroU32>,
+    // Registered only for the commands which read data
+    validate_sample: Option<SimpleParamHandle<Probability>>,
 }
 
 pub fn add_common_param_groups(
@@ -279,6 +288,13 @@ pub fn add_common_param_groups(
         "Time to run in (in seconds, minutes or hours)",
         true,
     );
+    // Not present in the original cassandra-stress.
+    let warmup = parser.simple_param(
+        "warmup=",
+        None,
+        "Time to run before the measured duration, whose operations are excluded from the results",
+        false,
+    );
     let keysize = parser.simple_param("keysize=", Some("10"), "Key size in bytes", false);
 
     // $ ./cassandra-stress help read
@@ -287,7 +303,7 @@ pub fn add_common_param_groups(
     //  OR
     // Usage: read n=? [no-warmup] [truncate=?] [cl=?] [serial-cl=?] [keysize=?]
     //  OR
-    // Usage: read duration=? [no-warmup] [truncate=?] [cl=?] [serial-cl=?] [keysize=?]
+    // Usage: read duration=? [warmup=?] [no-warmup] [truncate=?] [cl=?] [serial-cl=?] [keysize=?]
 
     let groups: Vec<Vec<Box<dyn ParamHandle>>> = vec![
         vec![
@@ -310,6 +326,7 @@ pub fn add_common_param_groups(
         ],
         vec![
             Box::new(duration.clone()),
+            Box::new(warmup.clone()),
             Box::new(no_warmup.clone()),
             Box::new(truncate.clone()),
             Box::new(cl.clone()),
@@ -330,15 +347,39 @@ pub fn add_common_param_groups(
             serial_cl,
             n,
             duration,
+            warmup,
             keysize,
+            validate_sample: None,
         },
     )
 }
 
+/// Adds the parameters which are relevant only for the commands reading data
+/// to each of the groups of the common parameters.
+pub fn add_read_params(
+    parser: &mut ParamsParser,
+    groups: &mut [Vec<Box<dyn ParamHandle>>],
+    handles: &mut CommonParamHandles,
+) {
+    // Not present in the original cassandra-stress.
+    let validate_sample = parser.simple_param(
+        "validate-sample=",
+        Some("1"),
+        "Fraction of the reads whose rows are fully validated. The remaining reads only check that the rows exist",
+        false,
+    );
+    for group in groups.iter_mut() {
+        group.push(Box::new(validate_sample.clone()));
+    }
+    handles.validate_sample = Some(validate_sample);
+}
+
 fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles) {
     let mut parser = ParamsParser::new(cmd);
 
-    let (groups, handles) = add_common_param_groups(&mut parser);
+    // Used by the read and counter_read commands
+    let (mut groups, mut handles) = add_common_param_groups(&mut parser);
+    add_read_params(&mut parser, &mut groups, &mut handles);
 
     for group in groups.iter() {
         parser.group(&group.iter().map(|e| e.as_ref()).collect::<Vec<_>>())
@@ -357,7 +398,11 @@ pub fn parse_with_handles(handles: CommonParamHandles) -> CommonParams {
     let serial_consistency_level = handles.serial_cl.get().unwrap();
     let operation_count = handles.n.get();
     let duration = handles.duration.get();
+    let warmup = handles.warmup.get();
     let keysize = handles.keysize.get().unwrap();
+    let validate_sample = handles
+        .validate_sample
+        .map_or(1.0, |validate_sample| validate_sample.get().unwrap());
 
     let uncertainty = match (err, ngt, nlt) {
         (Some(err), Some(ngt), Some(nlt)) => Some(Uncertainty::new(err, ngt, nlt)),
@@ -373,7 +418,9 @@ pub fn parse_with_handles(handles: CommonParamHandles) -> CommonParams {
         serial_consistency_level,
         operation_count,
         duration,
+        warmup,
         keysize,
+        validate_sample,
     }
 }
 
@@ -385,6 +432,7 @@ pub fn parse_common_params(cmd: &Command, payload: &mut ParsePayload) -> Result<
         common: parse_with_handles(handles),
         counter: None,
         mixed: None,
+        write: None,
         #[cfg(feature = "user-profile")]
         user: None,
     })
@@ -397,7 +445,7 @@ pub fn print_help_common(command_str: &str) {
 
 #[cfg(test)]
 mod tests {
-    use std::num::NonZeroU32;
+    use std::{num::NonZeroU32, time::Duration};
 
     use scylla::statement::{Consistency, SerialConsistency};
 
@@ -425,6 +473,39 @@ mod tests {
         assert_eq!(Some(10_000_000), params.operation_count);
         assert_eq!(None, params.duration);
         assert_eq!(NonZeroU32::new(5).unwrap(), params.keysize);
+        assert_eq!(1.0, params.validate_sample);
+    }
+
+    #[test]
+    fn read_params_parser_with_validate_sample_test() {
+        let args = vec!["n=10m", "validate-sample=0.1"];
+        let (parser, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = parse_with_handles(handles);
+        assert_eq!(0.1, params.validate_sample);
+
+        let args = vec!["n=10m", "validate-sample=1.5"];
+        let (parser, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(args).is_err());
+    }
+
+    #[test]
+    fn read_params_parser_with_warmup_test() {
+        let args = vec!["duration=10m", "warmup=30s"];
+        let (parser, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = parse_with_handles(handles);
+        assert_eq!(Some(Duration::from_secs(600)), params.duration);
+        assert_eq!(Some(Duration::from_secs(30)), params.warmup);
+
+        // The warmup is supported only by the runs limited by duration.
+        let args = vec!["n=1000", "warmup=30s"];
+        let (parser, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(args).is_err());
     }
 
     #[test]
@@ -7,6 +7,7 @@ use super::ParsePayload;
 pub fn print_help() {
     println!("Usage:      cassandra-stress <command> [options]");
     println!("Help usage: cassandra-stress help <command|option>");
+    println!("Schema usage: cassandra-stress schema <command> [options]");
     println!();
     Command::print_generic_help();
     println!();
@@ -18,6 +18,7 @@ pub enum MixedSubcommand {
     Write,
     CounterRead,
     CounterWrite,
+    Delete,
 }
 
 impl std::fmt::Display for MixedSubcommand {
@@ -27,6 +28,7 @@ impl std::fmt::Display for MixedSubcommand {
             MixedSubcommand::Write => "write",
             MixedSubcommand::CounterRead => "counter_read",
             MixedSubcommand::CounterWrite => "counter_write",
+            MixedSubcommand::Delete => "delete",
         };
         write!(f, "{}", s)
     }
@@ -34,15 +36,21 @@ impl std::fmt::Display for MixedSubcommand {
 
 pub type OperationRatio = EnumeratedDistribution<MixedSubcommand>;
 
-// There are 4 suboperations which can be sampled during mixed workloads:
+// There are 5 suboperations which can be sampled during mixed workloads:
 // - read
 // - write
 // - counter_read
 // - counter_write
+// - delete
+//
+// Delete is not a standalone command - it removes the whole partition
+// of the generated key and is available only in mixed workloads.
+// The reads of a workload with deletes accept the missing rows,
+// but still validate the ones they find.
 //
 // A user can specify a ratio with which the suboperations will be sampled.
 // The syntax for this parameter is (op1=x, op2=y, op3=z, ...)
-// where op1..n are one of the 4 operations mentioned above, and x,y,z are floats.
+// where op1..n are one of the 5 operations mentioned above, and x,y,z are floats.
 //
 // For example:
 // ratio(read=1, write=2) means that there will be approximately 1 read operation per 2 write operations.
@@ -55,73 +63,194 @@ impl Parsable for OperationRatio {
 }
 
 impl OperationRatio {
-    fn parse_command_weight(s: &str) -> Result<(MixedSubcommand, f64)> {
-        let (cmd, weight) = {
-            let mut iter = s.split('=').fuse();
-            match (iter.next(), iter.next(), iter.next()) {
-                (Some(cmd), Some(w), None) => (cmd, w),
-                _ => anyhow::bail!(
-                    "Command weight specification should match pattern <command>=<f64>"
-                ),
-            }
-        };
+    fn do_parse(s: &str) -> Result<Self> {
+        Self::new(parse_subcommand_values(s)?)
+    }
+}
+
+// Parses the values specified for the suboperations,
+// with the syntax (op1=x, op2=y, ...) shared by ratio, concurrency and throttle.
+fn parse_subcommand_values(s: &str) -> Result<Vec<(MixedSubcommand, f64)>> {
+    // Remove wrapping parenthesis.
+    let arg = {
+        let mut chars = s.chars();
+        anyhow::ensure!(
+            chars.next() == Some('(') && chars.next_back() == Some(')'),
+            "Invalid operation ratio specification: {}",
+            s
+        );
+        chars.as_str()
+    };
+
+    let mut command_set = HashSet::<MixedSubcommand>::new();
+    arg.split(',')
+        .map(|s| -> Result<(MixedSubcommand, f64)> {
+            let (command, weight) = parse_command_weight(s)?;
+            anyhow::ensure!(
+                !command_set.contains(&command),
+                "{} command has been specified more than once",
+                command
+            );
+            command_set.insert(command);
+            Ok((command, weight))
+        })
+        .collect()
+}
 
-        let command = match Command::parse(cmd)? {
+fn parse_command_weight(s: &str) -> Result<(MixedSubcommand, f64)> {
+    let (cmd, weight) = {
+        let mut iter = s.split('=').fuse();
+        match (iter.next(), iter.next(), iter.next()) {
+            (Some(cmd), Some(w), None) => (cmd, w),
+            _ => anyhow::bail!("Command weight specification should match pattern <command>=<f64>"),
+        }
+    };
+
+    let command = if cmd.eq_ignore_ascii_case("delete") {
+        MixedSubcommand::Delete
+    } else {
+        match Command::parse(cmd)? {
             Command::Read => MixedSubcommand::Read,
             Command::Write => MixedSubcommand::Write,
             Command::CounterRead => MixedSubcommand::CounterRead,
             Command::CounterWrite => MixedSubcommand::CounterWrite,
             _ => anyhow::bail!("Invalid command for mixed workload: {}", cmd),
-        };
-        let weight = weight.parse::<f64>()?;
-        Ok((command, weight))
+        }
+    };
+    let weight = weight.parse::<f64>()?;
+    Ok((command, weight))
+}
+
+// Positive values specified separately for some of the suboperations,
+// e.g. concurrency(read=64,write=8).
+#[derive(Clone)]
+pub struct SubcommandValues(Vec<(MixedSubcommand, f64)>);
+
+impl Parsable for SubcommandValues {
+    type Parsed = Self;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let values = parse_subcommand_values(s)
+            .with_context(|| format!("invalid per-operation specification: {}", s))?;
+        anyhow::ensure!(
+            values.iter().all(|(_, v)| *v > 0.0),
+            "Per-operation values must be positive: {}",
+            s
+        );
+        Ok(Self(values))
     }
+}
 
-    fn do_parse(s: &str) -> Result<Self> {
-        // Remove wrapping parenthesis.
-        let arg = {
-            let mut chars = s.chars();
-            anyhow::ensure!(
-                chars.next() == Some('(') && chars.next_back() == Some(')'),
-                "Invalid operation ratio specification: {}",
-                s
-            );
-            chars.as_str()
-        };
+impl SubcommandValues {
+    fn get(&self, command: &MixedSubcommand) -> Option<f64> {
+        self.0
+            .iter()
+            .find(|(cmd, _)| cmd == command)
+            .map(|(_, value)| *value)
+    }
+}
 
-        let mut command_set = HashSet::<MixedSubcommand>::new();
-        let weights = arg
-            .split(',')
-            .map(|s| -> Result<(MixedSubcommand, f64)> {
-                let (command, weight) = Self::parse_command_weight(s)?;
-                anyhow::ensure!(
-                    !command_set.contains(&command),
-                    "{} command has been specified more than once",
-                    command
-                );
-                command_set.insert(command);
-                Ok((command, weight))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+/// A separate pool of workers performing only one of the suboperations,
+/// with its own concurrency and rate limit.
+pub struct SubcommandPool {
+    pub subcommand: MixedSubcommand,
+    pub concurrency: u64,
+    pub throttle: Option<f64>,
+}
 
-        Self::new(weights)
+impl std::fmt::Display for SubcommandPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} threads", self.subcommand, self.concurrency)?;
+        if let Some(throttle) = self.throttle {
+            write!(f, ", {}/s", throttle)?;
+        }
+        Ok(())
+    }
+}
+
+fn make_pools(
+    concurrency: Option<SubcommandValues>,
+    throttle: Option<SubcommandValues>,
+    operation_count: Option<u64>,
+) -> Result<Option<Vec<SubcommandPool>>> {
+    let Some(concurrency) = concurrency else {
+        anyhow::ensure!(
+            throttle.is_none(),
+            "throttle(...) requires concurrency(...) to be specified"
+        );
+        return Ok(None);
+    };
+    // Each pool counts its operations separately
+    anyhow::ensure!(
+        operation_count.is_none(),
+        "concurrency(...) cannot be used with n="
+    );
+    if let Some(throttle) = &throttle {
+        for (cmd, _) in &throttle.0 {
+            anyhow::ensure!(
+                concurrency.get(cmd).is_some(),
+                "throttle(...) specified for {}, which is missing from concurrency(...)",
+                cmd
+            );
+        }
     }
+    let pools = concurrency
+        .0
+        .iter()
+        .map(|&(subcommand, threads)| {
+            anyhow::ensure!(
+                threads.fract() == 0.0,
+                "Concurrency of {} must be an integer",
+                subcommand
+            );
+            Ok(SubcommandPool {
+                subcommand,
+                concurrency: threads as u64,
+                throttle: throttle.as_ref().and_then(|t| t.get(&subcommand)),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Some(pools))
 }
 
 pub struct MixedParamHandles {
     operation_ratio: SimpleParamHandle<OperationRatio>,
     clustering: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    concurrency: SimpleParamHandle<SubcommandValues>,
+    throttle: SimpleParamHandle<SubcommandValues>,
 }
 
 pub struct MixedParams {
     pub operation_ratio: OperationRatio,
     pub clustering: Box<dyn DistributionFactory>,
+    // If set, each of the suboperations is performed by its own pool
+    // of workers instead of sampling them by the ratio
+    pub pools: Option<Vec<SubcommandPool>>,
 }
 
 impl MixedParams {
+    /// Whether any of the suboperations deletes rows, which the reads
+    /// then can't expect to find.
+    pub fn deletes_rows(&self) -> bool {
+        match &self.pools {
+            Some(pools) => pools
+                .iter()
+                .any(|pool| pool.subcommand == MixedSubcommand::Delete),
+            None => self.operation_ratio.contains(&MixedSubcommand::Delete),
+        }
+    }
+
     pub fn print_settings(&self) {
-        println!("Command ratios: {}", self.operation_ratio);
-        println!("Command clustering distribution: {}", self.clustering);
+        match &self.pools {
+            Some(pools) => {
+                let pools = pools.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                println!("Command pools: {}", pools.join("; "));
+            }
+            None => {
+                println!("Command ratios: {}", self.operation_ratio);
+                println!("Command clustering distribution: {}", self.clustering);
+            }
+        }
     }
 
     pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
@@ -129,15 +258,23 @@ impl MixedParams {
         let (parser, common_handles, counter_add_distribution_handle, mixed_handles) =
             prepare_parser(cmd.show());
         parser.parse(args)?;
+        let common = super::common::parse_with_handles(common_handles);
+        let pools = make_pools(
+            mixed_handles.concurrency.get(),
+            mixed_handles.throttle.get(),
+            common.operation_count,
+        )?;
         Ok(CommandParams {
-            common: super::common::parse_with_handles(common_handles),
+            common,
             counter: Some(CounterParams {
                 add_distribution: counter_add_distribution_handle.get().unwrap(),
             }),
             mixed: Some(MixedParams {
                 operation_ratio: mixed_handles.operation_ratio.get().unwrap(),
                 clustering: mixed_handles.clustering.get().unwrap(),
+                pools,
             }),
+            write: None,
             #[cfg(feature = "user-profile")]
             user: None,
         })
@@ -155,18 +292,28 @@ fn prepare_parser(
     let mut parser = ParamsParser::new(cmd);
 
     let mut counter_payload = super::counter::add_counter_param_groups(&mut parser);
+    super::common::add_read_params(
+        &mut parser,
+        &mut counter_payload.groups,
+        &mut counter_payload.common_handles,
+    );
 
-    let operation_ratio = parser.simple_param("ratio", Some("(read=1,write=1)"), "Specify the ratios for operations to perform; e.g. ratio(read=2,write=1) will perform 2 reads for each write. Available commands are: read, write, counter_write, counter_read.", false);
+    let operation_ratio = parser.simple_param("ratio", Some("(read=1,write=1)"), "Specify the ratios for operations to perform; e.g. ratio(read=2,write=1) will perform 2 reads for each write. Available commands are: read, write, counter_write, counter_read, delete.", false);
     let clustering = parser.distribution_param(
         "clustering=",
         Some("GAUSSIAN(1..10)"),
         "Distribution clustering runs of operations of the same kind",
         false,
     );
+    // Not present in the original cassandra-stress.
+    let concurrency = parser.simple_param("concurrency", None, "Run each of the specified operations with its own pool of threads instead of sampling them by ratio; e.g. concurrency(read=64,write=8). Cannot be used with n=", false);
+    let throttle = parser.simple_param("throttle", None, "Throttle the pools specified by concurrency(...) separately, in operations per second; e.g. throttle(write=1000)", false);
 
     for group in counter_payload.groups.iter_mut() {
         group.push(Box::new(operation_ratio.clone()));
         group.push(Box::new(clustering.clone()));
+        group.push(Box::new(concurrency.clone()));
+        group.push(Box::new(throttle.clone()));
         parser.group_iter(group.iter().map(|e| e.as_ref()))
     }
 
@@ -177,6 +324,8 @@ fn prepare_parser(
         MixedParamHandles {
             operation_ratio,
             clustering,
+            concurrency,
+            throttle,
         },
     )
 }
@@ -15,6 +15,7 @@ mod help;
 mod mixed;
 #[cfg(feature = "user-profile")]
 mod user;
+mod write;
 
 use self::common::{parse_common_params, print_help_common};
 use self::counter::print_help_counter;
@@ -25,6 +26,8 @@ use self::mixed::MixedParams;
 use self::user::UserParams;
 #[cfg(feature = "user-profile")]
 pub use self::user::{OpWeight, PREDEFINED_INSERT_OPERATION};
+use self::write::print_help_write;
+pub use self::write::WriteParams;
 pub use help::print_help;
 
 use super::ParsePayload;
@@ -54,9 +57,8 @@ impl Command {
 
     fn parse_params(&self, payload: &mut ParsePayload) -> Result<Option<CommandParams>> {
         match self {
-            Command::Read | Command::Write | Command::CounterRead => {
-                Ok(Some(parse_common_params(self, payload)?))
-            }
+            Command::Read | Command::CounterRead => Ok(Some(parse_common_params(self, payload)?)),
+            Command::Write => Ok(Some(WriteParams::parse(self, payload)?)),
             Command::CounterWrite => Ok(Some(CounterParams::parse(self, payload)?)),
             Command::Mixed => Ok(Some(MixedParams::parse(self, payload)?)),
             #[cfg(feature = "user-profile")]
@@ -72,6 +74,11 @@ impl Command {
         self.as_ref()
     }
 
+    /// Whether the command reads data which has to be written beforehand.
+    pub fn reads_data(&self) -> bool {
+        matches!(self, Command::Read | Command::CounterRead | Command::Mixed)
+    }
+
     fn print_short_description(&self) {
         let desc = match self {
             Command::Read => "Multiple concurrent reads - the cluster must first be populated by a write test.",
@@ -96,7 +103,8 @@ impl Command {
 
     fn print_help(&self) {
         match self {
-            Command::Read | Command::Write | Command::CounterRead => print_help_common(self.show()),
+            Command::Read | Command::CounterRead => print_help_common(self.show()),
+            Command::Write => print_help_write(self.show()),
             Command::CounterWrite => print_help_counter(self.show()),
             Command::Mixed => print_help_mixed(self.show()),
             #[cfg(feature = "user-profile")]
@@ -111,11 +119,19 @@ pub struct CommandParams {
     pub common: CommonParams,
     pub counter: Option<CounterParams>,
     pub mixed: Option<MixedParams>,
+    pub write: Option<WriteParams>,
     #[cfg(feature = "user-profile")]
     pub user: Option<UserParams>,
 }
 
 impl CommandParams {
+    /// The number of rows written by a single write operation.
+    pub fn rows_per_request(&self) -> u64 {
+        self.write
+            .as_ref()
+            .map_or(1, |write| write.rows_per_request.get() as u64)
+    }
+
     pub fn print_settings(&self, cmd: &Command) {
         self.common.print_settings(cmd);
         if let Some(counter) = &self.counter {
@@ -124,6 +140,9 @@ impl CommandParams {
         if let Some(mixed) = &self.mixed {
             mixed.print_settings()
         }
+        if let Some(write) = &self.write {
+            write.print_settings()
+        }
     }
 }
 
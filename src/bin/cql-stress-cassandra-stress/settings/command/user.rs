@@ -10,7 +10,7 @@ use crate::java_generate::distribution::DistributionFactory;
 use crate::settings::param::types::RatioMap;
 use crate::settings::{
     param::{types::Parsable, ParamsParser, SimpleParamHandle},
-    ParsePayload,
+    ParsePayload, SchemaDdl,
 };
 
 use super::{
@@ -26,6 +26,42 @@ pub struct UserProfile {
     pub table: String,
     pub table_definition: Option<String>,
     pub queries: HashMap<String, QueryDefinitionYaml>,
+    // Text columns filled with generated JSON documents.
+    // This is a cql-stress extension.
+    #[serde(default)]
+    pub json_columns: HashMap<String, JsonColumnYaml>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct JsonColumnYaml {
+    pub max_depth: Option<u32>,
+    pub size: Option<String>,
+}
+
+const DEFAULT_JSON_MAX_DEPTH: u32 = 3;
+
+impl JsonColumnYaml {
+    fn into_json_column_spec(self) -> Result<JsonColumnSpec> {
+        let size = self
+            .size
+            .map(|s| Box::<dyn DistributionFactory>::parse(&s))
+            .transpose()?
+            .map(Arc::from);
+
+        Ok(JsonColumnSpec {
+            max_depth: self.max_depth.unwrap_or(DEFAULT_JSON_MAX_DEPTH),
+            size,
+        })
+    }
+}
+
+/// Describes how the JSON documents for a column are generated.
+pub struct JsonColumnSpec {
+    pub max_depth: u32,
+    // Distribution of the document length.
+    // If not provided, the generator's default size distribution is used.
+    pub size: Option<Arc<dyn DistributionFactory>>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -116,6 +152,7 @@ pub struct UserParams {
     pub queries_payload: HashMap<String, (QueryDefinition, OpWeight)>,
     pub clustering: Arc<dyn DistributionFactory>,
     pub insert_operation_weight: Option<OpWeight>,
+    pub json_columns: HashMap<String, JsonColumnSpec>,
 }
 
 impl UserParams {
@@ -127,6 +164,7 @@ impl UserParams {
             common: super::common::parse_with_handles(common_handles),
             counter: None,
             mixed: None,
+            write: None,
             user: Some(Self::parse_with_handles(user_handles)?),
         })
     }
@@ -136,23 +174,12 @@ impl UserParams {
         parser.print_help();
     }
 
-    pub async fn create_schema(&self, session: &Session) -> Result<()> {
-        if let Some(keyspace_definition) = &self.keyspace_definition {
-            session
-                .query_unpaged(keyspace_definition.as_str(), ())
-                .await
-                .context("Failed to create keyspace based on user profile")?;
+    pub fn schema_ddl(&self) -> SchemaDdl {
+        SchemaDdl {
+            keyspace: self.keyspace.clone(),
+            create_keyspace: self.keyspace_definition.clone(),
+            create_table: self.table_definition.clone(),
         }
-        session.use_keyspace(&self.keyspace, true).await?;
-
-        if let Some(table_definition) = &self.table_definition {
-            session
-                .query_unpaged(table_definition.as_str(), ())
-                .await
-                .context("Failed to create table based on user profile")?;
-        }
-
-        Ok(())
     }
 
     fn parse_with_handles(handles: UserParamHandles) -> Result<Self> {
@@ -163,6 +190,7 @@ impl UserParams {
             table,
             table_definition,
             mut queries,
+            json_columns,
         } = handles.profile.get().unwrap();
         let mut queries_ratio = handles.ratio.get().unwrap();
         let clustering: Arc<dyn DistributionFactory> = handles.clustering.get().unwrap().into();
@@ -188,6 +216,16 @@ impl UserParams {
             )
             .collect::<Result<HashMap<_, _>, _>>()?;
 
+        let json_columns = json_columns
+            .into_iter()
+            .map(|(column, spec)| -> Result<(String, JsonColumnSpec)> {
+                let spec = spec
+                    .into_json_column_spec()
+                    .with_context(|| format!("Invalid JSON column specification: {}", column))?;
+                Ok((column, spec))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
         Ok(Self {
             keyspace,
             keyspace_definition,
@@ -196,6 +234,7 @@ impl UserParams {
             queries_payload,
             clustering,
             insert_operation_weight,
+            json_columns,
         })
     }
 }
@@ -403,6 +442,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn json_columns_profile_yaml_test() {
+        let yaml_filepath = build_file_path("json_columns_profile.yaml");
+        let profile_arg = format!("profile={yaml_filepath}");
+        let args = vec![&profile_arg, "ops(insert=1)"];
+
+        let (parser, _common_handles, user_handles) = prepare_parser("user");
+        parser.parse(args).unwrap();
+
+        let user = UserParams::parse_with_handles(user_handles).unwrap();
+        assert_eq!(2, user.json_columns.len());
+
+        let doc = user.json_columns.get("doc").unwrap();
+        assert_eq!(5, doc.max_depth);
+        assert_eq!("FIXED(1024)", format!("{}", doc.size.as_ref().unwrap()));
+
+        let meta = user.json_columns.get("meta").unwrap();
+        assert_eq!(3, meta.max_depth);
+        assert!(meta.size.is_none());
+    }
+
     #[test]
     fn full_profile_yaml_unknown_query() {
         let yaml_filepath = build_file_path("full_profile.yaml");
@@ -0,0 +1,104 @@
+use std::num::NonZeroU32;
+
+use anyhow::Result;
+
+use crate::settings::{
+    param::{ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+use super::{common::CommonParamHandles, Command, CommandParams};
+
+pub struct WriteParams {
+    // Not present in the original cassandra-stress.
+    pub rows_per_request: NonZeroU32,
+}
+
+impl WriteParams {
+    pub fn print_settings(&self) {
+        println!("  Rows Per Request: {}", self.rows_per_request)
+    }
+
+    pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
+        let args = payload.remove(cmd.show()).unwrap();
+        let (parser, common_handles, rows_per_request) = prepare_parser(cmd.show());
+        parser.parse(args)?;
+        Ok(CommandParams {
+            common: super::common::parse_with_handles(common_handles),
+            counter: None,
+            mixed: None,
+            write: Some(WriteParams {
+                rows_per_request: rows_per_request.get().unwrap(),
+            }),
+            #[cfg(feature = "user-profile")]
+            user: None,
+        })
+    }
+}
+
+fn prepare_parser(
+    cmd: &str,
+) -> (
+    ParamsParser,
+    CommonParamHandles,
+    SimpleParamHandle<NonZeroU32>,
+) {
+    let mut parser = ParamsParser::new(cmd);
+
+    let (mut groups, common_handles) = super::common::add_common_param_groups(&mut parser);
+
+    let rows_per_request = parser.simple_param(
+        "rows-per-request=",
+        Some("1"),
+        "Number of rows written by a single operation. More than one row is written as an unlogged batch",
+        false,
+    );
+
+    for group in groups.iter_mut() {
+        group.push(Box::new(rows_per_request.clone()));
+        parser.group_iter(group.iter().map(|e| e.as_ref()))
+    }
+
+    (parser, common_handles, rows_per_request)
+}
+
+pub fn print_help_write(command_str: &str) {
+    let (parser, _, _) = prepare_parser(command_str);
+    parser.print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::command::common::parse_with_handles;
+
+    use super::prepare_parser;
+
+    #[test]
+    fn write_params_parser_with_rows_per_request_test() {
+        let args = vec!["n=1000", "rows-per-request=10"];
+        let (parser, common_handles, rows_per_request) = prepare_parser("write");
+
+        assert!(parser.parse(args).is_ok());
+
+        assert_eq!(10, rows_per_request.get().unwrap().get());
+        assert_eq!(
+            Some(1000),
+            parse_with_handles(common_handles).operation_count
+        );
+    }
+
+    #[test]
+    fn write_params_parser_default_rows_per_request_test() {
+        let (parser, _, rows_per_request) = prepare_parser("write");
+
+        assert!(parser.parse(vec!["n=1000"]).is_ok());
+        assert_eq!(1, rows_per_request.get().unwrap().get());
+    }
+
+    #[test]
+    fn write_params_parser_zero_rows_per_request_test() {
+        let (parser, _, _) = prepare_parser("write");
+
+        assert!(parser.parse(vec!["n=1000", "rows-per-request=0"]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::iter::Iterator;
 
 mod command;
@@ -17,6 +18,7 @@ pub use command::OperationRatio;
 #[cfg(feature = "user-profile")]
 pub use command::{OpWeight, PREDEFINED_INSERT_OPERATION};
 pub use option::ThreadsInfo;
+pub use option::{ColumnSeeding, DuplicateKeys, OverwriteMode};
 use regex::Regex;
 use scylla::Session;
 
@@ -24,6 +26,7 @@ use crate::settings::command::print_help;
 
 use self::command::parse_command;
 use self::option::ColumnOption;
+use self::option::EstimateOption;
 use self::option::ModeOption;
 use self::option::NodeOption;
 use self::option::PopulationOption;
@@ -39,6 +42,7 @@ pub struct CassandraStressSettings {
     pub schema: SchemaOption,
     pub column: ColumnOption,
     pub population: PopulationOption,
+    pub estimate: EstimateOption,
 }
 
 impl CassandraStressSettings {
@@ -54,52 +58,87 @@ impl CassandraStressSettings {
         println!();
     }
 
-    pub async fn create_schema(&self, session: &Session) -> Result<()> {
+    /// Returns the schema statements executed before the run.
+    /// It's a pure function of the settings, so it doesn't need a session.
+    pub fn schema_ddl(&self) -> SchemaDdl {
         #[cfg(feature = "user-profile")]
         if let Some(user) = &self.command_params.user {
-            return user.create_schema(session).await;
+            return user.schema_ddl();
         }
 
-        if matches!(self.command, Command::Write | Command::CounterWrite) {
+        let create_keyspace = matches!(self.command, Command::Write | Command::CounterWrite)
+            .then(|| self.schema.construct_keyspace_creation_query());
+        let create_table = match self.command {
+            Command::Write => Some(
+                self.schema
+                    .construct_table_creation_query(&self.column.columns),
+            ),
+            Command::CounterWrite => Some(
+                self.schema
+                    .construct_counter_table_creation_query(&self.column.columns),
+            ),
+            _ => None,
+        };
+
+        SchemaDdl {
+            keyspace: self.schema.keyspace.clone(),
+            create_keyspace,
+            create_table,
+        }
+    }
+
+    pub async fn create_schema(&self, session: &Session) -> Result<()> {
+        let ddl = self.schema_ddl();
+
+        if let Some(create_keyspace) = &ddl.create_keyspace {
             session
-                .query_unpaged(self.schema.construct_keyspace_creation_query(), ())
-                .await?;
+                .query_unpaged(create_keyspace.as_str(), ())
+                .await
+                .context("Failed to create keyspace")?;
         }
 
-        session.use_keyspace(&self.schema.keyspace, true).await?;
-
-        match self.command {
-            Command::Write => {
-                session
-                    .query_unpaged(
-                        self.schema
-                            .construct_table_creation_query(&self.column.columns),
-                        (),
-                    )
-                    .await
-                    .context("Failed to create standard table")?;
-            }
-            Command::CounterWrite => {
-                session
-                    .query_unpaged(
-                        self.schema
-                            .construct_counter_table_creation_query(&self.column.columns),
-                        (),
-                    )
-                    .await
-                    .context("Failed to create counter table")?;
-            }
-            _ => (),
+        session.use_keyspace(&ddl.keyspace, true).await?;
+
+        if let Some(create_table) = &ddl.create_table {
+            session
+                .query_unpaged(create_table.as_str(), ())
+                .await
+                .context("Failed to create table")?;
         }
 
         Ok(())
     }
 }
 
+/// The statements creating the schema used by the run: the keyspace
+/// (only by the commands which write data), and the table in it.
+pub struct SchemaDdl {
+    pub keyspace: String,
+    pub create_keyspace: Option<String>,
+    pub create_table: Option<String>,
+}
+
+impl Display for SchemaDdl {
+    /// Prints the statements as a CQL script, with the USE statement
+    /// corresponding to the keyspace switch done by the session.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(create_keyspace) = &self.create_keyspace {
+            writeln!(f, "{}", create_keyspace)?;
+        }
+        writeln!(f, "USE \"{}\";", self.keyspace)?;
+        if let Some(create_table) = &self.create_table {
+            writeln!(f, "{}", create_table)?;
+        }
+        Ok(())
+    }
+}
+
 pub enum CassandraStressParsingResult {
     // HELP, PRINT, VERSION
     SpecialCommand,
     Workload(Box<CassandraStressSettings>),
+    // `schema <command> [options]` - print the schema statements of the command
+    Schema(Box<CassandraStressSettings>),
 }
 
 type ParsePayload<'a> = HashMap<String, Vec<&'a str>>;
@@ -199,8 +238,19 @@ where
     let args: Vec<S> = args.collect();
     let args: Vec<String> = repair_params(args.iter());
 
+    // `schema` is followed by a regular command, whose schema is printed
+    // instead of running it.
+    let print_schema = args
+        .first()
+        .is_some_and(|arg| arg.eq_ignore_ascii_case("schema"));
+    let args = if print_schema { &args[1..] } else { &args[..] };
+
     let result = || {
-        let (cmd, mut payload) = prepare_parse_payload(&args)?;
+        let (cmd, mut payload) = prepare_parse_payload(args)?;
+        anyhow::ensure!(
+            !print_schema || !cmd.eq_ignore_ascii_case("help"),
+            "schema must be followed by a command other than help",
+        );
 
         let (command, command_params) = match parse_command(cmd, &mut payload) {
             Ok((_, None)) => return Ok(CassandraStressParsingResult::SpecialCommand),
@@ -221,6 +271,39 @@ where
             .operation_count
             .map_or(String::from("1000000"), |op| format!("{op}"));
         let population = PopulationOption::parse(&mut payload, &operation_count)?;
+        anyhow::ensure!(
+            population.denormalize_factor.get() == 1
+                || matches!(command, Command::Write | Command::Read),
+            "-pop denormalize= is supported only by the write and read commands",
+        );
+        anyhow::ensure!(
+            population.overwrite_mode == OverwriteMode::Same || command == Command::Write,
+            "-pop overwrite=REVISION is supported only by the write command",
+        );
+        anyhow::ensure!(
+            population.duplicate_keys == DuplicateKeys::Ignore || command == Command::Write,
+            "-pop duplicates= is supported only by the write command",
+        );
+        // Runs limited by duration can't be checked upfront.
+        if let Some(operation_count) = command_params.common.operation_count {
+            let sampled_keys = operation_count.saturating_mul(command_params.rows_per_request());
+            population.check_duplicate_keys(sampled_keys)?;
+        }
+        // Reading from the implicit population silently mismatches the written
+        // data whenever the write used a different population.
+        if !population.explicit && command.reads_data() {
+            eprintln!(
+                "WARNING: -pop not specified, reading from the default population {}. \
+                Specify -pop explicitly if the data was written with a different one.",
+                population.pk_seed_distribution,
+            );
+        }
+
+        let estimate = EstimateOption::parse(&mut payload)?;
+        anyhow::ensure!(
+            !estimate.enabled || command == Command::Write,
+            "-estimate is supported only by the write command",
+        );
 
         // List the unknown options along with their parameters.
         let build_unknown_arguments_err_message = || -> String {
@@ -242,18 +325,22 @@ where
             build_unknown_arguments_err_message()
         );
 
-        Ok(CassandraStressParsingResult::Workload(Box::new(
-            CassandraStressSettings {
-                command,
-                command_params,
-                node,
-                rate,
-                mode,
-                schema,
-                column,
-                population,
-            },
-        )))
+        let settings = Box::new(CassandraStressSettings {
+            command,
+            command_params,
+            node,
+            rate,
+            mode,
+            schema,
+            column,
+            population,
+            estimate,
+        });
+        if print_schema {
+            Ok(CassandraStressParsingResult::Schema(settings))
+        } else {
+            Ok(CassandraStressParsingResult::Workload(settings))
+        }
     };
 
     match result() {
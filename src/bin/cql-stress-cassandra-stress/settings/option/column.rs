@@ -1,11 +1,15 @@
+use std::str::FromStr;
+
 use anyhow::{Context, Result};
 use cql_stress::distribution::{parse_description, SyntaxFlavor};
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
 
 use crate::{
-    java_generate::distribution::DistributionFactory,
+    java_generate::{distribution::DistributionFactory, values::Charset},
     settings::{
         param::{
-            types::{CommaDelimitedList, Parsable},
+            types::{CommaDelimitedList, Parsable, Ratio},
             ParamsParser, SimpleParamHandle,
         },
         ParsePayload,
@@ -15,6 +19,66 @@ use crate::{
 pub struct ColumnOption {
     pub columns: Vec<String>,
     pub size_distribution: Box<dyn DistributionFactory>,
+    pub compressibility: f64,
+    pub seeding: ColumnSeeding,
+    pub charset: Charset,
+}
+
+/// Defines how the seeds of the column generators within a row are derived
+/// from the seed computed for the row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[strum(ascii_case_insensitive)]
+pub enum ColumnSeeding {
+    /// All columns are seeded with the row seed and differ only by the salt
+    /// derived from the column name. Compatible with the original c-s.
+    Shared,
+    /// The index of the column is mixed into the row seed, so that the
+    /// columns are generated independently of each other.
+    PerColumn,
+}
+
+impl ColumnSeeding {
+    fn show(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+impl Parsable for ColumnSeeding {
+    type Parsed = ColumnSeeding;
+
+    fn parse(seeding: &str) -> Result<Self::Parsed> {
+        let create_err_msg = || {
+            let concat = Self::iter()
+                .map(|seeding| seeding.show().to_owned())
+                .collect::<Vec<String>>()
+                .join("|");
+
+            format!(
+                "Invalid column seeding: {}. Must be one of: {}",
+                seeding, concat
+            )
+        };
+
+        Self::from_str(seeding).with_context(create_err_msg)
+    }
+}
+
+impl Parsable for Charset {
+    type Parsed = Charset;
+
+    fn parse(charset: &str) -> Result<Self::Parsed> {
+        let create_err_msg = || {
+            let concat = Self::iter()
+                .map(|charset| charset.as_ref().to_owned())
+                .collect::<Vec<String>>()
+                .join("|");
+
+            format!("Invalid charset: {}. Must be one of: {}", charset, concat)
+        };
+
+        Self::from_str(charset).with_context(create_err_msg)
+    }
 }
 
 impl ColumnOption {
@@ -40,12 +104,18 @@ impl ColumnOption {
         println!("Column:");
         println!("  Column names: {:?}", self.columns);
         println!("  Size distribution: {}", self.size_distribution);
+        println!("  Compressibility: {}", self.compressibility);
+        println!("  Seeding: {}", self.seeding.show());
+        println!("  Charset: {}", self.charset.as_ref());
     }
 
     fn from_handles(handles: ColumnParamHandles) -> Self {
         let names = handles.names.get();
         let columns_count = handles.columns_count.get();
         let size_distribution = handles.size_distribution.get().unwrap();
+        let compressibility = handles.compressibility.get().unwrap();
+        let seeding = handles.seeding.get().unwrap();
+        let charset = handles.charset.get().unwrap();
 
         let columns = match names {
             Some(names) => names,
@@ -57,6 +127,9 @@ impl ColumnOption {
         Self {
             columns,
             size_distribution,
+            compressibility,
+            seeding,
+            charset,
         }
     }
 }
@@ -99,6 +172,9 @@ struct ColumnParamHandles {
     names: SimpleParamHandle<CommaDelimitedList>,
     columns_count: SimpleParamHandle<ColumnCount>,
     size_distribution: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    compressibility: SimpleParamHandle<Ratio>,
+    seeding: SimpleParamHandle<ColumnSeeding>,
+    charset: SimpleParamHandle<Charset>,
 }
 
 fn prepare_parser() -> (ParamsParser, ColumnParamHandles) {
@@ -108,13 +184,46 @@ fn prepare_parser() -> (ParamsParser, ColumnParamHandles) {
     let columns_count = parser.simple_param("n=", Some("5"), "Number of columns", false);
     let size_distribution =
         parser.distribution_param("size=", Some("fixed(34)"), "Cell size distribution", false);
+    let compressibility = parser.simple_param(
+        "compressibility=",
+        Some("1"),
+        "Approximate compression ratio of the generated cell values; 1 means incompressible",
+        false,
+    );
+    let seeding = parser.simple_param(
+        "seed=",
+        Some("SHARED"),
+        "Whether the columns share the seed of the row (SHARED) or mix in their index (PER_COLUMN)",
+        false,
+    );
+    let charset = parser.simple_param(
+        "charset=",
+        Some("BYTES"),
+        "Characters the cell values consist of: BYTES (full byte range), PRINTABLE (ASCII), \
+        ALPHANUMERIC or HEX",
+        false,
+    );
 
     // $ ./cassandra-stress help -col
     // Usage: -col [n=?] [size=DIST(?)]
     //  OR
     // Usage: -col names=? [size=DIST(?)]
-    parser.group(&[&names, &size_distribution]);
-    parser.group(&[&columns_count, &size_distribution]);
+    //
+    // compressibility=, seed= and charset= are cql-stress extensions.
+    parser.group(&[
+        &names,
+        &size_distribution,
+        &compressibility,
+        &seeding,
+        &charset,
+    ]);
+    parser.group(&[
+        &columns_count,
+        &size_distribution,
+        &compressibility,
+        &seeding,
+        &charset,
+    ]);
 
     (
         parser,
@@ -122,13 +231,16 @@ fn prepare_parser() -> (ParamsParser, ColumnParamHandles) {
             names,
             columns_count,
             size_distribution,
+            compressibility,
+            seeding,
+            charset,
         },
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ColumnOption;
+    use super::{Charset, ColumnOption, ColumnSeeding};
 
     use super::prepare_parser;
 
@@ -141,6 +253,75 @@ mod tests {
 
         let params = ColumnOption::from_handles(handles);
         assert_eq!(&["C0", "C1", "C2", "C3", "C4"], params.columns.as_slice());
+        // The defaults of cassandra-stress, keep them comparable with its results
+        assert_eq!("FIXED(34)", params.size_distribution.to_string());
+        assert_eq!(1.0, params.compressibility);
+        assert_eq!(ColumnSeeding::Shared, params.seeding);
+        assert_eq!(Charset::Bytes, params.charset);
+    }
+
+    #[test]
+    fn col_fixed_count_params_test() {
+        let args = vec!["n=FIXED(3)", "size=UNIFORM(50..200)"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(&["C0", "C1", "C2"], params.columns.as_slice());
+        assert_eq!("UNIFORM(50..200)", params.size_distribution.to_string());
+    }
+
+    #[test]
+    fn col_charset_params_test() {
+        let args = vec!["n=3", "charset=alphanumeric"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(Charset::Alphanumeric, params.charset);
+
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec!["charset=unicode"]).is_err());
+    }
+
+    #[test]
+    fn col_seeding_params_test() {
+        let args = vec!["names=foo,bar", "seed=per_column"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(ColumnSeeding::PerColumn, params.seeding);
+    }
+
+    #[test]
+    fn col_bad_seeding_params_test() {
+        let args = vec!["seed=random"];
+        let (parser, _) = prepare_parser();
+
+        assert!(parser.parse(args).is_err());
+    }
+
+    #[test]
+    fn col_compressibility_params_test() {
+        let args = vec!["n=3", "compressibility=2.5"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(2.5, params.compressibility);
+    }
+
+    #[test]
+    fn col_bad_compressibility_params_test() {
+        let args = vec!["compressibility=0.5"];
+        let (parser, _) = prepare_parser();
+
+        assert!(parser.parse(args).is_err());
     }
 
     #[test]
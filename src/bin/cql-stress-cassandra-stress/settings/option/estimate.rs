@@ -0,0 +1,39 @@
+use anyhow::Result;
+
+use crate::settings::ParsePayload;
+
+/// Not present in the original cassandra-stress.
+///
+/// When enabled, the tool prints the estimated volume of the data
+/// written by the workload and exits without running it.
+pub struct EstimateOption {
+    pub enabled: bool,
+}
+
+impl EstimateOption {
+    pub const CLI_STRING: &'static str = "-estimate";
+
+    pub fn description() -> &'static str {
+        "Print the estimated data volume of the write workload and exit"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        let enabled = match cl_args.remove(Self::CLI_STRING) {
+            Some(params) => {
+                anyhow::ensure!(
+                    params.is_empty(),
+                    "{} doesn't accept any parameters",
+                    Self::CLI_STRING,
+                );
+                true
+            }
+            None => false,
+        };
+        Ok(Self { enabled })
+    }
+
+    pub fn print_help() {
+        println!("Usage: {}", Self::CLI_STRING);
+        println!("  {}", Self::description());
+    }
+}
@@ -1,4 +1,5 @@
 mod column;
+mod estimate;
 mod mode;
 mod node;
 mod population;
@@ -7,10 +8,11 @@ mod schema;
 
 use anyhow::Result;
 
-pub use column::ColumnOption;
+pub use column::{ColumnOption, ColumnSeeding};
+pub use estimate::EstimateOption;
 pub use mode::ModeOption;
 pub use node::NodeOption;
-pub use population::PopulationOption;
+pub use population::{DuplicateKeys, OverwriteMode, PopulationOption};
 pub use rate::RateOption;
 pub use rate::ThreadsInfo;
 pub use schema::SchemaOption;
@@ -29,6 +31,7 @@ impl Options {
                 PopulationOption::CLI_STRING,
                 PopulationOption::description(),
             ),
+            (EstimateOption::CLI_STRING, EstimateOption::description()),
         ]
         .into_iter()
     }
@@ -48,6 +51,7 @@ impl Options {
             ColumnOption::CLI_STRING => ColumnOption::print_help(),
             PopulationOption::CLI_STRING => PopulationOption::print_help(),
             ModeOption::CLI_STRING => ModeOption::print_help(),
+            EstimateOption::CLI_STRING => EstimateOption::print_help(),
             _ => return Err(anyhow::anyhow!("Invalid option provided to command help")),
         }
 
@@ -59,6 +59,7 @@ impl NodeOption {
             // Note that it's never the case that both of them are Some.
             _ => read_nodes_from_file(&file.unwrap())?,
         };
+        anyhow::ensure!(!nodes.is_empty(), "no contact points provided");
 
         Ok(Self {
             nodes,
@@ -167,6 +168,21 @@ mod tests {
         assert_eq!(vec!["127.0.0.1", "localhost", "192.168.0.1"], params.nodes);
     }
 
+    #[test]
+    fn node_empty_file_test() {
+        let path = std::env::temp_dir().join("cql_stress_node_empty_file_test");
+        std::fs::write(&path, "\n\n").unwrap();
+        let file_arg = format!("file={}", path.display());
+        let args = vec![file_arg.as_str()];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let err = NodeOption::from_handles(handles).err().unwrap();
+        assert_eq!("no contact points provided", err.to_string());
+        std::fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn node_bad_params_test() {
         let args = vec!["whitelist", "127.0.0.1,localhost,192.168.0.1,"];
@@ -1,4 +1,8 @@
-use anyhow::Result;
+use std::{num::NonZeroU32, str::FromStr};
+
+use anyhow::{Context, Result};
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
 
 use crate::{
     java_generate::distribution::{sequence::SeqDistributionFactory, DistributionFactory},
@@ -13,6 +17,91 @@ use crate::{
 
 pub struct PopulationOption {
     pub pk_seed_distribution: Box<dyn DistributionFactory>,
+    pub denormalize_factor: NonZeroU32,
+    pub overwrite_mode: OverwriteMode,
+    pub duplicate_keys: DuplicateKeys,
+    /// Whether the population was given with -pop, as opposed to
+    /// the implicit default of seq=1..N.
+    pub explicit: bool,
+}
+
+/// Defines what values are written when the population wraps around
+/// and the same partition key is written again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[strum(ascii_case_insensitive)]
+pub enum OverwriteMode {
+    /// Overwrites use the same values - the writes are idempotent.
+    Same,
+    /// Each overwrite uses values derived from the number of times
+    /// the population wrapped around, so that every overwrite changes the row.
+    Revision,
+}
+
+impl OverwriteMode {
+    fn show(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+impl Parsable for OverwriteMode {
+    type Parsed = OverwriteMode;
+
+    fn parse(mode: &str) -> Result<Self::Parsed> {
+        let create_err_msg = || {
+            let concat = Self::iter()
+                .map(|mode| mode.show().to_owned())
+                .collect::<Vec<String>>()
+                .join("|");
+
+            format!(
+                "Invalid overwrite mode: {}. Must be one of: {}",
+                mode, concat
+            )
+        };
+
+        Self::from_str(mode).with_context(create_err_msg)
+    }
+}
+
+/// Defines how the runs whose writes are going to repeat
+/// some of the partition keys are handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[strum(ascii_case_insensitive)]
+pub enum DuplicateKeys {
+    /// The population isn't checked.
+    Ignore,
+    /// A warning is printed before the run.
+    Warn,
+    /// The run isn't started.
+    Error,
+}
+
+impl DuplicateKeys {
+    fn show(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+impl Parsable for DuplicateKeys {
+    type Parsed = DuplicateKeys;
+
+    fn parse(mode: &str) -> Result<Self::Parsed> {
+        let create_err_msg = || {
+            let concat = Self::iter()
+                .map(|mode| mode.show().to_owned())
+                .collect::<Vec<String>>()
+                .join("|");
+
+            format!(
+                "Invalid duplicate keys handling: {}. Must be one of: {}",
+                mode, concat
+            )
+        };
+
+        Self::from_str(mode).with_context(create_err_msg)
+    }
 }
 
 impl PopulationOption {
@@ -23,10 +112,11 @@ impl PopulationOption {
     }
 
     pub fn parse(cl_args: &mut ParsePayload, operation_count: &str) -> Result<Self> {
-        let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
+        let params = cl_args.remove(Self::CLI_STRING);
+        let explicit = params.is_some();
         let (parser, handles) = prepare_parser(operation_count);
-        parser.parse(params)?;
-        Ok(Self::from_handles(handles))
+        parser.parse(params.unwrap_or_default())?;
+        Ok(Self::from_handles(handles, explicit))
     }
 
     pub fn print_help() {
@@ -40,22 +130,83 @@ impl PopulationOption {
             "  Partition key seed distribution: {}",
             self.pk_seed_distribution
         );
+        println!("  Denormalize factor: {}", self.denormalize_factor);
+        println!("  Overwrite mode: {}", self.overwrite_mode.show());
+        println!("  Duplicate keys: {}", self.duplicate_keys.show());
     }
 
-    fn from_handles(handles: PopulationParamHandles) -> Self {
+    /// Checks whether `operation_count` operations sampling the population
+    /// are going to repeat some of the seeds, and so the partition keys,
+    /// and handles it according to `duplicates=`.
+    pub fn check_duplicate_keys(&self, operation_count: u64) -> Result<()> {
+        if self.duplicate_keys == DuplicateKeys::Ignore {
+            return Ok(());
+        }
+        let duplicates = expected_duplicates(self.pk_seed_distribution.as_ref(), operation_count);
+        if duplicates == 0 {
+            return Ok(());
+        }
+
+        let message = format!(
+            "{} of the {} operations are going to repeat a partition key of the population {}{}",
+            duplicates,
+            operation_count,
+            self.pk_seed_distribution,
+            if self.pk_seed_distribution.is_sequential() {
+                ""
+            } else {
+                " (estimated as if the seeds were sampled uniformly, so it's a lower bound)"
+            },
+        );
+        match self.duplicate_keys {
+            DuplicateKeys::Ignore => Ok(()),
+            DuplicateKeys::Warn => {
+                eprintln!("WARNING: {}", message);
+                Ok(())
+            }
+            DuplicateKeys::Error => Err(anyhow::anyhow!(message)),
+        }
+    }
+
+    fn from_handles(handles: PopulationParamHandles, explicit: bool) -> Self {
         let pk_seed_distribution = match handles.bash_friendly_seq_distribution.get() {
             Some(dist) => dist,
             None => handles.pk_seed_distribution.get().unwrap(),
         };
 
+        let denormalize_factor = handles.denormalize_factor.get().unwrap();
+        let overwrite_mode = handles.overwrite_mode.get().unwrap();
+        let duplicate_keys = handles.duplicate_keys.get().unwrap();
+
         Self {
             pk_seed_distribution,
+            denormalize_factor,
+            overwrite_mode,
+            duplicate_keys,
+            explicit,
         }
     }
 }
 
+/// Returns the number of the `operation_count` samples of the distribution
+/// which repeat a value sampled before. A sequence repeats the values only
+/// once it wraps around. For the other distributions, the number is estimated
+/// as if the values were sampled uniformly from the distribution's range.
+fn expected_duplicates(distribution: &dyn DistributionFactory, operation_count: u64) -> u64 {
+    let (min, max) = distribution.bounds();
+    let range = (max as i128 - min as i128 + 1) as f64;
+    let count = operation_count as f64;
+    let distinct = if distribution.is_sequential() {
+        count.min(range)
+    } else {
+        // The expected number of distinct values among `count` uniform samples
+        range * -(count * (-1.0 / range).ln_1p()).exp_m1()
+    };
+    (count - distinct).round().max(0.0) as u64
+}
+
 /// Cassandra-Stress supports bash-friendly syntax for SEQ distribution: -pop seq=1..10000
-/// This is equivalent to: -pop 'dist=SEQ(1..1000)'
+/// This is equivalent to: -pop 'dist=SEQ(1..10000)'
 struct BashFriendlySeqDistribution;
 impl Parsable for BashFriendlySeqDistribution {
     type Parsed = Box<dyn DistributionFactory>;
@@ -70,6 +221,9 @@ impl Parsable for BashFriendlySeqDistribution {
 struct PopulationParamHandles {
     pk_seed_distribution: SimpleParamHandle<Box<dyn DistributionFactory>>,
     bash_friendly_seq_distribution: SimpleParamHandle<BashFriendlySeqDistribution>,
+    denormalize_factor: SimpleParamHandle<NonZeroU32>,
+    overwrite_mode: SimpleParamHandle<OverwriteMode>,
+    duplicate_keys: SimpleParamHandle<DuplicateKeys>,
 }
 
 fn prepare_parser(operation_count: &str) -> (ParamsParser, PopulationParamHandles) {
@@ -83,25 +237,69 @@ fn prepare_parser(operation_count: &str) -> (ParamsParser, PopulationParamHandle
         false,
     );
 
+    // Not present in the original cassandra-stress.
+    let denormalize_factor = parser.simple_param(
+        "denormalize=",
+        Some("1"),
+        "Write each row under this many distinct partition keys derived from the sampled seed.",
+        false,
+    );
+
+    // Not present in the original cassandra-stress.
+    let overwrite_mode = parser.simple_param(
+        "overwrite=",
+        Some("SAME"),
+        "Values used when the population wraps around: SAME (idempotent) or REVISION (each overwrite changes the row).",
+        false,
+    );
+
+    // Not present in the original cassandra-stress.
+    let duplicate_keys = parser.simple_param(
+        "duplicates=",
+        Some("IGNORE"),
+        "Check before the write run whether its operations are going to repeat partition keys: IGNORE, WARN or ERROR.",
+        false,
+    );
+
     // $ ./cassandra-stress help -pop
-    // Usage: -pop [seq=?]
+    // Usage: -pop [seq=?] [denormalize=?] [overwrite=?] [duplicates=?]
     //   OR
-    // Usage: -pop [dist=DIST(?)]
-    parser.group(&[&bash_friendly_seq_distribution]);
-    parser.group(&[&pk_seed_distribution]);
+    // Usage: -pop [dist=DIST(?)] [denormalize=?] [overwrite=?] [duplicates=?]
+    parser.group(&[
+        &bash_friendly_seq_distribution,
+        &denormalize_factor,
+        &overwrite_mode,
+        &duplicate_keys,
+    ]);
+    parser.group(&[
+        &pk_seed_distribution,
+        &denormalize_factor,
+        &overwrite_mode,
+        &duplicate_keys,
+    ]);
 
     (
         parser,
         PopulationParamHandles {
             pk_seed_distribution,
             bash_friendly_seq_distribution,
+            denormalize_factor,
+            overwrite_mode,
+            duplicate_keys,
         },
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::prepare_parser;
+    use crate::java_generate::distribution::{
+        fixed::FixedDistributionFactory, sequence::SeqDistributionFactory,
+        uniform::UniformDistributionFactory,
+    };
+
+    use super::{
+        expected_duplicates, prepare_parser, DuplicateKeys, OverwriteMode, PopulationOption,
+    };
 
     #[test]
     fn pop_default_params_test() {
@@ -110,4 +308,79 @@ mod tests {
 
         assert!(parser.parse(args).is_ok());
     }
+
+    #[test]
+    fn pop_denormalize_param_test() {
+        let args = vec!["seq=1..100", "denormalize=3"];
+        let (parser, handles) = prepare_parser("100");
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(3, handles.denormalize_factor.get().unwrap().get());
+    }
+
+    #[test]
+    fn pop_overwrite_param_test() {
+        let args = vec!["seq=1..100", "overwrite=revision"];
+        let (parser, handles) = prepare_parser("100");
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(
+            OverwriteMode::Revision,
+            handles.overwrite_mode.get().unwrap()
+        );
+    }
+
+    #[test]
+    fn pop_seq_shorthand_test() {
+        let parse = |args| {
+            let (parser, handles) = prepare_parser("100");
+            assert!(parser.parse(args).is_ok());
+            PopulationOption::from_handles(handles, true).pk_seed_distribution
+        };
+        let shorthand = parse(vec!["seq=5..10"]);
+        let explicit = parse(vec!["dist=SEQ(5..10)"]);
+
+        assert_eq!(explicit.to_string(), shorthand.to_string());
+        let (shorthand, explicit) = (shorthand.create(), explicit.create());
+        for _ in 0..8 {
+            assert_eq!(explicit.next_i64(), shorthand.next_i64());
+        }
+    }
+
+    #[test]
+    fn pop_zero_denormalize_param_test() {
+        let args = vec!["dist=UNIFORM(1..100)", "denormalize=0"];
+        let (parser, _) = prepare_parser("100");
+
+        assert!(parser.parse(args).is_err());
+    }
+
+    #[test]
+    fn pop_expected_duplicates_test() {
+        let seq = SeqDistributionFactory::new(1, 100).unwrap();
+        assert_eq!(0, expected_duplicates(&seq, 100));
+        assert_eq!(50, expected_duplicates(&seq, 150));
+
+        let fixed = FixedDistributionFactory(7);
+        assert_eq!(0, expected_duplicates(&fixed, 1));
+        assert_eq!(99, expected_duplicates(&fixed, 100));
+
+        // 100 uniform samples out of 100 values give ~63.4 distinct ones
+        let uniform = UniformDistributionFactory::new(1.0, 100.0).unwrap();
+        assert_eq!(37, expected_duplicates(&uniform, 100));
+        let uniform = UniformDistributionFactory::new(1.0, 1e12).unwrap();
+        assert_eq!(0, expected_duplicates(&uniform, 1000));
+    }
+
+    #[test]
+    fn pop_duplicates_param_test() {
+        let args = vec!["seq=1..100", "duplicates=error"];
+        let (parser, handles) = prepare_parser("200");
+
+        assert!(parser.parse(args).is_ok());
+        let params = PopulationOption::from_handles(handles, true);
+        assert_eq!(DuplicateKeys::Error, params.duplicate_keys);
+        assert!(params.check_duplicate_keys(100).is_ok());
+        assert!(params.check_duplicate_keys(200).is_err());
+    }
 }
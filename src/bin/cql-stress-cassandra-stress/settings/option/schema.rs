@@ -2,7 +2,7 @@ use crate::settings::{
     param::{MultiParamAcceptsArbitraryHandle, ParamsParser, SimpleParamHandle},
     ParsePayload,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::collections::HashMap;
 
 pub struct SchemaOption {
@@ -72,15 +72,32 @@ impl SchemaOption {
         }
     }
 
-    fn construct_replication_string(&self) -> String {
-        let options_str = self
+    /// Returns the total number of replicas of each partition
+    /// across all datacenters.
+    pub fn total_replication_factor(&self) -> Result<u64> {
+        let parse = |key: &str, value: &str| {
+            value
+                .parse::<u64>()
+                .with_context(|| format!("Invalid replication option {key}: {value}"))
+        };
+        // NetworkTopologyStrategy specifies the replication factor per datacenter.
+        let per_dc = self
             .replication_opts
             .iter()
-            .map(|(key, value)| format!("'{}': '{}'", key, value))
-            .collect::<Vec<_>>()
-            .join(", ");
+            .filter(|(key, _)| !matches!(key.as_str(), "class" | "replication_factor"))
+            .map(|(key, value)| parse(key, value))
+            .collect::<Result<Vec<_>>>()?;
+        if !per_dc.is_empty() {
+            return Ok(per_dc.into_iter().sum());
+        }
+        parse(
+            "replication_factor",
+            &self.replication_opts["replication_factor"],
+        )
+    }
 
-        format!("{{{}}}", options_str)
+    fn construct_replication_string(&self) -> String {
+        format!("{{{}}}", construct_options_string(&self.replication_opts))
     }
 
     pub fn construct_keyspace_creation_query(&self) -> String {
@@ -93,14 +110,10 @@ impl SchemaOption {
 
     fn construct_compaction_string(&self) -> Option<String> {
         (!self.compaction_opts.is_empty()).then(|| {
-            let options_str = self
-                .compaction_opts
-                .iter()
-                .map(|(key, value)| format!("'{}': '{}'", key, value))
-                .collect::<Vec<_>>()
-                .join(", ");
-
-            format!(" AND compaction = {{{}}}", options_str)
+            format!(
+                " AND compaction = {{{}}}",
+                construct_options_string(&self.compaction_opts)
+            )
         })
     }
 
@@ -140,6 +153,17 @@ impl SchemaOption {
     }
 }
 
+// The options are sorted by their names, so that the generated statements
+// are the same in each run.
+fn construct_options_string(opts: &HashMap<String, String>) -> String {
+    let mut opts = opts.iter().collect::<Vec<_>>();
+    opts.sort();
+    opts.into_iter()
+        .map(|(key, value)| format!("'{}': '{}'", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 struct SchemaParamHandles {
     replication_factor: SimpleParamHandle<u64>,
     replication_strategy: SimpleParamHandle<String>,
@@ -92,6 +92,30 @@ impl Parsable for UnitInterval {
     }
 }
 
+/// A ratio expressed as a value not smaller than 1, e.g. 2.5 for 2.5:1.
+pub struct Ratio;
+impl Parsable for Ratio {
+    type Parsed = f64;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let ratio = f64::parse(s)?;
+        anyhow::ensure!(ratio >= 1.0, "Ratio must not be smaller than 1: {s}");
+        Ok(ratio)
+    }
+}
+
+/// A value from the closed interval [0, 1].
+pub struct Probability;
+impl Parsable for Probability {
+    type Parsed = f64;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        ensure_regex!(s, r"^(0(\.[0-9]+)?|1(\.0+)?)$");
+        s.parse::<f64>()
+            .with_context(|| format!("Invalid f64 argument: {s}"))
+    }
+}
+
 impl Parsable for bool {
     type Parsed = bool;
 
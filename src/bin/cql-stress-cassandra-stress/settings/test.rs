@@ -1,4 +1,4 @@
-use super::{parse_cassandra_stress_args, repair_params};
+use super::{parse_cassandra_stress_args, repair_params, CassandraStressParsingResult};
 
 const DATA_GOOD: &str = include_str!("cs_args_good_test.in");
 const DATA_BAD: &str = include_str!("cs_args_bad_test.in");
@@ -69,3 +69,31 @@ fn repair_params_test() {
         result
     );
 }
+
+#[test]
+fn schema_command_test() {
+    let args = "cassandra-stress schema write -schema keyspace=ks replication(factor=3) \
+        compaction(strategy=LeveledCompactionStrategy) -col n=2";
+    let settings = match parse_cassandra_stress_args(args.split_ascii_whitespace()) {
+        Ok(CassandraStressParsingResult::Schema(settings)) => settings,
+        _ => panic!("expected the schema command to be parsed"),
+    };
+
+    assert_eq!(
+        settings.schema_ddl().to_string(),
+        "CREATE KEYSPACE IF NOT EXISTS \"ks\" WITH REPLICATION = \
+        {'class': 'SimpleStrategy', 'replication_factor': '3'};\n\
+        USE \"ks\";\n\
+        CREATE TABLE IF NOT EXISTS standard1 (key blob, \"C0\" blob, \"C1\" blob, \
+        PRIMARY KEY (key)) WITH compression = {} \
+        AND compaction = {'class': 'LeveledCompactionStrategy'};\n"
+    );
+
+    // Reads don't create the keyspace, nor the table.
+    let args = "cassandra-stress schema read n=10 -pop seq=1..10 -schema keyspace=ks";
+    let settings = match parse_cassandra_stress_args(args.split_ascii_whitespace()) {
+        Ok(CassandraStressParsingResult::Schema(settings)) => settings,
+        _ => panic!("expected the schema command to be parsed"),
+    };
+    assert_eq!(settings.schema_ddl().to_string(), "USE \"ks\";\n");
+}
@@ -134,19 +134,28 @@ impl sharded_stats::Stats for Stats {
 
 pub struct StatsPrinter {
     start_time: Instant,
+    // The summary is computed over the time since the warmup.
+    measured_start_time: Instant,
     previous_time: Instant,
     total_ops: u64,
 }
 
 impl StatsPrinter {
     pub fn new() -> Self {
+        let now = Instant::now();
         Self {
-            start_time: Instant::now(),
-            previous_time: Instant::now(),
+            start_time: now,
+            measured_start_time: now,
+            previous_time: now,
             total_ops: 0,
         }
     }
 
+    pub fn with_warmup(mut self, warmup: Duration) -> Self {
+        self.measured_start_time = self.start_time + warmup;
+        self
+    }
+
     pub fn print_header(&self) {
         println!(
             "{:10},{:>8},{:>8},{:>8},{:>8},{:>8},{:>8},{:>8},{:>7},{:>7}",
@@ -178,7 +187,7 @@ impl StatsPrinter {
 
     pub fn print_summary(&self, final_stats: &Stats) {
         let now = Instant::now();
-        let benchmark_duration = now - self.start_time;
+        let benchmark_duration = now.saturating_duration_since(self.measured_start_time);
 
         println!();
         println!("Results:");
@@ -1,66 +1,148 @@
 use std::iter::Iterator;
+use std::net::Ipv6Addr;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
+use rand::distributions::WeightedIndex;
 use scylla::load_balancing::{DefaultPolicy, LoadBalancingPolicy};
 use scylla::statement::Consistency;
+use scylla::transport::session::PoolSize;
+use scylla::transport::Compression;
+use tokio::sync::Semaphore;
 
+use cql_stress::configuration::ErrorRatioLimit;
+
+use crate::counter_tracker::CounterUpdateTracker;
 use crate::distribution::{parse_distribution, Distribution, Fixed};
 use crate::gocompat::flags::{GoValue, ParserBuilder};
 use crate::gocompat::strconv::format_duration;
-use crate::stats::LatencyType;
+use crate::misrouting::Misrouting;
+use crate::node_latency::NodeLatencyTracker;
+use crate::operation::{ErrorCategory, RetryOn};
+use crate::row_checksums::RowChecksumTracker;
+use crate::stats::{LatencyType, LatencyUnit, OutputFormat, RateUnit, SummaryFormat};
+use crate::workload::TokenRange;
 
 // Explicitly marked as `pub(crate)`, because with `pub` rustc doesn't
 // complain about fields which are never read
 pub(crate) struct ScyllaBenchArgs {
     pub workload: WorkloadType,
     pub consistency_level: Consistency,
-    pub replication_factor: i64,
+    pub durable_writes: bool,
+    pub tablets: Option<bool>,
+    pub initial_tablets: u64,
     pub nodes: Vec<String>,
+    pub cloud_config_path: String,
     pub ca_cert_file: String,
     pub client_cert_file: String,
     pub client_key_file: String,
     pub server_name: String,
     pub host_verification: bool,
-    pub client_compression: bool,
+    pub client_compression: Option<Compression>,
     pub shard_connection_count: NonZeroUsize,
+    // Overrides shard_connection_count with a pool of a fixed size per host
+    pub connection_count: Option<NonZeroUsize>,
+    pub shard_coverage: bool,
+    pub keepalive: Option<Duration>,
     pub page_size: i64,
     pub partition_offset: i64,
+    pub token_range: Option<TokenRange>,
+    pub load_mode: LoadMode,
+    pub access_order: AccessOrder,
+    pub ck_order: CkOrder,
+    pub print_pk_ranges: bool,
+    pub resume_from: u64,
+    pub checkpoint_file: String,
 
     // (Timeseries-related parameters)
     pub write_rate: u64,
     pub distribution: TimeseriesDistribution,
     pub start_timestamp: u64,
 
+    // (Timebucket-related parameters)
+    pub time_bucket: Duration,
+    pub time_bucket_count: u64,
+    pub twcs_window: Duration,
+
     pub host_selection_policy: Arc<dyn LoadBalancingPolicy>,
+    // The datacenter preferred by the dc-aware host selection policy
+    pub local_dc: Option<String>,
     pub tls_encryption: bool,
     pub keyspace_name: String,
+    // Always contains at least one keyspace, the first one is keyspace_name
+    pub keyspaces: Vec<KeyspaceTarget>,
+    pub drop_after: bool,
     pub table_name: String,
+    pub table_count: u64,
+    pub table_selection: TableSelection,
+    pub compression_chunk_length_kb: u64,
     pub counter_table_name: String,
     pub username: String,
     pub password: String,
     pub mode: Mode,
     pub latency_type: LatencyType,
+    pub output_format: OutputFormat,
+    pub summary_format: SummaryFormat,
+    pub output_file: String,
+    pub json_summary: String,
+    pub results_db: String,
+    pub results_tags: String,
     pub max_retries_per_op: u64,
+    pub error_ratio_limit: Option<ErrorRatioLimit>,
+    pub max_consecutive_errors: Option<u64>,
+    // If set, only the operations failed with these errors are retried
+    pub retry_on: Option<Arc<RetryOn>>,
+    pub downgrade_on_unavailable: bool,
+    pub write_timeout_as_success: bool,
+    pub idempotent_writes: bool,
     pub concurrency: u64,
     pub maximum_rate: u64,
+    pub rate_jitter: u64,
+    pub control_port: u16,
+    pub timing_log: String,
+    pub replay_timing: String,
+    pub enforce_targets: bool,
+    pub target_tolerance: u64,
+    pub stop_when_stable: bool,
+    pub sweep_steps: u64,
+    pub sweep_step_duration: Duration,
+    pub stability_intervals: u64,
+    pub stability_tolerance: u64,
 
     pub test_duration: Duration,
+    pub warmup: Option<Duration>,
     pub partition_count: u64,
     pub clustering_row_count: u64,
     pub clustering_row_size_dist: Arc<dyn Distribution>,
 
     pub rows_per_request: u64,
+    pub max_batch_bytes: u64,
+    pub pregenerate_buffer: u64,
+    pub max_bytes: u64,
+    pub data_checksum: bool,
+    pub row_checksums: Option<Arc<RowChecksumTracker>>,
+    pub fire_and_forget: u64,
+    // Bounds the writes in flight sent by fire-and-forget,
+    // shared so that they can be waited for at the end of the run
+    pub in_flight_writes: Option<Arc<Semaphore>>,
     pub provide_upper_bound: bool,
     pub in_restriction: bool,
     pub select_order_by: Vec<OrderBy>,
     pub no_lower_bound: bool,
+    pub full_partition_read: bool,
     pub bypass_cache: bool,
 
     pub range_count: u64,
+    pub scan_validate_every: u64,
+    pub prepare_variants: u64,
+    pub partitioner: Partitioner,
+    pub delete_range_size: u64,
     pub timeout: Duration,
+    pub operation_deadline: Option<Duration>,
+    pub wait_for_cluster: Duration,
+    pub worker_start_stagger: Option<Duration>,
     pub iterations: u64,
     // // Any error response that comes with delay greater than errorToTimeoutCutoffTime
     // // to be considered as timeout error and recorded to histogram as such
@@ -68,7 +150,20 @@ pub(crate) struct ScyllaBenchArgs {
     pub hdr_latency_file: String,
     pub hdr_latency_resolution: u64,
     pub hdr_latency_sig_fig: u64,
+    pub latency_histogram_buckets: u64,
+    pub node_latency: Option<Arc<NodeLatencyTracker>>,
     pub validate_data: bool,
+    pub validation_consistency_level: Option<Consistency>,
+    pub reread_on_mismatch: bool,
+    pub reread_consistency_level: Option<Consistency>,
+    pub consistency_audit: bool,
+    pub misrouting: Option<Misrouting>,
+    pub counter_state_file: String,
+    pub counter_batch_size: u64,
+    pub counter_tracker: Option<Arc<CounterUpdateTracker>>,
+    pub write_audit_interval: u64,
+    pub write_audit_consistency_level: Consistency,
+    pub report_partition_widths: bool,
 }
 
 // Parses and validates scylla bench params.
@@ -84,15 +179,51 @@ where
 
     let mut flag = ParserBuilder::new();
 
-    let workload = flag.string_var("workload", "", "workload: sequential, uniform, timeseries");
+    let workload = flag.string_var(
+        "workload",
+        "",
+        "workload: sequential, uniform, timeseries, timebucket",
+    );
     let consistency_level = flag.string_var("consistency-level", "quorum", "consistency level");
     let replication_factor = flag.i64_var("replication-factor", 1, "replication factor");
+    let durable_writes = flag.bool_var(
+        "durable-writes",
+        true,
+        "value of the durable_writes option of the created keyspace",
+    );
+    let tablets = flag.string_var(
+        "tablets",
+        "",
+        "whether the created keyspace should use tablets: enabled, disabled \
+        (empty to use the cluster's default)",
+    );
+    let initial_tablets = flag.u64_var(
+        "initial-tablets",
+        0,
+        "initial number of tablets of the created keyspace (0 to use the cluster's default)",
+    );
 
-    let nodes = flag.string_var("nodes", "127.0.0.1:9042", "cluster contact nodes");
+    let nodes = flag.string_var(
+        "nodes",
+        "127.0.0.1",
+        "cluster contact nodes, in the host[:port] form",
+    );
+    let port = flag.u64_var(
+        "port",
+        DEFAULT_CQL_PORT as u64,
+        "CQL port of the contact nodes which are given without one",
+    );
+    let cloud_config_path = flag.string_var(
+        "cloud-config-path",
+        "",
+        "path to the cloud config bundle describing the SNI proxy and the nodes behind it; \
+        when set, nodes and TLS flags are ignored",
+    );
     let server_name = flag.string_var(
         "tls-server-name",
         "",
-        "TLS server hostname (currently unimplemented)",
+        "TLS server hostname, which the server certificate is verified against \
+        with tls-host-verification (not sent as SNI)",
     );
     let host_verification =
         flag.bool_var("tls-host-verification", false, "verify server certificate");
@@ -101,11 +232,29 @@ where
         true,
         "use compression for client-coordinator communication",
     );
+    let compression = flag.string_var(
+        "compression",
+        "snappy",
+        "algorithm used with client-compression: snappy, lz4",
+    );
     let shard_connection_count = flag.u64_var(
         "shard-connection-count",
         1,
         "number of connections per shard",
     );
+    let shard_coverage = flag.bool_var(
+        "shard-coverage",
+        false,
+        "after connecting, print the shards of each node that the connection pool \
+        opens the shard-connection-count connections to; this is the configured layout, \
+        the driver doesn't report the connections actually opened",
+    );
+    let keepalive = flag.duration_var(
+        "keepalive",
+        Duration::ZERO,
+        "interval of the heartbeat sent on each connection, so that idle connections \
+        aren't dropped by the middleboxes on the way (0 for the driver's default)",
+    );
     let ca_cert_file = flag.string_var(
         "tls-ca-cert-file",
         "",
@@ -122,17 +271,64 @@ where
         "path to client key file, needed to enable client certificate authentication",
     );
 
-    let _connection_count = flag.i64_var(
+    let connection_count = flag.u64_var(
         "connection-count",
-        4,
-        "number of connections (currently ignored)",
+        0,
+        "number of connections per host, which the driver spreads over the shards \
+        of the node; 0 opens shard-connection-count connections to each shard instead",
+    );
+    let page_size = flag.i64_var(
+        "page-size",
+        1000,
+        "page size of the reads, and of the scans which stream each token range page by page",
     );
-    let page_size = flag.i64_var("page-size", 1000, "page size");
     let partition_offset = flag.i64_var(
         "partition-offset",
         0,
         "start of the partition range (only for sequential workload)",
     );
+    let token_range = flag.string_var(
+        "token-range",
+        "",
+        "only use the partition keys whose Murmur3 tokens fall into the range start:end, \
+        open on the left like the token ranges of the cluster (not for timeseries workload)",
+    );
+    let load_mode = flag.string_var(
+        "load-mode",
+        "interleaved",
+        "how partitions are split between tasks (only for sequential workload): \
+        interleaved, contiguous",
+    );
+    let access_order = flag.string_var(
+        "access-order",
+        "sequential",
+        "order in which each task visits its partitions (only for sequential workload): \
+        sequential, shuffled",
+    );
+    let ck_order = flag.string_var(
+        "ck-order",
+        "ascending",
+        "order in which the clustering rows of a partition are written \
+        (only for sequential workload in write mode): ascending, descending, shuffled",
+    );
+    let print_pk_ranges = flag.bool_var(
+        "print-pk-ranges",
+        false,
+        "print the partition key ranges covered by the process and by each task \
+        in a single machine-readable line at startup (only for sequential workload)",
+    );
+    let resume_from = flag.u64_var(
+        "resume-from",
+        0,
+        "sequence number of the partition to start from, e.g. taken from the checkpoint file \
+        of an interrupted run (only for sequential workload)",
+    );
+    let checkpoint_file = flag.string_var(
+        "checkpoint-file",
+        "",
+        "periodically record the sequence number from which the run can be resumed \
+        into this file (only for sequential workload)",
+    );
 
     let write_rate = flag.u64_var(
         "write-rate",
@@ -150,11 +346,36 @@ where
         "start timestamp of the write load (relevant only for time series reads)",
     );
 
+    let time_bucket = flag.duration_var(
+        "time-bucket",
+        Duration::from_secs(3600),
+        "time span of a single partition (relevant only for timebucket workload)",
+    );
+    let time_bucket_count = flag.u64_var(
+        "time-bucket-count",
+        24,
+        "number of consecutive time buckets written and read by each series \
+        (relevant only for timebucket workload)",
+    );
+    let twcs_window = flag.duration_var(
+        "twcs-window",
+        Duration::ZERO,
+        "if non-zero, creates the tables with the time window compaction strategy \
+        and this compaction window size (must be a whole number of minutes)",
+    );
+
     let host_selection_policy = flag.string_var(
         "host-selection-policy",
         "token-aware",
         "set the driver host selection policy \
-        (round-robin,token-aware,dc-aware:name-of-local-dc),default 'token-aware'",
+        (round-robin,token-aware,dc-aware-round-robin,token-aware-dc-aware,dc-aware:name-of-local-dc),\
+        default 'token-aware'",
+    );
+    let datacenter = flag.string_var(
+        "datacenter",
+        "",
+        "the local datacenter of the dc-aware host selection policies; \
+        the requests are sent only to its nodes, so that local_* consistency levels stay local",
     );
     let tls_encryption = flag.bool_var(
         "tls",
@@ -162,33 +383,238 @@ where
         "use TLS encryption for clien-coordinator communication",
     );
     let keyspace_name = flag.string_var("keyspace", "scylla_bench", "keyspace to use");
+    let keyspaces = flag.string_var(
+        "keyspaces",
+        "",
+        "comma-separated list of keyspaces to spread the operations across, \
+        as name[:weight[:replication-factor]]; each operation picks a keyspace randomly \
+        with the probability proportional to its weight (default: 1), and each keyspace \
+        is created with its own replication factor (default: replication-factor); \
+        overrides keyspace (relevant only for write and read modes)",
+    );
     let table_name = flag.string_var("table", "test", "table to use");
+    let drop_after = flag.bool_var(
+        "drop-after",
+        false,
+        "drop the keyspace at the end of the run, even if the run failed",
+    );
+    let table_count = flag.u64_var(
+        "table-count",
+        1,
+        "number of tables to spread the writes across; if greater than 1, \
+        tables are named <table>_0..<table>_N-1 (relevant only for write mode)",
+    );
+    let table_selection = flag.string_var(
+        "table-selection",
+        "round-robin",
+        "how the target table is selected for each write: round-robin, hash",
+    );
+    let compression_chunk_length_kb = flag.u64_var(
+        "compression-chunk-length-kb",
+        0,
+        "if non-zero, enables LZ4 table compression with the given chunk length in KiB \
+        (must be a power of two)",
+    );
     let counter_table_name =
         flag.string_var("counter-table", "test_counters", "counter table to use");
     let username = flag.string_var("username", "", "cql username for authentication");
-    let password = flag.string_var("password", "", "cql password for authentication");
+    let password = flag.string_var(
+        "password",
+        "",
+        "cql password for authentication; if empty, taken from the SCYLLA_BENCH_PASSWORD \
+        environment variable, which keeps it out of the process listings",
+    );
+    let credentials_file = flag.string_var(
+        "credentials-file",
+        "",
+        "read the cql username and password from this file, containing the lines \
+        username=... and password=...; the file must not be readable by other users",
+    );
     let mode = flag.string_var(
         "mode",
         "",
-        "operating mode: write, read, counter_update, counter_read, scan",
+        "operating mode: write, read, counter_update, counter_read, scan, delete, prepare",
     );
     let latency_type = flag.string_var(
         "latency-type",
         "raw",
         "type of the latency to print during the run: raw, fixed-coordinated-omission",
     );
-    let max_errors_at_row = flag.u64_var(
+    let output_format = flag.string_var(
+        "output-format",
+        "text",
+        "format of the results printed every second: text, ndjson (one JSON object per line)",
+    );
+    let summary_rate_units = flag.string_var(
+        "summary-rate-units",
+        "",
+        "unit of the rates in the final results: k (thousands), m (millions), \
+        auto (either of them, whichever fits); default: operations per second",
+    );
+    let summary_latency_units = flag.string_var(
+        "summary-latency-units",
+        "",
+        "unit of the latencies in the final results: ns, us, ms, s \
+        (default: the same as the results printed during the run)",
+    );
+    let summary_precision = flag.u64_var(
+        "summary-precision",
+        0,
+        "number of significant figures of the rates and latencies in the final results \
+        (0 for full precision)",
+    );
+    let json_summary = flag.string_var(
+        "json",
+        "",
+        "write the final results to this file as a JSON object, also when the run \
+        is interrupted by an error or Ctrl-C",
+    );
+    let output_file = flag.string_var(
+        "output-file",
+        "",
+        "write the results printed every second into this file instead of stdout",
+    );
+    let results_db = flag.string_var(
+        "results-db",
+        "",
+        "append the summary of the run to this SQLite database, creating it if needed \
+        (requires the results-db feature)",
+    );
+    let results_tags = flag.string_var(
+        "results-tags",
+        "",
+        "free-form tags stored with the run in the results database, e.g. commit=abc,cluster=3x",
+    );
+    let error_at_row_limit = flag.u64_var(
         "error-at-row-limit",
         0,
         "the maximum number of attempts allowed for a single operation. \
         After exceeding it, the workflow will terminate with an error. \
         Set to 0 if you want to have unlimited retries",
     );
+    let retries = flag.i64_var(
+        "retries",
+        -1,
+        "the number of times a failed operation is retried before the workflow \
+        terminates with an error; an alternative to error-at-row-limit, \
+        which counts the first attempt too (-1 for unlimited retries)",
+    );
+    let max_errors_at_row = flag.u64_var(
+        "max-errors-at-row",
+        0,
+        "stop the run when more than this many operation attempts fail in a row, \
+        counted across all connections: a successful attempt resets the count. \
+        Set to 0 to never stop because of the errors in a row",
+    );
+    let retry_on = flag.string_var(
+        "retry-on",
+        "",
+        "comma-separated categories of the errors after which the operation is retried: \
+        timeout, unavailable, overloaded, failure, invalid, connection, other; \
+        an operation failed with an error of another category fails immediately \
+        (default: retry after any error)",
+    );
+    let max_error_ratio = flag.string_var(
+        "max-error-ratio",
+        "0",
+        "the maximum ratio of failed attempts, between 0 and 1, over the last \
+        error-ratio-window attempts. After exceeding it, the workflow will terminate \
+        with an error. Set to 0 to disable the check",
+    );
+    let error_ratio_window = flag.u64_var(
+        "error-ratio-window",
+        1000,
+        "the number of most recent attempts over which max-error-ratio is computed; \
+        the check is enforced only once this many attempts have finished",
+    );
+    let downgrade_on_unavailable = flag.bool_var(
+        "downgrade-on-unavailable",
+        false,
+        "on an UNAVAILABLE error, retry the operation once with a consistency level \
+        that the alive replicas can satisfy",
+    );
+    let write_timeout_as_success = flag.bool_var(
+        "write-timeout-as-success",
+        false,
+        "in write mode, count writes which failed with a WRITE_TIMEOUT error, \
+        and so might have been applied, as successes and report them as uncertain writes",
+    );
+    let idempotent_writes = flag.bool_var(
+        "idempotent-writes",
+        true,
+        "mark the writes as idempotent, allowing the driver to retry them and execute them \
+        speculatively; reads are always idempotent and counter updates never are",
+    );
     let concurrency = flag.u64_var("concurrency", 16, "number of used tasks");
     let maximum_rate = flag.u64_var(
         "max-rate",
         0,
-        "the maximum rate of outbound requests in op/s (0 for unlimited)",
+        "the maximum rate of outbound requests in op/s (0 for unlimited); \
+        in scan mode, the scanned rows are limited to this rate as well",
+    );
+    let rate_jitter = flag.u64_var(
+        "rate-jitter",
+        0,
+        "randomly vary the intervals between the requests sent at max-rate by up to \
+        this many percent, keeping the mean rate",
+    );
+    let control_port = flag.u64_var(
+        "control-port",
+        0,
+        "if non-zero, listen on this port on localhost for HTTP requests controlling \
+        the run: POST /pause, POST /resume and POST /rate?ops=N",
+    );
+    let timing_log = flag.string_var(
+        "timing-log",
+        "",
+        "record the arrival time of every operation into a file, \
+        so that it can be replayed with replay-timing",
+    );
+    let replay_timing = flag.string_var(
+        "replay-timing",
+        "",
+        "start the operations at the arrival times recorded in a file with timing-log, \
+        instead of at a constant rate; the run ends when all of them are issued",
+    );
+    let enforce_targets = flag.bool_var(
+        "enforce-targets",
+        false,
+        "exit with code 2 if the run didn't perform all of the workload's operations \
+        or didn't sustain max-rate within the tolerance",
+    );
+    let target_tolerance = flag.u64_var(
+        "target-tolerance",
+        5,
+        "allowed shortfall of the achieved rate below max-rate, in percent",
+    );
+    let stop_when_stable = flag.bool_var(
+        "stop-when-stable",
+        false,
+        "stop the run once throughput and p99 latency stay within stability-tolerance \
+        for stability-intervals consecutive reporting intervals",
+    );
+    let stability_intervals = flag.u64_var(
+        "stability-intervals",
+        10,
+        "number of consecutive stable intervals required by stop-when-stable",
+    );
+    let stability_tolerance = flag.u64_var(
+        "stability-tolerance",
+        5,
+        "maximum spread of the measurements, relative to their mean, \
+        for the run to be considered stable, in percent",
+    );
+    let sweep_steps = flag.u64_var(
+        "sweep-steps",
+        0,
+        "if non-zero, runs a latency-vs-throughput sweep instead of a single run: \
+        step i runs at i/sweep-steps of max-rate, or of the maximum rate discovered \
+        by an initial unthrottled step if max-rate is not set",
+    );
+    let sweep_step_duration = flag.duration_var(
+        "sweep-step-duration",
+        Duration::from_secs(60),
+        "duration of a single step of the sweep",
     );
 
     let test_duration = flag.duration_var(
@@ -196,6 +622,12 @@ where
         Duration::ZERO,
         "duration of the test in seconds (0 for unlimited)",
     );
+    let warmup = flag.duration_var(
+        "warmup",
+        Duration::ZERO,
+        "duration of the warmup before the measured duration of the test, \
+        whose operations are excluded from the results",
+    );
     let partition_count = flag.u64_var("partition-count", 10_000, "number of partitions");
     let clustering_row_count = flag.u64_var(
         "clustering-row-count",
@@ -211,6 +643,38 @@ where
 
     let rows_per_request =
         flag.u64_var("rows-per-request", 1, "clustering rows per single request");
+    let max_batch_bytes = flag.u64_var(
+        "max-batch-bytes",
+        0,
+        "if non-zero, batch writes whose estimated size exceeds this many bytes are split \
+        into multiple batches; should be kept below the server's batch_size_fail_threshold_in_kb",
+    );
+    let max_bytes = flag.u64_var(
+        "max-bytes",
+        0,
+        "in write mode, if non-zero, stop the run once the generated clustering row data \
+        reaches this many bytes",
+    );
+    let data_checksum = flag.bool_var(
+        "data-checksum",
+        false,
+        "in write mode, print the checksum of all successfully written rows at the end: \
+        the wrapping sum of the first 8 bytes (big-endian) of SHA-256 over \
+        pk (8 bytes, big-endian), ck (8 bytes, big-endian) and v of each row; \
+        a row written more than once is counted once, with its last written value",
+    );
+    let fire_and_forget = flag.u64_var(
+        "fire-and-forget",
+        0,
+        "for timeseries writes, if non-zero, don't wait for the writes to be acknowledged \
+        before sending the next ones, keeping at most this many writes in flight",
+    );
+    let pregenerate_buffer = flag.u64_var(
+        "pregenerate-buffer",
+        0,
+        "in write mode, if non-zero, each worker keeps up to this many rows generated ahead \
+        of time on a background task, taking the row generation off the request path",
+    );
     let provide_upper_bound = flag.bool_var(
         "provide-upper-bound",
         false,
@@ -233,6 +697,12 @@ where
         false,
         "do not provide lower bound in read requests",
     );
+    let full_partition_read = flag.bool_var(
+        "full-partition-read",
+        false,
+        "read each selected partition in full, paging through all of its clustering rows \
+        instead of reading rows-per-request of them",
+    );
     let bypass_cache = flag.bool_var(
         "bypass-cache",
         false,
@@ -244,7 +714,50 @@ where
         1,
         "number of ranges to split the token space into (relevant only for scan mode)",
     );
+    let scan_validate_every = flag.u64_var(
+        "scan-validate-every",
+        1,
+        "in scan mode with validate-data, validate only every n-th row returned \
+        by each scanned range, so that validation doesn't make the scan CPU-bound",
+    );
+    let prepare_variants = flag.u64_var(
+        "prepare-variants",
+        100,
+        "number of distinct statements prepared in turn (relevant only for prepare mode); \
+        0 makes every statement new, so that the cached ones keep getting evicted",
+    );
+    let partitioner = flag.string_var(
+        "partitioner",
+        "murmur3",
+        "partitioner used by the cluster, determines the token ring split \
+        by the scan mode: murmur3, random",
+    );
+    let delete_range_size = flag.u64_var(
+        "delete-range-size",
+        1,
+        "number of clustering rows removed by a single range delete, starting from \
+        the generated clustering key; a size of at least clustering-row-count deletes \
+        whole partitions (relevant only for delete mode)",
+    );
     let timeout = flag.duration_var("timeout", Duration::from_secs(5), "request timeout");
+    let operation_deadline = flag.duration_var(
+        "operation-deadline",
+        Duration::ZERO,
+        "client-side deadline for a single operation, enforced independently of the driver \
+        timeout; operations exceeding it are counted as client timeouts (0 for no deadline)",
+    );
+    let wait_for_cluster = flag.duration_var(
+        "wait-for-cluster",
+        Duration::ZERO,
+        "keep retrying to connect to the cluster at startup for up to this long \
+        instead of failing immediately when it is unreachable",
+    );
+    let worker_start_stagger = flag.duration_var(
+        "worker-start-stagger",
+        Duration::ZERO,
+        "spread the start of the workers evenly over the given window \
+        instead of starting all of them at once (0 for no stagger)",
+    );
     let iterations = flag.u64_var(
         "iterations",
         1,
@@ -269,11 +782,92 @@ where
         3,
         "significant figures of the hdr histogram, number from 1 to 5 (default: 3)",
     );
+    let latency_histogram_buckets = flag.u64_var(
+        "latency-histogram",
+        0,
+        "print an ASCII histogram of the latency with the given number of buckets \
+        along with the final results (0 to disable)",
+    );
+    let per_node_latency = flag.bool_var(
+        "per-node-latency",
+        false,
+        "additionally report latency grouped by the coordinator node; with the dc-aware \
+        host selection policy, also report how many requests were coordinated \
+        by the nodes of the local and remote datacenters",
+    );
 
     let validate_data = flag.bool_var(
         "validate-data",
         false,
-        "write meaningful data and validate while reading",
+        "write meaningful data and validate while reading; reads also check that \
+        they return as many rows as the fully written partition should contain; \
+        counter_read checks that the counters of each row agree on the number of updates, \
+        or match counter-state-file exactly",
+    );
+
+    let validation_consistency_level = flag.string_var(
+        "validation-consistency",
+        "",
+        "consistency level of the reads validating the data, e.g. quorum or all \
+        to validate data written at a weaker one (default: consistency-level)",
+    );
+    let reread_on_mismatch = flag.bool_var(
+        "reread-on-mismatch",
+        false,
+        "re-read once the rows which failed validation, and report data corruption \
+        only if the mismatch persists",
+    );
+    let reread_consistency_level = flag.string_var(
+        "reread-consistency",
+        "",
+        "consistency level of the re-reads done by reread-on-mismatch \
+        (default: the one of the original read)",
+    );
+    let consistency_audit = flag.bool_var(
+        "consistency-audit",
+        false,
+        "read each key both at consistency level ONE and ALL, and report the keys \
+        for which the results differ, i.e. whose replicas diverged",
+    );
+    let misrouted_fraction = flag.string_var(
+        "misrouted-fraction",
+        "0",
+        "fraction of the reads, between 0 and 1, deliberately sent to coordinators which \
+        aren't replicas of the partition; the latency of those and the token-aware routed \
+        reads is reported separately; requires a replication factor lower than \
+        the number of nodes",
+    );
+    let counter_state_file = flag.string_var(
+        "counter-state-file",
+        "",
+        "in counter_update mode, count the updates of each row into this file; \
+        in counter_read mode with validate-data, expect the exact counter values recorded there",
+    );
+
+    let counter_batch_size = flag.u64_var(
+        "counter-batch-size",
+        1,
+        "in counter_update mode, the number of clustering rows of a partition \
+        whose counters are incremented in a single counter batch",
+    );
+
+    let write_audit_interval = flag.u64_var(
+        "write-audit-interval",
+        0,
+        "in write mode, read back every n-th written row at write-audit-consistency-level \
+        and report the rows which couldn't be confirmed (0 to disable)",
+    );
+    let write_audit_consistency_level = flag.string_var(
+        "write-audit-consistency-level",
+        "all",
+        "consistency level of the reads done by write-audit-interval",
+    );
+
+    let report_partition_widths = flag.bool_var(
+        "report-partition-widths",
+        false,
+        "report the distribution of clustering rows per partition generated during the run \
+        (relevant only for write and counter_update modes)",
     );
 
     let (parser, desc) = flag.build();
@@ -281,7 +875,23 @@ where
     let result = move || -> Result<ScyllaBenchArgs> {
         parser.parse_args(args)?;
 
-        let nodes = nodes.get().split(',').map(str::to_string).collect();
+        let port = port.get();
+        let default_port = u16::try_from(port)
+            .ok()
+            .filter(|port| *port != 0)
+            .with_context(|| format!("invalid port: {}", port))?;
+        let nodes = nodes
+            .get()
+            .split(',')
+            .map(str::trim)
+            .filter(|node| !node.is_empty())
+            .map(|node| parse_node(node, default_port))
+            .collect::<Result<Vec<_>>>()?;
+        let cloud_config_path = cloud_config_path.get();
+        anyhow::ensure!(
+            !nodes.is_empty() || !cloud_config_path.is_empty(),
+            "no contact points provided",
+        );
         let mode = parse_mode(&mode.get())?;
         let workload = if mode == Mode::Scan {
             anyhow::ensure!(
@@ -289,56 +899,520 @@ where
                 "workload type cannot be specified for scan mode",
             );
             WorkloadType::Scan
+        } else if mode == Mode::Prepare {
+            anyhow::ensure!(
+                workload.get() == "",
+                "workload type cannot be specified for prepare mode",
+            );
+            WorkloadType::Prepare
         } else {
             parse_workload(&workload.get())?
         };
         let consistency_level = parse_consistency_level(&consistency_level.get())?;
+        let validate_data = validate_data.get();
+        let validation_consistency_level = match validation_consistency_level.get().as_str() {
+            "" => None,
+            s => {
+                anyhow::ensure!(
+                    validate_data,
+                    "validation-consistency requires validate-data",
+                );
+                Some(parse_consistency_level(s)?)
+            }
+        };
+        let reread_on_mismatch = reread_on_mismatch.get();
+        if reread_on_mismatch {
+            anyhow::ensure!(
+                validate_data && (mode == Mode::Read || mode == Mode::CounterRead),
+                "reread-on-mismatch requires validate-data and the read or counter_read mode",
+            );
+        }
+        let reread_consistency_level = match reread_consistency_level.get().as_str() {
+            "" => None,
+            s => {
+                anyhow::ensure!(
+                    reread_on_mismatch,
+                    "reread-consistency requires reread-on-mismatch",
+                );
+                Some(parse_consistency_level(s)?)
+            }
+        };
+        let tls_encryption = tls_encryption.get();
+        let host_verification = host_verification.get();
+        let server_name = server_name.get();
+        anyhow::ensure!(
+            !tls_encryption || !host_verification || !server_name.is_empty(),
+            "tls-host-verification requires tls-server-name to be set",
+        );
+        let provide_upper_bound = provide_upper_bound.get();
+        let in_restriction = in_restriction.get();
+        let no_lower_bound = no_lower_bound.get();
+        let full_partition_read = full_partition_read.get();
+        if full_partition_read {
+            anyhow::ensure!(
+                matches!(mode, Mode::Read | Mode::CounterRead),
+                "full-partition-read can only be used with the read and counter_read modes",
+            );
+            anyhow::ensure!(
+                !in_restriction && !provide_upper_bound && !no_lower_bound,
+                "full-partition-read can't be used together with in-restriction, \
+                provide-upper-bound or no-lower-bound",
+            );
+        }
+        let downgrade_on_unavailable = downgrade_on_unavailable.get();
+        let consistency_audit = consistency_audit.get();
+        if consistency_audit {
+            anyhow::ensure!(
+                mode == Mode::Read,
+                "consistency-audit can only be used with the read mode",
+            );
+            anyhow::ensure!(
+                !reread_on_mismatch && !downgrade_on_unavailable,
+                "consistency-audit can't be used together with reread-on-mismatch \
+                or downgrade-on-unavailable",
+            );
+        }
+        let scan_validate_every = scan_validate_every.get();
+        anyhow::ensure!(
+            scan_validate_every > 0,
+            "scan-validate-every must be greater than zero",
+        );
+        anyhow::ensure!(
+            scan_validate_every == 1 || (mode == Mode::Scan && validate_data),
+            "scan-validate-every requires the scan mode and validate-data",
+        );
+        let write_timeout_as_success = write_timeout_as_success.get();
+        anyhow::ensure!(
+            !write_timeout_as_success || mode == Mode::Write,
+            "write-timeout-as-success is supported only in the write mode",
+        );
+        let delete_range_size = delete_range_size.get();
+        anyhow::ensure!(
+            delete_range_size > 0,
+            "delete-range-size must be greater than zero",
+        );
+        let max_bytes = max_bytes.get();
+        anyhow::ensure!(
+            max_bytes == 0 || mode == Mode::Write,
+            "max-bytes is supported only in the write mode",
+        );
+        let data_checksum = data_checksum.get();
+        anyhow::ensure!(
+            !data_checksum || mode == Mode::Write,
+            "data-checksum is supported only in the write mode",
+        );
+        let write_audit_interval = write_audit_interval.get();
+        let fire_and_forget = fire_and_forget.get();
+        if fire_and_forget > 0 {
+            anyhow::ensure!(
+                mode == Mode::Write && workload == WorkloadType::Timeseries,
+                "fire-and-forget is supported only in the write mode of the timeseries workload",
+            );
+            anyhow::ensure!(
+                fire_and_forget <= u32::MAX as u64,
+                "fire-and-forget can't exceed {}",
+                u32::MAX,
+            );
+            anyhow::ensure!(
+                write_audit_interval == 0
+                    && !downgrade_on_unavailable
+                    && !write_timeout_as_success
+                    && !data_checksum,
+                "fire-and-forget can't be used together with write-audit-interval, \
+                downgrade-on-unavailable, write-timeout-as-success or data-checksum",
+            );
+        }
+        let pregenerate_buffer = pregenerate_buffer.get();
+        anyhow::ensure!(
+            pregenerate_buffer == 0 || mode == Mode::Write,
+            "pregenerate-buffer is supported only in the write mode",
+        );
+        anyhow::ensure!(
+            write_audit_interval == 0 || mode == Mode::Write,
+            "write-audit-interval is supported only in the write mode",
+        );
+        let write_audit_consistency_level =
+            parse_consistency_level(&write_audit_consistency_level.get())?;
+        let counter_state_file = counter_state_file.get();
+        if !counter_state_file.is_empty() {
+            anyhow::ensure!(
+                mode == Mode::CounterUpdate || (mode == Mode::CounterRead && validate_data),
+                "counter-state-file is supported only in the counter_update mode \
+                and in the counter_read mode with validate-data",
+            );
+        }
+        let results_db = results_db.get();
+        #[cfg(not(feature = "results-db"))]
+        anyhow::ensure!(
+            results_db.is_empty(),
+            "results-db requires cql-stress to be built with the results-db feature",
+        );
+        let counter_batch_size = counter_batch_size.get();
+        anyhow::ensure!(
+            counter_batch_size > 0,
+            "counter-batch-size must be greater than zero",
+        );
+        anyhow::ensure!(
+            counter_batch_size == 1 || mode == Mode::CounterUpdate,
+            "counter-batch-size is supported only in the counter_update mode",
+        );
+        let tablets = match tablets.get().as_str() {
+            "" => None,
+            "enabled" => Some(true),
+            "disabled" => Some(false),
+            s => return Err(anyhow::anyhow!("Unknown tablets setting: {}", s)),
+        };
+        let initial_tablets = initial_tablets.get();
+        anyhow::ensure!(
+            initial_tablets == 0 || tablets != Some(false),
+            "initial-tablets cannot be set when tablets are disabled",
+        );
         let shard_connection_count = NonZeroUsize::new(shard_connection_count.get() as usize)
             .context("shard connection count cannot be 0")?;
+        let connection_count = NonZeroUsize::new(connection_count.get() as usize);
+        anyhow::ensure!(
+            connection_count.is_none() || shard_connection_count.get() == 1,
+            "only one of connection-count and shard-connection-count can be specified"
+        );
+        let load_mode = parse_load_mode(&load_mode.get())?;
+        let access_order = parse_access_order(&access_order.get())?;
+        let replication_factor = replication_factor.get();
+        let keyspaces = match keyspaces.get().as_str() {
+            "" => vec![KeyspaceTarget {
+                name: keyspace_name.get(),
+                weight: 1,
+                replication_factor,
+            }],
+            s => {
+                anyhow::ensure!(
+                    mode == Mode::Write || mode == Mode::Read,
+                    "multiple keyspaces are supported only in the write and read modes",
+                );
+                parse_keyspaces(s, replication_factor)?
+            }
+        };
+        let table_count = table_count.get();
+        let compression_chunk_length_kb = compression_chunk_length_kb.get();
+        anyhow::ensure!(
+            compression_chunk_length_kb == 0 || compression_chunk_length_kb.is_power_of_two(),
+            "compression-chunk-length-kb must be a power of two",
+        );
+        anyhow::ensure!(table_count > 0, "table-count must be greater than zero");
+        anyhow::ensure!(
+            table_count == 1 || mode == Mode::Write,
+            "multiple tables are supported only in the write mode",
+        );
+        let table_selection = match table_selection.get().as_str() {
+            "round-robin" => TableSelection::RoundRobin,
+            "hash" => TableSelection::Hash,
+            s => return Err(anyhow::anyhow!("Unknown table selection: {}", s)),
+        };
+        let partitioner = match partitioner.get().as_str() {
+            "murmur3" => Partitioner::Murmur3,
+            "random" => Partitioner::Random,
+            s => return Err(anyhow::anyhow!("Unknown partitioner: {}", s)),
+        };
+        let ck_order = parse_ck_order(&ck_order.get())?;
+        anyhow::ensure!(
+            ck_order == CkOrder::Ascending
+                || (workload == WorkloadType::Sequential && mode == Mode::Write),
+            "ck-order is supported only by the sequential workload in the write mode",
+        );
+        let token_range = match token_range.get().as_str() {
+            "" => None,
+            s => Some(parse_token_range(s)?),
+        };
+        anyhow::ensure!(
+            token_range.is_none()
+                || (workload != WorkloadType::Timeseries
+                    && mode != Mode::Scan
+                    && mode != Mode::Prepare),
+            "token-range is not supported by the timeseries workload, nor in the scan \
+            and prepare modes",
+        );
+        let print_pk_ranges = print_pk_ranges.get();
+        anyhow::ensure!(
+            !print_pk_ranges || workload == WorkloadType::Sequential,
+            "print-pk-ranges is supported only by the sequential workload",
+        );
+        let resume_from = resume_from.get();
+        let checkpoint_file = checkpoint_file.get();
+        if resume_from != 0 || !checkpoint_file.is_empty() {
+            anyhow::ensure!(
+                workload == WorkloadType::Sequential && load_mode == LoadMode::Interleaved,
+                "resume-from and checkpoint-file are supported only by the sequential workload \
+                with the interleaved load mode",
+            );
+        }
         let distribution = parse_timeseries_distribution(&distribution.get())?;
         let mut start_timestamp = start_timestamp.get();
+        let start_timestamp_given = start_timestamp != 0;
         if start_timestamp == 0 {
             start_timestamp = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() as u64;
         }
-        let host_selection_policy = parse_host_selection_policy(&host_selection_policy.get())?;
+        let measure_latency = measure_latency.get();
+        let host_selection_policy = host_selection_policy.get();
+        let (local_dc, token_aware) =
+            parse_host_selection_policy(&host_selection_policy, &datacenter.get())?;
+        let misrouted_fraction = parse_misrouted_fraction(&misrouted_fraction.get())?;
+        if misrouted_fraction > 0.0 {
+            anyhow::ensure!(
+                mode == Mode::Read,
+                "misrouted-fraction can only be used with the read mode",
+            );
+            anyhow::ensure!(
+                token_aware,
+                "misrouted-fraction requires a token-aware host selection policy",
+            );
+            anyhow::ensure!(
+                measure_latency,
+                "misrouted-fraction requires the latency to be measured",
+            );
+            anyhow::ensure!(
+                !consistency_audit,
+                "misrouted-fraction can't be used together with consistency-audit",
+            );
+        }
+        let mut policy = DefaultPolicy::builder().token_aware(token_aware);
+        if let Some(local_dc) = &local_dc {
+            policy = policy.prefer_datacenter(local_dc.clone());
+        }
+        let host_selection_policy: Arc<dyn LoadBalancingPolicy> = policy.build();
         let select_order_by = parse_order_by_chain(&select_order_by.get())?;
         let write_rate = write_rate.get();
         let concurrency = concurrency.get();
         let partition_count = partition_count.get();
         let maximum_rate = maximum_rate.get();
-
-        if workload == WorkloadType::Timeseries {
-            if mode == Mode::Read {
-                anyhow::ensure!(
-                    write_rate != 0,
-                    "Write rate must be provided for time series reads loads",
-                );
-                anyhow::ensure!(
-                    start_timestamp != 0,
-                    "Start timestamp must be provided for time series reads loads",
-                );
-            } else if mode == Mode::Write {
-                anyhow::ensure!(
-                    concurrency <= partition_count,
-                    "Time series writes require concurrency less than or equal partition count",
-                );
-                anyhow::ensure!(
-                    maximum_rate != 0,
-                    "max-rate must be provided for time series write loads"
-                );
-            }
-        }
-
-        let latency_type = match latency_type.get().as_str() {
-            "raw" => LatencyType::Raw,
-            "fixed-coordinated-omission" => LatencyType::AdjustedForCoordinatorOmission,
-            s => return Err(anyhow::anyhow!("Unsupported latency type: {}; supported types are: raw, fixed-coordinated-omission", s)),
+        let replay_timing = replay_timing.get();
+        anyhow::ensure!(
+            replay_timing.is_empty() || maximum_rate == 0,
+            "replay-timing and max-rate are mutually exclusive",
+        );
+        let rate_jitter = rate_jitter.get();
+        anyhow::ensure!(
+            rate_jitter <= 100,
+            "rate-jitter must be a percentage between 0 and 100",
+        );
+        anyhow::ensure!(
+            rate_jitter == 0 || maximum_rate > 0,
+            "rate-jitter requires max-rate to be set",
+        );
+        let control_port = control_port.get();
+        anyhow::ensure!(
+            control_port <= u16::MAX as u64,
+            "control-port must be a valid port number",
+        );
+        let page_size = page_size.get();
+        anyhow::ensure!(
+            page_size > 0 && page_size <= i32::MAX as i64,
+            "page-size must be positive and fit in 32 bits",
+        );
+        let target_tolerance = target_tolerance.get();
+        anyhow::ensure!(
+            target_tolerance <= 100,
+            "target-tolerance must be a percentage between 0 and 100",
+        );
+        let stability_intervals = stability_intervals.get();
+        anyhow::ensure!(
+            stability_intervals > 0,
+            "stability-intervals must be greater than zero",
+        );
+        let stability_tolerance = stability_tolerance.get();
+        anyhow::ensure!(
+            stability_tolerance <= 100,
+            "stability-tolerance must be a percentage between 0 and 100",
+        );
+        let latency_histogram_buckets = latency_histogram_buckets.get();
+        if latency_histogram_buckets > 0 {
+            anyhow::ensure!(
+                measure_latency,
+                "latency-histogram requires the latency to be measured",
+            );
+            anyhow::ensure!(
+                latency_histogram_buckets <= 100,
+                "latency-histogram must be at most 100 buckets",
+            );
+        }
+        let sweep_steps = sweep_steps.get();
+        let sweep_step_duration = sweep_step_duration.get();
+        let test_duration = test_duration.get();
+        let warmup = Some(warmup.get()).filter(|w| !w.is_zero());
+        if warmup.is_some() {
+            // Otherwise the workload could finish within the warmup,
+            // or perform fewer operations than expected in the measured window
+            anyhow::ensure!(
+                !test_duration.is_zero(),
+                "warmup requires the duration of the test to be set",
+            );
+            anyhow::ensure!(sweep_steps == 0, "warmup can't be used with sweep-steps");
+        }
+        if sweep_steps > 0 {
+            anyhow::ensure!(
+                measure_latency,
+                "sweep-steps requires the latency to be measured",
+            );
+            anyhow::ensure!(
+                workload != WorkloadType::Sequential
+                    && !(workload == WorkloadType::Timebucket && mode == Mode::Write),
+                "sweep-steps is not supported by the sequential workload and by the \
+                timebucket workload in the write mode, which could finish before the sweep does",
+            );
+            anyhow::ensure!(
+                !sweep_step_duration.is_zero(),
+                "sweep-step-duration must be greater than zero",
+            );
+            anyhow::ensure!(
+                replay_timing.is_empty(),
+                "sweep-steps and replay-timing are mutually exclusive",
+            );
+            anyhow::ensure!(
+                counter_state_file.is_empty(),
+                "sweep-steps and counter-state-file are mutually exclusive",
+            );
+            anyhow::ensure!(
+                results_db.is_empty(),
+                "sweep-steps and results-db are mutually exclusive",
+            );
+            anyhow::ensure!(
+                control_port == 0,
+                "sweep-steps and control-port are mutually exclusive",
+            );
+        }
+
+        let time_bucket = time_bucket.get();
+        let time_bucket_count = time_bucket_count.get();
+        let clustering_row_count = clustering_row_count.get();
+        if workload == WorkloadType::Timebucket {
+            anyhow::ensure!(
+                mode == Mode::Write || mode == Mode::Read,
+                "Timebucket workload supports only write and read modes",
+            );
+            anyhow::ensure!(
+                time_bucket.as_nanos() >= clustering_row_count as u128,
+                "time-bucket must be at least as many nanoseconds long as clustering-row-count",
+            );
+            anyhow::ensure!(
+                time_bucket_count > 0,
+                "time-bucket-count must be greater than zero",
+            );
+            anyhow::ensure!(
+                mode != Mode::Read || start_timestamp_given,
+                "Start timestamp must be provided for timebucket reads loads",
+            );
+        }
+        let twcs_window = twcs_window.get();
+        anyhow::ensure!(
+            twcs_window.as_nanos().is_multiple_of(60_000_000_000),
+            "twcs-window must be a whole number of minutes",
+        );
+
+        if workload == WorkloadType::Timeseries {
+            if mode == Mode::Read {
+                anyhow::ensure!(
+                    write_rate != 0,
+                    "Write rate must be provided for time series reads loads",
+                );
+                anyhow::ensure!(
+                    start_timestamp != 0,
+                    "Start timestamp must be provided for time series reads loads",
+                );
+            } else if mode == Mode::Write {
+                anyhow::ensure!(
+                    concurrency <= partition_count,
+                    "Time series writes require concurrency less than or equal partition count",
+                );
+                anyhow::ensure!(
+                    maximum_rate != 0,
+                    "max-rate must be provided for time series write loads"
+                );
+            }
+        }
+
+        let latency_type = match latency_type.get().as_str() {
+            "raw" => LatencyType::Raw,
+            "fixed-coordinated-omission" => LatencyType::AdjustedForCoordinatorOmission,
+            s => return Err(anyhow::anyhow!("Unsupported latency type: {}; supported types are: raw, fixed-coordinated-omission", s)),
         };
 
+        let output_format = match output_format.get().as_str() {
+            "text" => OutputFormat::Text,
+            "ndjson" => OutputFormat::Ndjson,
+            s => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported output format: {}; supported formats are: text, ndjson",
+                    s
+                ))
+            }
+        };
+
+        // Parse the algorithm even if compression is disabled, so that typos are caught
+        let compression = parse_compression(&compression.get())?;
+        let client_compression = Some(compression).filter(|_| client_compression.get());
+
+        let credentials_file = credentials_file.get();
+        let (username, password) = if credentials_file.is_empty() {
+            let username = username.get();
+            let mut password = password.get();
+            if password.is_empty() && !username.is_empty() {
+                password = std::env::var(PASSWORD_ENV_VAR).unwrap_or_default();
+            }
+            anyhow::ensure!(
+                username.is_empty() == password.is_empty(),
+                "username and password must be given together \
+                (the password either with -password or {})",
+                PASSWORD_ENV_VAR,
+            );
+            (username, password)
+        } else {
+            anyhow::ensure!(
+                username.get().is_empty() && password.get().is_empty(),
+                "credentials-file can't be used together with username or password",
+            );
+            read_credentials_file(&credentials_file)?
+        };
+        anyhow::ensure!(
+            cloud_config_path.is_empty() || username.is_empty(),
+            "the credentials are taken from cloud-config-path, \
+            they can't be given with username, password or credentials-file",
+        );
+
+        let summary_format = parse_summary_format(
+            &summary_rate_units.get(),
+            &summary_latency_units.get(),
+            summary_precision.get(),
+        )?;
+
         // Zero means unlimited tries,
         // and #tries == #retries + 1,
         // therefore just subtract with wraparound and treat u64::MAX as infinity
-        let max_retries_per_op = max_errors_at_row.get().wrapping_sub(1);
+        let error_at_row_limit = error_at_row_limit.get();
+        let retries = retries.get();
+        anyhow::ensure!(
+            retries >= -1,
+            "retries must be -1 (unlimited) or a non-negative number"
+        );
+        anyhow::ensure!(
+            retries == -1 || error_at_row_limit == 0,
+            "only one of retries and error-at-row-limit can be specified"
+        );
+        let max_retries_per_op = if retries >= 0 {
+            retries as u64
+        } else {
+            error_at_row_limit.wrapping_sub(1)
+        };
+        let max_consecutive_errors = Some(max_errors_at_row.get()).filter(|n| *n > 0);
+        let retry_on = retry_on.get();
+        let retry_on = if retry_on.is_empty() {
+            None
+        } else {
+            let categories = retry_on
+                .split(',')
+                .map(ErrorCategory::parse)
+                .collect::<Result<Vec<_>>>()?;
+            Some(Arc::new(RetryOn(categories)))
+        };
+        let error_ratio_limit =
+            parse_error_ratio_limit(&max_error_ratio.get(), error_ratio_window.get())?;
 
         let hdr_latency_resolution = match hdr_latency_units.get().as_str() {
             "ns" => 1,
@@ -361,50 +1435,128 @@ where
         Ok(ScyllaBenchArgs {
             workload,
             consistency_level,
-            replication_factor: replication_factor.get(),
+            durable_writes: durable_writes.get(),
+            tablets,
+            initial_tablets,
             nodes,
+            cloud_config_path,
             ca_cert_file: ca_cert_file.get(),
             client_cert_file: client_cert_file.get(),
             client_key_file: client_key_file.get(),
-            server_name: server_name.get(),
-            host_verification: host_verification.get(),
-            client_compression: client_compression.get(),
+            server_name,
+            host_verification,
+            client_compression,
+            keepalive: Some(keepalive.get()).filter(|d| !d.is_zero()),
             shard_connection_count,
-            page_size: page_size.get(),
+            connection_count,
+            shard_coverage: shard_coverage.get(),
+            page_size,
             partition_offset: partition_offset.get(),
+            token_range,
+            load_mode,
+            access_order,
+            ck_order,
+            print_pk_ranges,
+            resume_from,
+            checkpoint_file,
             write_rate,
             distribution,
             start_timestamp,
+            time_bucket,
+            time_bucket_count,
+            twcs_window,
             host_selection_policy,
-            tls_encryption: tls_encryption.get(),
-            keyspace_name: keyspace_name.get(),
+            local_dc,
+            tls_encryption,
+            keyspace_name: keyspaces[0].name.clone(),
+            keyspaces,
+            drop_after: drop_after.get(),
             table_name: table_name.get(),
+            table_count,
+            table_selection,
+            compression_chunk_length_kb,
             counter_table_name: counter_table_name.get(),
-            username: username.get(),
-            password: password.get(),
+            username,
+            password,
             mode,
             concurrency,
             latency_type,
+            output_format,
+            summary_format,
+            output_file: output_file.get(),
+            json_summary: json_summary.get(),
+            results_db,
+            results_tags: results_tags.get(),
             max_retries_per_op,
+            retry_on,
+            error_ratio_limit,
+            max_consecutive_errors,
+            downgrade_on_unavailable,
+            write_timeout_as_success,
+            idempotent_writes: idempotent_writes.get(),
             maximum_rate,
-            test_duration: test_duration.get(),
+            rate_jitter,
+            control_port: control_port as u16,
+            timing_log: timing_log.get(),
+            replay_timing,
+            enforce_targets: enforce_targets.get(),
+            target_tolerance,
+            stop_when_stable: stop_when_stable.get(),
+            sweep_steps,
+            sweep_step_duration,
+            stability_intervals,
+            stability_tolerance,
+            test_duration,
+            warmup,
             partition_count,
-            clustering_row_count: clustering_row_count.get(),
+            clustering_row_count,
             clustering_row_size_dist: clustering_row_size_dist.get().0,
             rows_per_request: rows_per_request.get(),
-            provide_upper_bound: provide_upper_bound.get(),
-            in_restriction: in_restriction.get(),
+            max_batch_bytes: max_batch_bytes.get(),
+            pregenerate_buffer,
+            max_bytes,
+            data_checksum,
+            row_checksums: data_checksum.then(|| Arc::new(RowChecksumTracker::new())),
+            fire_and_forget,
+            in_flight_writes: (fire_and_forget > 0)
+                .then(|| Arc::new(Semaphore::new(fire_and_forget as usize))),
+            provide_upper_bound,
+            in_restriction,
             select_order_by,
-            no_lower_bound: no_lower_bound.get(),
+            no_lower_bound,
+            full_partition_read,
             bypass_cache: bypass_cache.get(),
             range_count: range_count.get(),
+            scan_validate_every,
+            prepare_variants: prepare_variants.get(),
+            partitioner,
+            delete_range_size,
             timeout: timeout.get(),
+            operation_deadline: Some(operation_deadline.get()).filter(|d| !d.is_zero()),
+            wait_for_cluster: wait_for_cluster.get(),
+            worker_start_stagger: Some(worker_start_stagger.get()).filter(|d| !d.is_zero()),
             iterations: iterations.get(),
-            measure_latency: measure_latency.get(),
+            measure_latency,
             hdr_latency_file: hdr_latency_file.get(),
             hdr_latency_sig_fig,
             hdr_latency_resolution,
-            validate_data: validate_data.get(),
+            latency_histogram_buckets,
+            node_latency: per_node_latency
+                .get()
+                .then(|| Arc::new(NodeLatencyTracker::new())),
+            validate_data,
+            validation_consistency_level,
+            reread_on_mismatch,
+            reread_consistency_level,
+            consistency_audit,
+            misrouting: (misrouted_fraction > 0.0).then(|| Misrouting::new(misrouted_fraction)),
+            counter_tracker: (!counter_state_file.is_empty())
+                .then(|| Arc::new(CounterUpdateTracker::new())),
+            counter_state_file,
+            counter_batch_size,
+            write_audit_interval,
+            write_audit_consistency_level,
+            report_partition_widths: report_partition_widths.get(),
         })
     }();
 
@@ -422,25 +1574,119 @@ where
 }
 
 impl ScyllaBenchArgs {
+    /// Consistency level of the reads, which may be stronger than the one
+    /// of the writes if the reads validate the data.
+    pub fn read_consistency_level(&self) -> Consistency {
+        match self.validation_consistency_level {
+            Some(cl) if self.validate_data => cl,
+            _ => self.consistency_level,
+        }
+    }
+
+    pub fn pool_size(&self) -> PoolSize {
+        match self.connection_count {
+            Some(count) => PoolSize::PerHost(count),
+            None => PoolSize::PerShard(self.shard_connection_count),
+        }
+    }
+
     pub fn print_configuration(&self) {
         println!("Configuration");
         println!("Mode:\t\t\t {}", show_mode(&self.mode));
         println!("Workload:\t\t {}", show_workload(&self.workload));
         println!("Timeout:\t\t {}", format_duration(self.timeout));
+        if let Some(local_dc) = &self.local_dc {
+            println!("Local datacenter:\t {}", local_dc);
+        }
+        if let Some(warmup) = self.warmup {
+            println!("Warmup:\t\t\t {}", format_duration(warmup));
+        }
+        if let Some(deadline) = self.operation_deadline {
+            println!("Operation deadline:\t {}", format_duration(deadline));
+        }
+        if !self.wait_for_cluster.is_zero() {
+            println!(
+                "Wait for cluster:\t {}",
+                format_duration(self.wait_for_cluster)
+            );
+        }
         println!(
             "Consistency level:\t {}",
             show_consistency_level(&self.consistency_level)
         );
+        if let Some(cl) = &self.validation_consistency_level {
+            println!("Validation consistency:\t {}", show_consistency_level(cl));
+        }
+        if self.reread_on_mismatch {
+            match &self.reread_consistency_level {
+                Some(cl) => println!("Reread on mismatch:\t {}", show_consistency_level(cl)),
+                None => println!("Reread on mismatch:\t true"),
+            }
+        }
+        if self.consistency_audit {
+            println!("Consistency audit:\t true");
+        }
+        if let Some(misrouting) = &self.misrouting {
+            println!("Misrouted fraction:\t {}", misrouting.fraction());
+        }
+        if !self.counter_state_file.is_empty() {
+            println!("Counter state file:\t {}", self.counter_state_file);
+        }
+        if self.counter_batch_size > 1 {
+            println!("Counter batch size:\t {}", self.counter_batch_size);
+        }
+        if self.write_audit_interval > 0 {
+            println!(
+                "Write audit:\t\t every {} ops at {}",
+                self.write_audit_interval,
+                show_consistency_level(&self.write_audit_consistency_level)
+            );
+        }
         println!("Partition count:\t {}", self.partition_count);
         if self.workload == WorkloadType::Sequential && self.partition_offset != 0 {
             println!("Partition offset:\t {}", self.partition_offset);
         }
+        if let Some(range) = &self.token_range {
+            println!("Token range:\t\t ({}, {}]", range.start, range.end);
+        }
+        if self.workload == WorkloadType::Sequential {
+            println!("Load mode:\t\t {}", show_load_mode(&self.load_mode));
+            println!(
+                "Access order:\t\t {}",
+                show_access_order(&self.access_order)
+            );
+            println!("Clustering key order:\t {}", show_ck_order(&self.ck_order));
+        }
+        if self.resume_from != 0 {
+            println!("Resume from:\t\t {}", self.resume_from);
+        }
         println!("Clustering rows:\t {}", self.clustering_row_count);
         println!(
             "Clustering row size:\t {}",
             self.clustering_row_size_dist.describe()
         );
         println!("Rows per request:\t {}", self.rows_per_request);
+        if self.max_batch_bytes > 0 {
+            println!("Max batch size:\t\t {}B", self.max_batch_bytes);
+        }
+        if self.write_timeout_as_success {
+            println!("Write timeout as success: true");
+        }
+        if self.max_bytes > 0 {
+            println!("Max bytes:\t\t {}B", self.max_bytes);
+        }
+        if self.data_checksum {
+            println!("Data checksum:\t\t true");
+        }
+        if self.fire_and_forget > 0 {
+            println!("Fire and forget:\t {} in flight", self.fire_and_forget);
+        }
+        if self.pregenerate_buffer > 0 {
+            println!("Pregenerate buffer:\t {}", self.pregenerate_buffer);
+        }
+        if self.mode == Mode::Delete {
+            println!("Delete range size:\t {}", self.delete_range_size);
+        }
         if self.mode == Mode::Read {
             println!("Provide upper bound:\t {}", self.provide_upper_bound);
             println!("IN queries:\t\t {}", self.in_restriction);
@@ -450,16 +1696,151 @@ impl ScyllaBenchArgs {
             );
             println!("No lower bound:\t\t {}", self.no_lower_bound);
         }
+        if self.full_partition_read {
+            println!("Full partition read:\t true");
+        }
+        if self.mode == Mode::Scan {
+            println!("Range count:\t\t {}", self.range_count);
+            if self.validate_data && self.scan_validate_every > 1 {
+                println!("Validate every:\t\t {} rows", self.scan_validate_every);
+            }
+            println!("Partitioner:\t\t {}", show_partitioner(&self.partitioner));
+        }
+        if self.mode == Mode::Prepare {
+            println!("Prepare variants:\t {}", self.prepare_variants);
+        }
+        if self.keyspaces.len() > 1 {
+            let keyspaces = self
+                .keyspaces
+                .iter()
+                .map(|ks| {
+                    format!(
+                        "{} (weight {}, RF {})",
+                        ks.name, ks.weight, ks.replication_factor
+                    )
+                })
+                .collect::<Vec<_>>();
+            println!("Keyspaces:\t\t {}", keyspaces.join(", "));
+        }
+        if self.table_count > 1 {
+            println!("Table count:\t\t {}", self.table_count);
+        }
+        if !self.twcs_window.is_zero() {
+            println!("TWCS window:\t\t {}", format_duration(self.twcs_window));
+        }
+        if self.compression_chunk_length_kb > 0 {
+            println!(
+                "Compression chunk:\t {}KiB",
+                self.compression_chunk_length_kb
+            );
+        }
         println!("Page size:\t\t {}", self.page_size);
         println!("Concurrency:\t\t {}", self.concurrency);
-        // println!("Connections:\t\t {}", self.connection_count);
-        if self.maximum_rate > 0 {
+        if let Some(stagger) = self.worker_start_stagger {
+            println!("Worker start stagger:\t {}", format_duration(stagger));
+        }
+        if !self.replay_timing.is_empty() {
+            println!("Replay timing:\t\t {}", self.replay_timing);
+        } else if self.maximum_rate > 0 {
             println!("Maximum rate:\t\t {}ops/s", self.maximum_rate);
+            if self.rate_jitter > 0 {
+                println!("Rate jitter:\t\t {}%", self.rate_jitter);
+            }
         } else {
             println!("Maximum rate:\t\t unlimited");
         }
-        println!("Client compression:\t {}", self.client_compression);
-        println!("Shard connection count:\t {}", self.shard_connection_count);
+        if self.control_port > 0 {
+            println!("Control port:\t\t {}", self.control_port);
+        }
+        if self.max_retries_per_op == u64::MAX {
+            println!("Retries:\t\t unlimited");
+        } else {
+            println!("Retries:\t\t {}", self.max_retries_per_op);
+        }
+        if let Some(retry_on) = &self.retry_on {
+            let names: Vec<_> = retry_on.0.iter().map(ErrorCategory::name).collect();
+            println!("Retry on:\t\t {}", names.join(","));
+        }
+        if let Some(limit) = &self.error_ratio_limit {
+            println!(
+                "Max error ratio:\t {} over {} attempts",
+                limit.max_ratio, limit.window
+            );
+        }
+        if let Some(limit) = self.max_consecutive_errors {
+            println!("Max errors at row:\t {}", limit);
+        }
+        if self.sweep_steps > 0 {
+            println!(
+                "Sweep:\t\t\t {} steps of {}",
+                self.sweep_steps,
+                format_duration(self.sweep_step_duration)
+            );
+        }
+        if self.stop_when_stable {
+            println!(
+                "Stop when stable:\t {} intervals within {}%",
+                self.stability_intervals, self.stability_tolerance
+            );
+        }
+        if !self.idempotent_writes {
+            println!("Idempotent writes:\t false");
+        }
+        match self.client_compression {
+            Some(c) => println!("Client compression:\t {}", show_compression(&c)),
+            None => println!("Client compression:\t false"),
+        }
+        match self.connection_count {
+            Some(count) => println!("Connections:\t\t {} per host", count),
+            None => println!("Shard connection count:\t {}", self.shard_connection_count),
+        }
+        if self.shard_coverage {
+            println!("Shard coverage:\t\t true");
+        }
+        if let Some(keepalive) = self.keepalive {
+            println!("Keepalive:\t\t {}", format_duration(keepalive));
+        }
+        if self.latency_histogram_buckets > 0 {
+            println!(
+                "Latency histogram:\t {} buckets",
+                self.latency_histogram_buckets
+            );
+        }
+        if self.node_latency.is_some() {
+            println!("Per-node latency:\t true");
+        }
+        if self.output_format == OutputFormat::Ndjson {
+            println!("Output format:\t\t ndjson");
+        }
+        if self.summary_format.rate_unit != RateUnit::One {
+            println!("Summary rate units:\t {}", self.summary_format.rate_unit);
+        }
+        if let Some(unit) = self.summary_format.latency_unit {
+            println!("Summary latency units:\t {}", unit);
+        }
+        if let Some(figures) = self.summary_format.significant_figures {
+            println!("Summary precision:\t {} significant figures", figures);
+        }
+        if !self.json_summary.is_empty() {
+            println!("JSON summary:\t\t {}", self.json_summary);
+        }
+        if !self.output_file.is_empty() {
+            println!("Output file:\t\t {}", self.output_file);
+        }
+        if !self.results_db.is_empty() {
+            println!("Results database:\t {}", self.results_db);
+        }
+        if self.drop_after {
+            println!("Drop after:\t\t true");
+        }
+        if !self.results_tags.is_empty() {
+            println!("Results tags:\t\t {}", self.results_tags);
+        }
+        if self.workload == WorkloadType::Timebucket {
+            println!("Start timestamp:\t {}", self.start_timestamp);
+            println!("Time bucket:\t\t {}", format_duration(self.time_bucket));
+            println!("Time bucket count:\t {}", self.time_bucket_count);
+        }
         if self.workload == WorkloadType::Timeseries {
             println!("Start timestamp:\t {}", self.start_timestamp);
             println!(
@@ -470,6 +1851,44 @@ impl ScyllaBenchArgs {
 
         // println!("Hdr memory consumption:\t", results.GetHdrMemoryConsumption(concurrency), "bytes");
     }
+
+    /// Returns the names of all regular tables used by the benchmark,
+    /// the tables of each keyspace one after another.
+    pub fn table_names(&self) -> Vec<String> {
+        let table_names = if self.table_count == 1 {
+            vec![self.table_name.clone()]
+        } else {
+            (0..self.table_count)
+                .map(|idx| format!("{}_{}", self.table_name, idx))
+                .collect()
+        };
+        (0..self.keyspaces.len())
+            .flat_map(|ks_idx| {
+                table_names
+                    .iter()
+                    .map(move |table_name| self.table_in_keyspace(ks_idx, table_name))
+            })
+            .collect()
+    }
+
+    /// Returns the name of the table in the keyspace with the given index.
+    /// The name is qualified with the keyspace name only if there are
+    /// multiple keyspaces, otherwise the session's keyspace is used.
+    pub fn table_in_keyspace(&self, ks_idx: usize, table_name: &str) -> String {
+        if self.keyspaces.len() == 1 {
+            return table_name.to_owned();
+        }
+        format!("{}.{}", self.keyspaces[ks_idx].name, table_name)
+    }
+
+    /// Returns the distribution of the indices of the keyspaces picked
+    /// by the operations, or None if there is only one keyspace.
+    pub fn keyspace_choice(&self) -> Option<WeightedIndex<u64>> {
+        (self.keyspaces.len() > 1).then(|| {
+            // The weights are validated to be positive when parsing
+            WeightedIndex::new(self.keyspaces.iter().map(|ks| ks.weight)).unwrap()
+        })
+    }
 }
 
 struct ScyllaBenchDistribution(Arc<dyn Distribution>);
@@ -545,6 +1964,8 @@ pub enum Mode {
     CounterUpdate,
     CounterRead,
     Scan,
+    Delete,
+    Prepare,
 }
 
 fn parse_mode(s: &str) -> Result<Mode> {
@@ -554,18 +1975,22 @@ fn parse_mode(s: &str) -> Result<Mode> {
         "counter_update" => Ok(Mode::CounterUpdate),
         "counter_read" => Ok(Mode::CounterRead),
         "scan" => Ok(Mode::Scan),
+        "delete" => Ok(Mode::Delete),
+        "prepare" => Ok(Mode::Prepare),
         "" => Err(anyhow::anyhow!("mode needs to be specified")),
         _ => Err(anyhow::anyhow!("unknown mode: {}", s)),
     }
 }
 
-fn show_mode(m: &Mode) -> &'static str {
+pub(crate) fn show_mode(m: &Mode) -> &'static str {
     match m {
         Mode::Write => "write",
         Mode::Read => "read",
         Mode::CounterUpdate => "counter_update",
         Mode::CounterRead => "counter_read",
         Mode::Scan => "scan",
+        Mode::Delete => "delete",
+        Mode::Prepare => "prepare",
     }
 }
 
@@ -574,7 +1999,9 @@ pub enum WorkloadType {
     Sequential,
     Uniform,
     Timeseries,
+    Timebucket,
     Scan,
+    Prepare,
 }
 
 fn parse_workload(s: &str) -> Result<WorkloadType> {
@@ -582,37 +2009,169 @@ fn parse_workload(s: &str) -> Result<WorkloadType> {
         "sequential" => Ok(WorkloadType::Sequential),
         "uniform" => Ok(WorkloadType::Uniform),
         "timeseries" => Ok(WorkloadType::Timeseries),
-        // scan workload cannot be specified through CLI
+        "timebucket" => Ok(WorkloadType::Timebucket),
+        // scan and prepare workloads cannot be specified through CLI
         "" => Err(anyhow::anyhow!("workload type needs to be specified")),
         _ => Err(anyhow::anyhow!("unknown workload type: {}", s)),
     }
 }
 
-fn show_workload(w: &WorkloadType) -> &'static str {
+pub(crate) fn show_workload(w: &WorkloadType) -> &'static str {
     match w {
         WorkloadType::Sequential => "sequential",
         WorkloadType::Uniform => "uniform",
         WorkloadType::Timeseries => "timeseries",
+        WorkloadType::Timebucket => "timebucket",
         WorkloadType::Scan => "scan",
+        WorkloadType::Prepare => "prepare",
     }
 }
 
+const CONSISTENCY_LEVELS: &[(&str, Consistency)] = &[
+    ("any", Consistency::Any),
+    ("one", Consistency::One),
+    ("two", Consistency::Two),
+    ("three", Consistency::Three),
+    ("quorum", Consistency::Quorum),
+    ("all", Consistency::All),
+    ("local_quorum", Consistency::LocalQuorum),
+    ("each_quorum", Consistency::EachQuorum),
+    ("local_one", Consistency::LocalOne),
+    ("serial", Consistency::Serial),
+    ("local_serial", Consistency::LocalSerial),
+];
+
 fn parse_consistency_level(s: &str) -> Result<Consistency> {
-    let level = match s {
-        "any" => Consistency::Any,
-        "one" => Consistency::One,
-        "two" => Consistency::Two,
-        "three" => Consistency::Three,
-        "quorum" => Consistency::Quorum,
-        "all" => Consistency::All,
-        "local_quorum" => Consistency::LocalQuorum,
-        "each_quorum" => Consistency::EachQuorum,
-        "local_one" => Consistency::LocalOne,
-        "serial" => Consistency::Serial,
-        "local_serial" => Consistency::LocalSerial,
-        _ => return Err(anyhow::anyhow!("Unknown consistency level: {}", s)),
-    };
-    Ok(level)
+    CONSISTENCY_LEVELS
+        .iter()
+        .find(|(name, _)| *name == s)
+        .map(|(_, level)| *level)
+        .ok_or_else(|| {
+            let names: Vec<_> = CONSISTENCY_LEVELS.iter().map(|(name, _)| *name).collect();
+            anyhow::anyhow!(
+                "Unknown consistency level: {}, expected one of: {}",
+                s,
+                names.join(", ")
+            )
+        })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Partitioner {
+    Murmur3,
+    Random,
+}
+
+fn show_partitioner(p: &Partitioner) -> &'static str {
+    match p {
+        Partitioner::Murmur3 => "murmur3",
+        Partitioner::Random => "random",
+    }
+}
+
+/// A keyspace targeted by the benchmark, see -keyspaces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyspaceTarget {
+    pub name: String,
+    pub weight: u64,
+    pub replication_factor: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableSelection {
+    RoundRobin,
+    Hash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadMode {
+    Interleaved,
+    Contiguous,
+}
+
+fn parse_load_mode(s: &str) -> Result<LoadMode> {
+    match s {
+        "interleaved" => Ok(LoadMode::Interleaved),
+        "contiguous" => Ok(LoadMode::Contiguous),
+        _ => Err(anyhow::anyhow!("Unknown load mode: {}", s)),
+    }
+}
+
+fn show_load_mode(m: &LoadMode) -> &'static str {
+    match m {
+        LoadMode::Interleaved => "interleaved",
+        LoadMode::Contiguous => "contiguous",
+    }
+}
+
+const COMPRESSIONS: &[(&str, Compression)] =
+    &[("snappy", Compression::Snappy), ("lz4", Compression::Lz4)];
+
+fn parse_compression(s: &str) -> Result<Compression> {
+    COMPRESSIONS
+        .iter()
+        .find(|(name, _)| *name == s)
+        .map(|(_, c)| *c)
+        .ok_or_else(|| {
+            let names: Vec<_> = COMPRESSIONS.iter().map(|(name, _)| *name).collect();
+            anyhow::anyhow!(
+                "Unknown compression: {}, expected one of: {}",
+                s,
+                names.join(", ")
+            )
+        })
+}
+
+fn show_compression(c: &Compression) -> &'static str {
+    match c {
+        Compression::Snappy => "snappy",
+        Compression::Lz4 => "lz4",
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessOrder {
+    Sequential,
+    Shuffled,
+}
+
+fn parse_access_order(s: &str) -> Result<AccessOrder> {
+    match s {
+        "sequential" => Ok(AccessOrder::Sequential),
+        "shuffled" => Ok(AccessOrder::Shuffled),
+        _ => Err(anyhow::anyhow!("Unknown access order: {}", s)),
+    }
+}
+
+fn show_access_order(o: &AccessOrder) -> &'static str {
+    match o {
+        AccessOrder::Sequential => "sequential",
+        AccessOrder::Shuffled => "shuffled",
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CkOrder {
+    Ascending,
+    Descending,
+    Shuffled,
+}
+
+fn parse_ck_order(s: &str) -> Result<CkOrder> {
+    match s {
+        "ascending" => Ok(CkOrder::Ascending),
+        "descending" => Ok(CkOrder::Descending),
+        "shuffled" => Ok(CkOrder::Shuffled),
+        _ => Err(anyhow::anyhow!("Unknown clustering key order: {}", s)),
+    }
+}
+
+fn show_ck_order(o: &CkOrder) -> &'static str {
+    match o {
+        CkOrder::Ascending => "ascending",
+        CkOrder::Descending => "descending",
+        CkOrder::Shuffled => "shuffled",
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -645,20 +2204,518 @@ fn show_consistency_level(cl: &Consistency) -> &'static str {
     }
 }
 
-fn parse_host_selection_policy(s: &str) -> Result<Arc<dyn LoadBalancingPolicy>> {
+const DEFAULT_CQL_PORT: u16 = 9042;
+
+// Normalizes a node address to the host:port form, using the given default port
+// if the port is missing. IPv6 addresses can be given either bare
+// or in brackets, which is mandatory if a port is provided.
+fn parse_node(s: &str, default_port: u16) -> Result<String> {
+    let parse_port = |port: &str| -> Result<u16> {
+        port.parse()
+            .with_context(|| format!("Invalid port in node address: {}", s))
+    };
+
+    if let Some(rest) = s.strip_prefix('[') {
+        let (addr, port) = rest
+            .split_once(']')
+            .with_context(|| format!("Missing closing bracket in node address: {}", s))?;
+        let addr: Ipv6Addr = addr
+            .parse()
+            .with_context(|| format!("Invalid IPv6 address in node address: {}", s))?;
+        let port = match port {
+            "" => default_port,
+            _ => match port.strip_prefix(':') {
+                Some(port) => parse_port(port)?,
+                None => return Err(anyhow::anyhow!("Invalid node address: {}", s)),
+            },
+        };
+        return Ok(format!("[{}]:{}", addr, port));
+    }
+
+    match s.matches(':').count() {
+        0 => Ok(format!("{}:{}", s, default_port)),
+        1 => {
+            let (host, port) = s.split_once(':').unwrap();
+            anyhow::ensure!(!host.is_empty(), "Missing host in node address: {}", s);
+            Ok(format!("{}:{}", host, parse_port(port)?))
+        }
+        // Multiple colons are only valid in a bare IPv6 address
+        _ => {
+            let addr: Ipv6Addr = s.parse().with_context(|| {
+                format!(
+                    "Invalid node address: {}; IPv6 addresses with a port must be bracketed",
+                    s
+                )
+            })?;
+            Ok(format!("[{}]:{}", addr, default_port))
+        }
+    }
+}
+
+fn parse_token_range(s: &str) -> Result<TokenRange> {
+    let parse = || -> Result<TokenRange> {
+        let (start, end) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("expected start:end"))?;
+        Ok(TokenRange {
+            start: start.parse()?,
+            end: end.parse()?,
+        })
+    };
+    parse().with_context(|| format!("Invalid token range: {}", s))
+}
+
+// Read when the username is given, but the password flag isn't
+const PASSWORD_ENV_VAR: &str = "SCYLLA_BENCH_PASSWORD";
+
+// Reads the username and password, refusing the files which other users can read,
+// as the point of the file is to keep the password away from them
+fn read_credentials_file(path: &str) -> Result<(String, String)> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open the credentials file {}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = file.metadata()?.permissions().mode();
+        anyhow::ensure!(
+            mode & 0o004 == 0,
+            "The credentials file {} must not be readable by other users (mode {:o})",
+            path,
+            mode & 0o777,
+        );
+    }
+    let contents = std::io::read_to_string(file)
+        .with_context(|| format!("Failed to read the credentials file {}", path))?;
+    parse_credentials(&contents).with_context(|| format!("Invalid credentials file {}", path))
+}
+
+fn parse_credentials(contents: &str) -> Result<(String, String)> {
+    let (mut username, mut password) = (None, None);
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // The line isn't included in the errors, as it might contain the password
+        let (key, value) = line
+            .split_once('=')
+            .with_context(|| format!("line {} is not of the form key=value", idx + 1))?;
+        let target = match key.trim() {
+            "username" => &mut username,
+            "password" => &mut password,
+            _ => anyhow::bail!("line {} has an unknown key", idx + 1),
+        };
+        anyhow::ensure!(target.is_none(), "line {} repeats a key", idx + 1);
+        *target = Some(value.trim().to_owned());
+    }
+    match (username, password) {
+        (Some(username), Some(password)) => Ok((username, password)),
+        _ => Err(anyhow::anyhow!("both username and password must be given")),
+    }
+}
+
+fn parse_keyspaces(s: &str, default_replication_factor: i64) -> Result<Vec<KeyspaceTarget>> {
+    let parse = |spec: &str| -> Result<KeyspaceTarget> {
+        let mut parts = spec.split(':');
+        let name = parts.next().unwrap_or_default();
+        anyhow::ensure!(!name.is_empty(), "missing keyspace name");
+        let weight = match parts.next() {
+            Some(weight) => weight.parse()?,
+            None => 1,
+        };
+        anyhow::ensure!(weight > 0, "the weight must be greater than zero");
+        let replication_factor = match parts.next() {
+            Some(rf) => rf.parse()?,
+            None => default_replication_factor,
+        };
+        anyhow::ensure!(
+            replication_factor > 0,
+            "the replication factor must be greater than zero",
+        );
+        anyhow::ensure!(parts.next().is_none(), "too many fields");
+        Ok(KeyspaceTarget {
+            name: name.to_owned(),
+            weight,
+            replication_factor,
+        })
+    };
+
+    let keyspaces = s
+        .split(',')
+        .map(|spec| parse(spec).with_context(|| format!("Invalid keyspace: {}", spec)))
+        .collect::<Result<Vec<_>>>()?;
+    for (idx, ks) in keyspaces.iter().enumerate() {
+        anyhow::ensure!(
+            !keyspaces[..idx].iter().any(|other| other.name == ks.name),
+            "Keyspace {} is given multiple times",
+            ks.name,
+        );
+    }
+    Ok(keyspaces)
+}
+
+fn parse_error_ratio_limit(max_ratio: &str, window: u64) -> Result<Option<ErrorRatioLimit>> {
+    let max_ratio: f64 = max_ratio
+        .parse()
+        .with_context(|| format!("invalid max-error-ratio: {}", max_ratio))?;
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&max_ratio),
+        "max-error-ratio must be between 0 and 1",
+    );
+    if max_ratio == 0.0 {
+        return Ok(None);
+    }
+    anyhow::ensure!(window > 0, "error-ratio-window must be greater than zero");
+    Ok(Some(ErrorRatioLimit {
+        max_ratio,
+        window: window as usize,
+    }))
+}
+
+fn parse_summary_format(
+    rate_units: &str,
+    latency_units: &str,
+    precision: u64,
+) -> Result<SummaryFormat> {
+    let rate_unit = match rate_units {
+        "" => RateUnit::One,
+        "k" => RateUnit::Thousands,
+        "m" => RateUnit::Millions,
+        "auto" => RateUnit::Auto,
+        s => {
+            return Err(anyhow::anyhow!(
+                "Unsupported summary rate units: {}; supported units are: k, m, auto",
+                s
+            ))
+        }
+    };
+    let latency_unit = match latency_units {
+        "" => None,
+        "ns" => Some(LatencyUnit::Nanoseconds),
+        "us" => Some(LatencyUnit::Microseconds),
+        "ms" => Some(LatencyUnit::Milliseconds),
+        "s" => Some(LatencyUnit::Seconds),
+        s => {
+            return Err(anyhow::anyhow!(
+                "Unsupported summary latency units: {}; supported units are: ns, us, ms, s",
+                s
+            ))
+        }
+    };
+    anyhow::ensure!(
+        precision <= 17,
+        "summary-precision must be at most 17 significant figures",
+    );
+    Ok(SummaryFormat {
+        rate_unit,
+        latency_unit,
+        significant_figures: (precision > 0).then_some(precision as usize),
+    })
+}
+
+fn parse_misrouted_fraction(s: &str) -> Result<f64> {
+    let fraction: f64 = s
+        .parse()
+        .with_context(|| format!("invalid misrouted-fraction: {}", s))?;
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&fraction),
+        "misrouted-fraction must be between 0 and 1",
+    );
+    Ok(fraction)
+}
+
+const HOST_SELECTION_POLICIES: &[&str] = &[
+    "round-robin",
+    "token-aware",
+    "dc-aware-round-robin",
+    "token-aware-dc-aware",
+    "dc-aware:name-of-local-dc",
+];
+
+// Returns the local datacenter of the policy, if it's dc-aware,
+// and whether the policy is token-aware.
+fn parse_host_selection_policy(s: &str, datacenter: &str) -> Result<(Option<String>, bool)> {
     // host-pool is unsupported
-    let policy: Arc<dyn LoadBalancingPolicy> = match s {
-        "round-robin" => DefaultPolicy::builder().token_aware(false).build(),
-        "token-aware" => DefaultPolicy::builder().token_aware(true).build(),
+    let (local_dc, token_aware) = match s {
+        "round-robin" => (None, false),
+        "token-aware" => (None, true),
+        "dc-aware-round-robin" => (Some(datacenter), false),
+        "token-aware-dc-aware" => (Some(datacenter), true),
         // dc-aware is unimplemented in the original s-b, so here is
         // my interpretation of it
         _ => match s.strip_prefix("dc-aware:") {
-            Some(local_dc) => DefaultPolicy::builder()
-                .token_aware(false)
-                .prefer_datacenter(local_dc.to_owned())
-                .build(),
-            None => return Err(anyhow::anyhow!("Unknown host selection policy: {}", s)),
+            Some(local_dc) => {
+                anyhow::ensure!(
+                    datacenter.is_empty() || datacenter == local_dc,
+                    "datacenter {} contradicts the host selection policy {}",
+                    datacenter,
+                    s,
+                );
+                (Some(local_dc), false)
+            }
+            None => anyhow::bail!(
+                "Unknown host selection policy: {}, expected one of: {}",
+                s,
+                HOST_SELECTION_POLICIES.join(", "),
+            ),
         },
     };
-    Ok(policy)
+    match local_dc {
+        Some(local_dc) => anyhow::ensure!(
+            !local_dc.is_empty(),
+            "host selection policy {} requires the datacenter to be set",
+            s,
+        ),
+        None => anyhow::ensure!(
+            datacenter.is_empty(),
+            "datacenter requires a dc-aware host selection policy",
+        ),
+    }
+    Ok((local_dc.map(str::to_owned), token_aware))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_selection_policy() {
+        let parse = |s, dc| parse_host_selection_policy(s, dc).unwrap();
+        assert_eq!(parse("round-robin", ""), (None, false));
+        assert_eq!(parse("token-aware", ""), (None, true));
+        assert_eq!(
+            parse("dc-aware-round-robin", "dc1"),
+            (Some("dc1".to_owned()), false)
+        );
+        assert_eq!(
+            parse("token-aware-dc-aware", "dc1"),
+            (Some("dc1".to_owned()), true)
+        );
+        assert_eq!(parse("dc-aware:dc1", ""), (Some("dc1".to_owned()), false));
+        assert_eq!(
+            parse("dc-aware:dc1", "dc1"),
+            (Some("dc1".to_owned()), false)
+        );
+
+        assert!(parse_host_selection_policy("dc-aware-round-robin", "").is_err());
+        assert!(parse_host_selection_policy("token-aware", "dc1").is_err());
+        assert!(parse_host_selection_policy("dc-aware:dc1", "dc2").is_err());
+        let err = parse_host_selection_policy("host-pool", "")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("token-aware-dc-aware"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_compression() {
+        assert_eq!(parse_compression("snappy").unwrap(), Compression::Snappy);
+        assert_eq!(parse_compression("lz4").unwrap(), Compression::Lz4);
+
+        let err = parse_compression("zstd").unwrap_err().to_string();
+        assert!(err.contains("snappy, lz4"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_consistency_level() {
+        let expected = [
+            ("any", Consistency::Any),
+            ("one", Consistency::One),
+            ("two", Consistency::Two),
+            ("three", Consistency::Three),
+            ("quorum", Consistency::Quorum),
+            ("all", Consistency::All),
+            ("local_quorum", Consistency::LocalQuorum),
+            ("each_quorum", Consistency::EachQuorum),
+            ("local_one", Consistency::LocalOne),
+            ("serial", Consistency::Serial),
+            ("local_serial", Consistency::LocalSerial),
+        ];
+        for (name, level) in expected {
+            assert_eq!(parse_consistency_level(name).unwrap(), level, "{}", name);
+        }
+
+        let err = parse_consistency_level("LOCAL_ONE")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("local_one"), "{}", err);
+        assert!(parse_consistency_level("").is_err());
+    }
+
+    #[test]
+    fn test_parse_node() {
+        let good = [
+            ("127.0.0.1", "127.0.0.1:9042"),
+            ("127.0.0.1:19042", "127.0.0.1:19042"),
+            ("scylla-node1", "scylla-node1:9042"),
+            (
+                "scylla-node1.example.com:9043",
+                "scylla-node1.example.com:9043",
+            ),
+            ("::1", "[::1]:9042"),
+            ("fe80::1:2", "[fe80::1:2]:9042"),
+            ("[::1]", "[::1]:9042"),
+            ("[::1]:19042", "[::1]:19042"),
+            (
+                "[2001:db8::ff00:42:8329]:9042",
+                "[2001:db8::ff00:42:8329]:9042",
+            ),
+        ];
+        for (input, expected) in good {
+            assert_eq!(
+                parse_node(input, DEFAULT_CQL_PORT).unwrap(),
+                expected,
+                "input: {}",
+                input
+            );
+        }
+
+        let bad = [
+            "127.0.0.1:port",
+            "127.0.0.1:99999",
+            ":9042",
+            "[::1",
+            "[::1]9042",
+            "[::1]:",
+            "[127.0.0.1]:9042",
+            "::1:9042:x",
+        ];
+        for input in bad {
+            assert!(
+                parse_node(input, DEFAULT_CQL_PORT).is_err(),
+                "input: {}",
+                input
+            );
+        }
+
+        // Only the nodes without a port use the default one
+        assert_eq!(parse_node("10.0.0.1", 19042).unwrap(), "10.0.0.1:19042");
+        assert_eq!(parse_node("::1", 19042).unwrap(), "[::1]:19042");
+        assert_eq!(parse_node("[::1]", 19042).unwrap(), "[::1]:19042");
+        assert_eq!(parse_node("10.0.0.1:9042", 19042).unwrap(), "10.0.0.1:9042");
+    }
+
+    #[test]
+    fn test_parse_token_range() {
+        assert_eq!(
+            parse_token_range("-100:200").unwrap(),
+            TokenRange {
+                start: -100,
+                end: 200
+            },
+        );
+        assert_eq!(
+            parse_token_range("-9223372036854775808:-4611686018427387904").unwrap(),
+            TokenRange {
+                start: i64::MIN,
+                end: i64::MIN / 2
+            },
+        );
+        assert!(parse_token_range("100").is_err());
+        assert!(parse_token_range("a:b").is_err());
+        assert!(parse_token_range("1:2:3").is_err());
+    }
+
+    #[test]
+    fn test_parse_keyspaces() {
+        let ks = |name: &str, weight, replication_factor| KeyspaceTarget {
+            name: name.to_owned(),
+            weight,
+            replication_factor,
+        };
+        assert_eq!(
+            parse_keyspaces("tenant_a:3:2,tenant_b:1,tenant_c", 3).unwrap(),
+            [
+                ks("tenant_a", 3, 2),
+                ks("tenant_b", 1, 3),
+                ks("tenant_c", 1, 3)
+            ],
+        );
+
+        assert!(parse_keyspaces("", 3).is_err());
+        assert!(parse_keyspaces("a,,b", 3).is_err());
+        assert!(parse_keyspaces("a:0", 3).is_err());
+        assert!(parse_keyspaces("a:1:0", 3).is_err());
+        assert!(parse_keyspaces("a:x", 3).is_err());
+        assert!(parse_keyspaces("a:1:1:1", 3).is_err());
+        assert!(parse_keyspaces("a:1,a:2", 3).is_err());
+    }
+
+    #[test]
+    fn test_parse_credentials() {
+        assert_eq!(
+            parse_credentials("# bench user\nusername = bench\npassword=s3cr=t\n").unwrap(),
+            ("bench".to_owned(), "s3cr=t".to_owned()),
+        );
+
+        assert!(parse_credentials("username=bench\n").is_err());
+        assert!(parse_credentials("username=bench\npassword\n").is_err());
+        assert!(parse_credentials("username=a\nusername=b\npassword=c\n").is_err());
+        assert!(parse_credentials("user=a\npassword=c\n").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_credentials_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join("cql_stress_sb_credentials_test");
+        std::fs::write(&path, "username=bench\npassword=secret\n").unwrap();
+        let path_str = path.to_str().unwrap();
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(
+            read_credentials_file(path_str).unwrap(),
+            ("bench".to_owned(), "secret".to_owned()),
+        );
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(read_credentials_file(path_str).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_summary_format() {
+        assert_eq!(
+            parse_summary_format("", "", 0).unwrap(),
+            SummaryFormat::default()
+        );
+        assert_eq!(
+            parse_summary_format("auto", "ms", 3).unwrap(),
+            SummaryFormat {
+                rate_unit: RateUnit::Auto,
+                latency_unit: Some(LatencyUnit::Milliseconds),
+                significant_figures: Some(3),
+            },
+        );
+
+        assert!(parse_summary_format("g", "", 0).is_err());
+        assert!(parse_summary_format("", "min", 0).is_err());
+        assert!(parse_summary_format("", "", 18).is_err());
+    }
+
+    #[test]
+    fn test_parse_misrouted_fraction() {
+        assert_eq!(parse_misrouted_fraction("0").unwrap(), 0.0);
+        assert_eq!(parse_misrouted_fraction("0.25").unwrap(), 0.25);
+        assert_eq!(parse_misrouted_fraction("1").unwrap(), 1.0);
+
+        assert!(parse_misrouted_fraction("1.5").is_err());
+        assert!(parse_misrouted_fraction("-0.1").is_err());
+        assert!(parse_misrouted_fraction("half").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_ratio_limit() {
+        assert!(parse_error_ratio_limit("0", 1000).unwrap().is_none());
+        assert!(parse_error_ratio_limit("0", 0).unwrap().is_none());
+
+        let limit = parse_error_ratio_limit("0.05", 500).unwrap().unwrap();
+        assert_eq!(limit.max_ratio, 0.05);
+        assert_eq!(limit.window, 500);
+
+        assert!(parse_error_ratio_limit("1.5", 1000).is_err());
+        assert!(parse_error_ratio_limit("-0.1", 1000).is_err());
+        assert!(parse_error_ratio_limit("five", 1000).is_err());
+        assert!(parse_error_ratio_limit("0.1", 0).is_err());
+    }
 }
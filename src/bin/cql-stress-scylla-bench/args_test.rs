@@ -1,5 +1,7 @@
 const DATA: &str = include_str!("args_test.in");
 
+use std::time::Duration;
+
 use crate::args::parse_scylla_bench_args;
 
 #[test]
@@ -24,3 +26,137 @@ fn test_example_sets() {
     println!("Successes: {}, failures: {}", success_count, failure_count);
     assert_eq!(failure_count, 0);
 }
+
+#[test]
+fn test_no_contact_points() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+
+    assert!(parse("scylla-bench -workload=uniform -mode=write -nodes=").is_none());
+    assert!(parse("scylla-bench -workload=uniform -mode=write -nodes=,,").is_none());
+    assert!(parse("scylla-bench -workload=uniform -mode=write -nodes=127.0.0.1,").is_some());
+}
+
+#[test]
+fn test_tls_host_verification() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+
+    // One-way TLS, the client doesn't present a certificate
+    assert!(
+        parse("scylla-bench -workload=uniform -mode=write -tls -tls-ca-cert-file=ca.pem").is_some()
+    );
+    assert!(parse(
+        "scylla-bench -workload=uniform -mode=write -tls -tls-ca-cert-file=ca.pem \
+        -tls-host-verification"
+    )
+    .is_none());
+    assert!(parse(
+        "scylla-bench -workload=uniform -mode=write -tls -tls-ca-cert-file=ca.pem \
+        -tls-host-verification -tls-server-name=scylla.example.com"
+    )
+    .is_some());
+}
+
+#[test]
+fn test_credentials_given_together() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+
+    assert!(parse("scylla-bench -workload=uniform -mode=write -password=secret").is_none());
+    assert!(
+        parse("scylla-bench -workload=uniform -mode=write -username=bench -password=secret")
+            .is_some()
+    );
+    // The password might be given by the environment instead
+    if std::env::var_os("SCYLLA_BENCH_PASSWORD").is_none() {
+        assert!(parse("scylla-bench -workload=uniform -mode=write -username=bench").is_none());
+    }
+}
+
+#[test]
+fn test_page_size_must_be_positive() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+
+    assert!(parse("scylla-bench -mode=scan -page-size=1").is_some());
+    assert!(parse("scylla-bench -mode=scan -page-size=0").is_none());
+    assert!(parse("scylla-bench -mode=scan -page-size=-5").is_none());
+    assert!(parse("scylla-bench -mode=scan -page-size=4294967296").is_none());
+}
+
+#[test]
+fn test_delete_range_size_must_be_positive() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+
+    assert!(parse("scylla-bench -workload=sequential -mode=delete -delete-range-size=1").is_some());
+    assert!(parse("scylla-bench -workload=sequential -mode=delete -delete-range-size=0").is_none());
+}
+
+#[test]
+fn test_warmup_requires_duration() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+
+    let args = parse("scylla-bench -workload=uniform -mode=read -duration=1m -warmup=10s").unwrap();
+    assert_eq!(args.warmup, Some(Duration::from_secs(10)));
+    assert_eq!(
+        parse("scylla-bench -workload=uniform -mode=read -duration=1m")
+            .unwrap()
+            .warmup,
+        None
+    );
+    assert!(parse("scylla-bench -workload=uniform -mode=read -warmup=10s").is_none());
+}
+
+#[test]
+fn test_retries() {
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+    let max_retries = |s: &str| parse(s).unwrap().max_retries_per_op;
+
+    assert_eq!(
+        max_retries("scylla-bench -workload=uniform -mode=write"),
+        u64::MAX
+    );
+    assert_eq!(
+        max_retries("scylla-bench -workload=uniform -mode=write -retries=0"),
+        0
+    );
+    assert_eq!(
+        max_retries("scylla-bench -workload=uniform -mode=write -retries=3"),
+        3
+    );
+    assert_eq!(
+        max_retries("scylla-bench -workload=uniform -mode=write -error-at-row-limit=3"),
+        2
+    );
+    assert!(parse("scylla-bench -workload=uniform -mode=write -retries=-2").is_none());
+    assert!(
+        parse("scylla-bench -workload=uniform -mode=write -retries=3 -error-at-row-limit=3")
+            .is_none()
+    );
+}
+
+#[test]
+fn test_connection_count() {
+    use scylla::transport::session::PoolSize;
+
+    let parse = |s: &str| parse_scylla_bench_args(s.split_ascii_whitespace(), false);
+    let pool_size = |s: &str| parse(s).unwrap().pool_size();
+
+    assert!(matches!(
+        pool_size("scylla-bench -workload=uniform -mode=write"),
+        PoolSize::PerShard(n) if n.get() == 1
+    ));
+    assert!(matches!(
+        pool_size("scylla-bench -workload=uniform -mode=write -connection-count=0"),
+        PoolSize::PerShard(n) if n.get() == 1
+    ));
+    assert!(matches!(
+        pool_size("scylla-bench -workload=uniform -mode=write -shard-connection-count=2"),
+        PoolSize::PerShard(n) if n.get() == 2
+    ));
+    assert!(matches!(
+        pool_size("scylla-bench -workload=uniform -mode=write -connection-count=8"),
+        PoolSize::PerHost(n) if n.get() == 8
+    ));
+    assert!(parse(
+        "scylla-bench -workload=uniform -mode=write -connection-count=8 -shard-connection-count=2"
+    )
+    .is_none());
+}
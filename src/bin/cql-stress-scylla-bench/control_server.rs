@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use cql_stress::run::RunController;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::warn;
+
+// Requests with a larger head are rejected; the commands are tiny
+const MAX_REQUEST_HEAD_SIZE: usize = 8192;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Pause,
+    Resume,
+    SetRate(f64),
+}
+
+/// Starts listening for the HTTP requests controlling the run on localhost.
+///
+/// Supported requests:
+/// - `POST /pause` - pauses the run,
+/// - `POST /resume` - resumes the paused run,
+/// - `POST /rate?ops=N` - changes the maximum rate to N op/s.
+pub async fn serve(port: u16, ctrl: Arc<RunController>) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("Failed to listen on the control port {}", port))?;
+
+    // Don't care about the leaking task, it won't prevent the runtime
+    // from being stopped.
+    tokio::task::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let ctrl = Arc::clone(&ctrl);
+                    tokio::task::spawn(async move {
+                        if let Err(err) = handle_connection(stream, &ctrl).await {
+                            warn!(error = %err, "control connection error");
+                        }
+                    });
+                }
+                Err(err) => warn!(error = %err, "failed to accept a control connection"),
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn handle_connection(mut stream: TcpStream, ctrl: &RunController) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        anyhow::ensure!(read > 0, "connection closed before the end of the request");
+        head.extend_from_slice(&buf[..read]);
+        anyhow::ensure!(head.len() <= MAX_REQUEST_HEAD_SIZE, "request too large");
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let request_line = head.lines().next().unwrap_or_default();
+    let (status, body) = match parse_request_line(request_line) {
+        Ok(Command::Pause) => {
+            ctrl.pause();
+            ("200 OK", "paused".to_owned())
+        }
+        Ok(Command::Resume) => {
+            ctrl.resume();
+            ("200 OK", "resumed".to_owned())
+        }
+        Ok(Command::SetRate(ops)) => match ctrl.set_rate(ops) {
+            Ok(()) => ("200 OK", format!("rate set to {} op/s", ops)),
+            Err(err) => ("409 Conflict", err.to_string()),
+        },
+        Err(err) => err,
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\
+        Connection: close\r\n\r\n{}\n",
+        status,
+        body.len() + 1,
+        body,
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn parse_request_line(line: &str) -> Result<Command, (&'static str, String)> {
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(("400 Bad Request", "malformed request".to_owned()));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let command = match path {
+        "/pause" => Command::Pause,
+        "/resume" => Command::Resume,
+        "/rate" => {
+            let ops = query
+                .split('&')
+                .find_map(|param| param.strip_prefix("ops="))
+                .and_then(|ops| ops.parse::<f64>().ok())
+                .filter(|ops| ops.is_finite() && *ops > 0.0)
+                .ok_or((
+                    "400 Bad Request",
+                    "expected a positive rate in the ops parameter".to_owned(),
+                ))?;
+            Command::SetRate(ops)
+        }
+        _ => return Err(("404 Not Found", format!("unknown path {}", path))),
+    };
+    if method != "POST" {
+        return Err((
+            "405 Method Not Allowed",
+            "only POST is supported".to_owned(),
+        ));
+    }
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        assert_eq!(
+            parse_request_line("POST /pause HTTP/1.1"),
+            Ok(Command::Pause)
+        );
+        assert_eq!(
+            parse_request_line("POST /resume HTTP/1.1"),
+            Ok(Command::Resume)
+        );
+        assert_eq!(
+            parse_request_line("POST /rate?ops=2500 HTTP/1.1"),
+            Ok(Command::SetRate(2500.0))
+        );
+        assert_eq!(
+            parse_request_line("POST /rate?x=1&ops=0.5 HTTP/1.1"),
+            Ok(Command::SetRate(0.5))
+        );
+
+        let status = |line| parse_request_line(line).unwrap_err().0;
+        assert_eq!(status("POST /rate HTTP/1.1"), "400 Bad Request");
+        assert_eq!(status("POST /rate?ops=-1 HTTP/1.1"), "400 Bad Request");
+        assert_eq!(status("POST /rate?ops=fast HTTP/1.1"), "400 Bad Request");
+        assert_eq!(status("GET /pause HTTP/1.1"), "405 Method Not Allowed");
+        assert_eq!(status("POST /stop HTTP/1.1"), "404 Not Found");
+        assert_eq!(status(""), "400 Bad Request");
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+
+/// Counts the updates applied to each counter row by all of the workers,
+/// so that counter_read can validate the exact values of the counters.
+///
+/// Each update of the row with clustering key `ck` increments the counters
+/// `c1..=c5` by `ck + 1..=ck + 5`, so after `n` updates `ci == n * (ck + i)`.
+/// An update which failed might have been applied anyway, so failed updates
+/// are counted separately and allow the number of updates to be anywhere
+/// between the applied updates and the applied and failed updates combined.
+///
+/// The counts are carried between the runs in the counter state file,
+/// one row per line: `pk ck applied failed`.
+#[derive(Debug, Default)]
+pub struct CounterUpdateTracker {
+    rows: RwLock<HashMap<(i64, i64), UpdateCounts>>,
+}
+
+#[derive(Debug, Default)]
+struct UpdateCounts {
+    applied: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl CounterUpdateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_update(&self, pk: i64, ck: i64, succeeded: bool) {
+        self.add(pk, ck, succeeded as u64, !succeeded as u64);
+    }
+
+    fn add(&self, pk: i64, ck: i64, applied: u64, failed: u64) {
+        let increment = |counts: &UpdateCounts| {
+            counts.applied.fetch_add(applied, Ordering::Relaxed);
+            counts.failed.fetch_add(failed, Ordering::Relaxed);
+        };
+        // Most updates hit a row which was already seen,
+        // so avoid taking the write lock for them
+        if let Some(counts) = self.rows.read().get(&(pk, ck)) {
+            increment(counts);
+            return;
+        }
+        increment(self.rows.write().entry((pk, ck)).or_default());
+    }
+
+    pub fn validate(&self, pk: i64, ck: i64, counters: [i64; 5]) -> Result<()> {
+        let (applied, failed) = match self.rows.read().get(&(pk, ck)) {
+            Some(counts) => (
+                counts.applied.load(Ordering::Relaxed),
+                counts.failed.load(Ordering::Relaxed),
+            ),
+            None => (0, 0),
+        };
+
+        let updates = counters[1].wrapping_sub(counters[0]);
+        let consistent = counters
+            .iter()
+            .zip(1..)
+            .all(|(c, i)| *c == updates.wrapping_mul(ck + i));
+        anyhow::ensure!(
+            consistent && (applied as i64..=(applied + failed) as i64).contains(&updates),
+            "Corrupt counter data: expected between {} and {} updates, \
+            pk: {}, ck: {}, c1: {}, c2: {}, c3: {}, c4: {}, c5: {}",
+            applied,
+            applied + failed,
+            pk,
+            ck,
+            counters[0],
+            counters[1],
+            counters[2],
+            counters[3],
+            counters[4],
+        );
+        Ok(())
+    }
+
+    /// Adds the counts from the counter state file to the tracked ones.
+    pub fn load(&self, path: &str) -> Result<()> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open the counter state file {}", path))?;
+        self.load_from(BufReader::new(file))
+            .with_context(|| format!("Failed to read the counter state file {}", path))
+    }
+
+    fn load_from(&self, input: impl BufRead) -> Result<()> {
+        for (idx, line) in input.lines().enumerate() {
+            let line = line?;
+            let parse = || -> Result<_> {
+                let fields = line
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<i64>, _>>()?;
+                let &[pk, ck, applied, failed] = fields.as_slice() else {
+                    anyhow::bail!("expected 4 fields, got {}", fields.len());
+                };
+                anyhow::ensure!(applied >= 0 && failed >= 0, "negative update count");
+                Ok((pk, ck, applied as u64, failed as u64))
+            };
+            let (pk, ck, applied, failed) =
+                parse().with_context(|| format!("Invalid line {}: {}", idx + 1, line))?;
+            self.add(pk, ck, applied, failed);
+        }
+        Ok(())
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create the counter state file {}", path))?;
+        self.write_to(&mut BufWriter::new(file))
+    }
+
+    fn write_to(&self, out: &mut impl Write) -> Result<()> {
+        let rows = self.rows.read();
+        // Sort the rows so that the output is stable
+        let rows: BTreeMap<_, _> = rows.iter().collect();
+        for ((pk, ck), counts) in rows {
+            writeln!(
+                out,
+                "{} {} {} {}",
+                pk,
+                ck,
+                counts.applied.load(Ordering::Relaxed),
+                counts.failed.load(Ordering::Relaxed),
+            )?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn counters(ck: i64, updates: i64) -> [i64; 5] {
+        [1, 2, 3, 4, 5].map(|i| updates * (ck + i))
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let tracker = Arc::new(CounterUpdateTracker::new());
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let tracker = Arc::clone(&tracker);
+                std::thread::spawn(move || {
+                    for ck in 0..100 {
+                        tracker.record_update(1, ck % 10, true);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        for ck in 0..10 {
+            tracker.validate(1, ck, counters(ck, 40)).unwrap();
+            assert!(tracker.validate(1, ck, counters(ck, 39)).is_err());
+        }
+        tracker.validate(2, 0, counters(0, 0)).unwrap();
+        assert!(tracker.validate(2, 0, counters(0, 1)).is_err());
+    }
+
+    #[test]
+    fn test_failed_updates() {
+        let tracker = CounterUpdateTracker::new();
+        tracker.record_update(1, 3, true);
+        tracker.record_update(1, 3, false);
+        tracker.record_update(1, 3, false);
+
+        assert!(tracker.validate(1, 3, counters(3, 0)).is_err());
+        for updates in 1..=3 {
+            tracker.validate(1, 3, counters(3, updates)).unwrap();
+        }
+        assert!(tracker.validate(1, 3, counters(3, 4)).is_err());
+
+        let mut inconsistent = counters(3, 2);
+        inconsistent[4] += 1;
+        assert!(tracker.validate(1, 3, inconsistent).is_err());
+    }
+
+    #[test]
+    fn test_counter_state_roundtrip() {
+        let tracker = CounterUpdateTracker::new();
+        tracker.record_update(2, 0, true);
+        tracker.record_update(1, 5, true);
+        tracker.record_update(1, 5, false);
+
+        let mut state = Vec::new();
+        tracker.write_to(&mut state).unwrap();
+        assert_eq!(String::from_utf8_lossy(&state), "1 5 1 1\n2 0 1 0\n");
+
+        // Loading adds to the counts, so that consecutive runs accumulate
+        let loaded = CounterUpdateTracker::new();
+        loaded.record_update(2, 0, true);
+        loaded.load_from(state.as_slice()).unwrap();
+        loaded.validate(2, 0, counters(0, 2)).unwrap();
+        loaded.validate(1, 5, counters(5, 2)).unwrap();
+
+        assert!(loaded.load_from("1 2 3\n".as_bytes()).is_err());
+        assert!(loaded.load_from("1 2 -3 0\n".as_bytes()).is_err());
+    }
+}
@@ -108,6 +108,10 @@ impl GoValue for String {
 
 impl GoValue for Duration {
     fn parse(s: &str) -> Result<Self> {
+        // The original scylla-bench also accepts a bare number of seconds
+        if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+            return Ok(Duration::from_secs(s.parse()?));
+        }
         super::strconv::parse_duration(s)
     }
 
@@ -537,6 +541,35 @@ mod tests {
         assert!(parse(&["-var=thing"]).is_err());
     }
 
+    #[test]
+    fn test_duration_var() {
+        let parse = make_single_flag_parser(|set| {
+            set.duration_var("var", Duration::from_secs(1), "duration flag")
+        });
+
+        // Go duration syntax
+        assert_eq!(parse(&[]).unwrap(), Duration::from_secs(1));
+        assert_eq!(parse(&["-var=30s"]).unwrap(), Duration::from_secs(30));
+        assert_eq!(parse(&["-var", "5m"]).unwrap(), Duration::from_secs(5 * 60));
+        assert_eq!(
+            parse(&["-var=1h30m"]).unwrap(),
+            Duration::from_secs(90 * 60)
+        );
+        assert_eq!(parse(&["-var=250ms"]).unwrap(), Duration::from_millis(250));
+        assert_eq!(parse(&["-var=0"]).unwrap(), Duration::ZERO);
+
+        // Bare number of seconds
+        assert_eq!(parse(&["-var=45"]).unwrap(), Duration::from_secs(45));
+        assert_eq!(parse(&["-var", "3600"]).unwrap(), Duration::from_secs(3600));
+
+        // Invalid values
+        let err = parse(&["-var=5x"]).unwrap_err();
+        assert!(err.to_string().contains("5x"), "{}", err);
+        assert!(parse(&["-var=1.5"]).is_err());
+        assert!(parse(&["-var=-5"]).is_err());
+        assert!(parse(&["-var="]).is_err());
+    }
+
     #[test]
     fn test_custom_var() {
         #[derive(Copy, Clone, Debug, PartialEq, Eq)]
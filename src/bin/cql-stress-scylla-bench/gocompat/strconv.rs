@@ -161,10 +161,19 @@ pub fn parse_duration(mut s: &str) -> Result<Duration> {
         let (unit, rest) = s.split_at(unit_end);
         s = rest;
 
+        anyhow::ensure!(!unit.is_empty(), "Missing unit in duration: {}", original);
         let unit_multiplicand = UNIT_MULTIPLICANDS
             .iter()
             .find_map(|(uname, mult)| (&unit == uname).then_some(mult))
-            .ok_or_else(|| anyhow::anyhow!("Invalid duration unit: {}", unit))?;
+            .ok_or_else(|| {
+                let units: Vec<_> = UNIT_MULTIPLICANDS.iter().map(|(uname, _)| *uname).collect();
+                anyhow::anyhow!(
+                    "Invalid unit {} in duration {}; supported units are: {}",
+                    unit,
+                    original,
+                    units.join(", "),
+                )
+            })?;
 
         // Converting floats to ints when the float is too big to fit is UB,
         // therefore before converting we check if the currently parsed part
@@ -557,6 +566,10 @@ mod tests {
             // Invalid suffixes
             "100days",
             "1min",
+            "5x",
+            // Missing unit, accepted only by the duration flags
+            "10",
+            "1.5",
             // Non-decimal numbers
             "0x123ms",
             "0o123ms",
@@ -2,48 +2,92 @@
 extern crate async_trait;
 
 mod args;
+mod control_server;
+mod counter_tracker;
 mod distribution;
 mod gocompat;
 mod histogram_log_writer;
+mod misrouting;
+mod node_latency;
 mod operation;
+mod reprepare_counter;
+#[cfg(feature = "results-db")]
+mod results_db;
+mod row_checksums;
+mod scan_coverage;
+mod stability;
 pub(crate) mod stats;
+mod sweep;
+mod timing_log;
 mod workload;
 
 #[cfg(test)]
 mod args_test;
 
+use std::collections::HashMap;
+use std::io::{LineWriter, Write};
+use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use futures::future;
 use openssl::ssl::{SslContext, SslContextBuilder, SslFiletype, SslMethod, SslVerifyMode};
+use scylla::transport::errors::NewSessionError;
 use scylla::transport::session::PoolSize;
+use scylla::transport::session_builder::{GenericSessionBuilder, SessionBuilderKind};
 use scylla::ExecutionProfile;
-use scylla::{transport::Compression, Session, SessionBuilder};
-use tracing_subscriber::EnvFilter;
+use scylla::{CloudSessionBuilder, Session, SessionBuilder};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
 
-use cql_stress::configuration::{Configuration, OperationFactory};
+use cql_stress::configuration::{Configuration, OperationFactory, RetryFilter};
 use cql_stress::run::RunController;
 use cql_stress::sharded_stats::{Stats as _, StatsFactory as _};
 
 use crate::args::{Mode, ScyllaBenchArgs, WorkloadType};
+use crate::gocompat::strconv::format_duration;
 use crate::operation::counter_update::CounterUpdateOperationFactory;
+use crate::operation::delete::DeleteOperationFactory;
+use crate::operation::prepare::PrepareOperationFactory;
 use crate::operation::read::{ReadKind, ReadOperationFactory};
 use crate::operation::scan::ScanOperationFactory;
 use crate::operation::write::WriteOperationFactory;
-use crate::stats::{ShardedStats, StatsFactory, StatsPrinter};
+use crate::reprepare_counter::ReprepareCounter;
+use crate::scan_coverage::ScanCoverage;
+use crate::stability::StabilityDetector;
+use crate::stats::{
+    format_result_line, write_json_summary, LatencyType, ShardedStats, Stats, StatsFactory,
+    StatsPrinter,
+};
+use crate::timing_log::TimingRecorder;
 use crate::workload::{
-    SequentialConfig, SequentialFactory, TimeseriesReadConfig, TimeseriesReadFactory,
-    TimeseriesWriteConfig, TimeseriesWriteFactory, UniformConfig, UniformFactory, WorkloadFactory,
+    SequentialConfig, SequentialFactory, SequentialProgress, TimeBucketConfig,
+    TimeBucketReadFactory, TimeBucketWriteFactory, TimeseriesReadConfig, TimeseriesReadFactory,
+    TimeseriesWriteConfig, TimeseriesWriteFactory, TokenRangeFactory, UniformConfig,
+    UniformFactory, WorkloadFactory,
 };
 
+// Returned when the run completed without errors, but didn't meet
+// the targets requested with -enforce-targets.
+const TARGETS_NOT_MET_EXIT_CODE: i32 = 2;
+
 // TODO: Return exit code
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_ansi(false)
-        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("warn")))
+    let reprepare_counter = ReprepareCounter::new();
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_filter(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("warn"))),
+        )
+        .with(
+            reprepare_counter
+                .clone()
+                .with_filter(ReprepareCounter::filter()),
+        )
         .init();
 
     #[cfg(debug_assertions)]
@@ -64,12 +108,82 @@ async fn main() -> Result<()> {
     let stats_factory = Arc::new(StatsFactory::new(&sb_config));
     let sharded_stats = Arc::new(ShardedStats::new(Arc::clone(&stats_factory)));
 
-    let run_config = prepare(sb_config.clone(), Arc::clone(&sharded_stats))
-        .await
-        .context("Failed to prepare the benchmark")?;
+    // Ranges are tracked only in the scan mode
+    let tracked_ranges = match sb_config.mode {
+        Mode::Scan => sb_config.range_count,
+        _ => 0,
+    };
+    let scan_coverage = Arc::new(ScanCoverage::new(tracked_ranges));
+
+    if let Some(tracker) = &sb_config.counter_tracker {
+        // Consecutive counter_update runs accumulate the updates
+        // of the previous ones, if they are given the same file
+        if sb_config.mode == Mode::CounterRead
+            || std::path::Path::new(&sb_config.counter_state_file).exists()
+        {
+            tracker.load(&sb_config.counter_state_file)?;
+        }
+    }
+
+    let (run_config, session) = prepare(
+        sb_config.clone(),
+        Arc::clone(&sharded_stats),
+        Arc::clone(&scan_coverage),
+    )
+    .await
+    .context("Failed to prepare the benchmark")?;
+
+    let result = run_benchmark(
+        run_config,
+        &session,
+        &sb_config,
+        &stats_factory,
+        &sharded_stats,
+        &scan_coverage,
+        &reprepare_counter,
+    )
+    .await;
+
+    if sb_config.drop_after {
+        drop_schema_after_run(&session, &sb_config).await;
+    }
+
+    if !result? {
+        std::process::exit(TARGETS_NOT_MET_EXIT_CODE);
+    }
+    Ok(())
+}
+
+// Runs the benchmark and prints its results. Returns whether the targets
+// requested with -enforce-targets were met.
+async fn run_benchmark(
+    mut run_config: Configuration,
+    session: &Session,
+    sb_config: &ScyllaBenchArgs,
+    stats_factory: &StatsFactory,
+    sharded_stats: &ShardedStats,
+    scan_coverage: &ScanCoverage,
+    reprepare_counter: &ReprepareCounter,
+) -> Result<bool> {
+    if sb_config.sweep_steps > 0 {
+        let steps = sweep::run_sweep(run_config, sb_config, sharded_stats).await?;
+        sweep::print_sweep(&steps, &mut std::io::stdout())?;
+        return Ok(true);
+    }
 
     let mut combined_stats = stats_factory.create();
 
+    let timing_recorder = if !sb_config.timing_log.is_empty() {
+        let recorder = Arc::new(TimingRecorder::new(&sb_config.timing_log)?);
+        run_config.operation_hook = Some(recorder.clone());
+        Some(recorder)
+    } else {
+        None
+    };
+
+    let run_start = Instant::now();
+    // The results cover only the operations started after the warmup
+    let measured_start = run_start + sb_config.warmup.unwrap_or(Duration::ZERO);
     let (ctrl, run_finished) = cql_stress::run::run(run_config);
     let ctrl = Arc::new(ctrl);
 
@@ -77,39 +191,284 @@ async fn main() -> Result<()> {
     // from being stopped.
     tokio::task::spawn(stop_on_signal(Arc::clone(&ctrl)));
 
+    if sb_config.control_port > 0 {
+        control_server::serve(sb_config.control_port, Arc::clone(&ctrl)).await?;
+    }
+
     let mut printer = StatsPrinter::new(
         sb_config.measure_latency.then_some(sb_config.latency_type),
+        sb_config.output_format,
         (!sb_config.hdr_latency_file.is_empty()).then_some(sb_config.hdr_latency_file.as_str()),
     )
-    .await?;
+    .await?
+    .with_ascii_histogram(sb_config.latency_histogram_buckets as usize)
+    .with_summary_format(sb_config.summary_format)
+    .with_warmup(sb_config.warmup.unwrap_or(Duration::ZERO));
+    let mut stability_detector = sb_config.stop_when_stable.then(|| {
+        StabilityDetector::new(sb_config.stability_intervals, sb_config.stability_tolerance)
+    });
     let mut ticker = tokio::time::interval(Duration::from_secs(1));
     futures::pin_mut!(run_finished);
 
     // Skip the first tick, which is immediate
     ticker.tick().await;
 
-    printer.print_header(&mut std::io::stdout())?;
+    let mut interval_out: Box<dyn Write> = if sb_config.output_file.is_empty() {
+        Box::new(std::io::stdout())
+    } else {
+        // Flush every line, so that the file can be followed during the run
+        let file = std::fs::File::create(&sb_config.output_file).with_context(|| {
+            format!("Failed to create the output file {}", sb_config.output_file)
+        })?;
+        Box::new(LineWriter::new(file))
+    };
+
+    printer.print_header(&mut interval_out)?;
 
     loop {
         tokio::select! {
             _ = ticker.tick() => {
                 let partial_stats = sharded_stats.get_combined_and_clear();
-                printer.print_partial(&partial_stats, &mut std::io::stdout()).await?;
+                printer.print_partial(&partial_stats, &mut interval_out).await?;
                 combined_stats.combine(&partial_stats);
+
+                let warming_up = Instant::now() < measured_start;
+                if let Some(detector) = stability_detector.as_mut().filter(|_| !warming_up) {
+                    let p99 = partial_stats
+                        .get_histogram(sb_config.latency_type)
+                        .map(|h| h.value_at_quantile(0.99));
+                    if detector.observe(partial_stats.operations, p99) {
+                        eprintln!("The run has stabilized, stopping");
+                        stability_detector = None;
+                        ctrl.ask_to_stop();
+                    }
+                }
             }
             result = &mut run_finished => {
+                let mut targets_met = true;
+                // Wait for the writes sent without waiting for the acknowledgement,
+                // so that their results, including the errors, are in the final stats
+                if let Some(in_flight) = &sb_config.in_flight_writes {
+                    let _permits = in_flight
+                        .acquire_many(sb_config.fire_and_forget as u32)
+                        .await?;
+                }
+                // Combine stats for the last time
+                let partial_stats = sharded_stats.get_combined_and_clear();
+                combined_stats.combine(&partial_stats);
+                if let Some(tracker) = &sb_config.row_checksums {
+                    (combined_stats.data_checksum, combined_stats.checksummed_rows) =
+                        tracker.summary();
+                }
+                if !sb_config.json_summary.is_empty() {
+                    // Written even if the run failed, with the results gathered so far
+                    let error = result.as_ref().err().map(|err| format!("{:#}", err));
+                    write_json_summary_file(
+                        &sb_config.json_summary,
+                        &combined_stats,
+                        sb_config.measure_latency.then_some(sb_config.latency_type),
+                        measured_start.elapsed(),
+                        error.as_deref(),
+                    )?;
+                }
                 if result.is_ok() {
-                    // Combine stats for the last time
-                    let partial_stats = sharded_stats.get_combined_and_clear();
-                    combined_stats.combine(&partial_stats);
+                    combined_stats.reprepares = reprepare_counter.get();
                     printer.print_final(&combined_stats, &mut std::io::stdout())?;
+                    if sb_config.mode == Mode::Scan {
+                        scan_coverage.summary().print(&mut std::io::stdout())?;
+                    }
+                    if let Some(tracker) = &sb_config.node_latency {
+                        tracker.print_summary(
+                            &node_datacenters(session),
+                            sb_config.local_dc.as_deref(),
+                            &mut std::io::stdout(),
+                        )?;
+                    }
+                    #[cfg(feature = "results-db")]
+                    if !sb_config.results_db.is_empty() {
+                        let summary = results_db::RunSummary::new(
+                            sb_config,
+                            &combined_stats,
+                            measured_start.elapsed(),
+                        );
+                        results_db::append_run(&sb_config.results_db, &summary)?;
+                    }
+
+                    if sb_config.enforce_targets {
+                        let elapsed = measured_start.elapsed();
+                        if let Err(err) = check_targets(sb_config, &combined_stats, elapsed) {
+                            eprintln!("Targets not met: {}", err);
+                            targets_met = false;
+                        }
+                    }
+                }
+                if let Some(recorder) = &timing_recorder {
+                    recorder.finish()?;
+                }
+                if let Some(tracker) = &sb_config.counter_tracker {
+                    if sb_config.mode == Mode::CounterUpdate {
+                        tracker.write(&sb_config.counter_state_file)?;
+                    }
                 }
-                return result.context("An error occurred during the benchmark");
+                // Printed even if the run failed, so that the scripts
+                // always find the line, together with the status of the run
+                let status = match (&result, targets_met) {
+                    (Err(_), _) => "error",
+                    (Ok(_), false) => "targets_not_met",
+                    (Ok(_), true) => "ok",
+                };
+                println!(
+                    "{}",
+                    format_result_line(
+                        &combined_stats,
+                        sb_config.measure_latency.then_some(sb_config.latency_type),
+                        measured_start.elapsed(),
+                        status,
+                    )
+                );
+                result.context("An error occurred during the benchmark")?;
+                return Ok(targets_met);
             }
         }
     }
 }
 
+// Maps the IP addresses of the nodes known to the driver to their datacenters.
+fn node_datacenters(session: &Session) -> HashMap<IpAddr, String> {
+    session
+        .get_cluster_data()
+        .get_nodes_info()
+        .iter()
+        .filter_map(|node| Some((node.address.ip(), node.datacenter.clone()?)))
+        .collect()
+}
+
+// Prints the shards of each node known to the driver, which the connection
+// pool opens the configured number of connections to, per shard or per host.
+// The driver doesn't expose the shard of each of the opened connections,
+// so this only shows the configured layout, derived from the shard count
+// in each node's sharding info, and not the connections actually opened:
+// the nodes without it (e.g. Cassandra, or Scylla behind a proxy) get
+// connections which aren't pinned to any shard.
+fn print_shard_coverage(session: &Session, args: &ScyllaBenchArgs) {
+    println!("Shard coverage (configured, not the connections actually opened):");
+    for node in session.get_cluster_data().get_nodes_info() {
+        let datacenter = node.datacenter.as_deref().unwrap_or("unknown");
+        let (connections, distribution) = match args.pool_size() {
+            PoolSize::PerShard(count) => (count, "to each of"),
+            PoolSize::PerHost(count) => (count, "in total, spread over"),
+        };
+        match node.sharder() {
+            Some(sharder) => println!(
+                "  {} (dc {}):\t{} shards, {} connection(s) {} shards 0..{}",
+                node.address,
+                datacenter,
+                sharder.nr_shards.get(),
+                connections,
+                distribution,
+                sharder.nr_shards.get() - 1,
+            ),
+            None => println!(
+                "  {} (dc {}):\tno sharding info, {} connection(s) not pinned to shards",
+                node.address, datacenter, connections,
+            ),
+        }
+    }
+}
+
+// Fails if some of the keyspaces are replicated to every node known
+// to the driver, in which case there are no non-replica coordinators
+// and the misrouted requests would silently go to the replicas.
+fn check_non_replicas_exist(session: &Session, args: &ScyllaBenchArgs) -> Result<()> {
+    let node_count = session.get_cluster_data().get_nodes_info().len() as i64;
+    for keyspace in &args.keyspaces {
+        anyhow::ensure!(
+            keyspace.replication_factor < node_count,
+            "misrouted-fraction requires nodes which aren't replicas, but the keyspace {} \
+            has replication factor {} with {} node(s) in the cluster",
+            keyspace.name,
+            keyspace.replication_factor,
+            node_count,
+        );
+    }
+    Ok(())
+}
+
+// Checks that the run performed all operations defined by the workload
+// and sustained the requested rate.
+fn check_targets(args: &ScyllaBenchArgs, stats: &Stats, elapsed: Duration) -> Result<()> {
+    if let Some(expected_ops) = expected_operation_count(args) {
+        anyhow::ensure!(
+            stats.operations >= expected_ops,
+            "performed {} operations out of {}",
+            stats.operations,
+            expected_ops,
+        );
+    }
+
+    if args.maximum_rate > 0 {
+        // The scans are limited by the rate of the rows they stream
+        let performed = match args.mode {
+            Mode::Scan => stats.clustering_rows,
+            _ => stats.operations,
+        };
+        let achieved_rate = performed as f64 / elapsed.as_secs_f64();
+        let required_rate = args.maximum_rate as f64 * (100 - args.target_tolerance) as f64 / 100.0;
+        anyhow::ensure!(
+            achieved_rate >= required_rate,
+            "achieved rate of {:.2} ops/s is below the required {:.2} ops/s",
+            achieved_rate,
+            required_rate,
+        );
+    }
+
+    Ok(())
+}
+
+// Returns the number of operations after which the workload finishes,
+// if it is known upfront.
+fn expected_operation_count(args: &ScyllaBenchArgs) -> Option<u64> {
+    // Only the partitions in the token range are visited
+    if args.token_range.is_some() {
+        return None;
+    }
+    if args.workload == WorkloadType::Timebucket && args.mode == Mode::Write {
+        let ops_per_bucket = args
+            .clustering_row_count
+            .div_ceil(args.rows_per_request.max(1));
+        return Some(args.partition_count * ops_per_bucket * args.time_bucket_count);
+    }
+    if args.workload != WorkloadType::Sequential || args.iterations == 0 {
+        return None;
+    }
+    let rows_per_op = match args.mode {
+        Mode::Write => args.rows_per_request,
+        Mode::CounterUpdate => args.counter_batch_size,
+        // A range covering the whole partition deletes it in one operation
+        Mode::Delete => args.delete_range_size.min(args.clustering_row_count),
+        Mode::Read if args.in_restriction => args.rows_per_request,
+        Mode::Read | Mode::CounterRead if !args.no_lower_bound => 1,
+        _ => return None,
+    };
+    let ops_per_pk = args.clustering_row_count.div_ceil(rows_per_op.max(1));
+    // The partitions before resume-from were visited by the interrupted run
+    let visited_pks = (args.partition_count * args.iterations).saturating_sub(args.resume_from);
+    Some(visited_pks * ops_per_pk)
+}
+
+fn write_json_summary_file(
+    path: &str,
+    stats: &Stats,
+    latency_type: Option<LatencyType>,
+    time: Duration,
+    error: Option<&str>,
+) -> Result<()> {
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create the JSON summary file {}", path))?;
+    write_json_summary(stats, latency_type, time, error, &mut file)
+        .with_context(|| format!("Failed to write the JSON summary file {}", path))
+}
+
 async fn stop_on_signal(runner: Arc<RunController>) {
     tokio::signal::ctrl_c().await.unwrap();
     runner.ask_to_stop();
@@ -118,45 +477,153 @@ async fn stop_on_signal(runner: Arc<RunController>) {
     runner.abort();
 }
 
-async fn prepare(args: Arc<ScyllaBenchArgs>, stats: Arc<ShardedStats>) -> Result<Configuration> {
-    let mut builder = SessionBuilder::new().known_nodes(&args.nodes);
+// Retries connecting until the cluster becomes reachable,
+// for at most the time given by wait-for-cluster.
+async fn connect_waiting_for_cluster(args: &ScyllaBenchArgs) -> Result<Session> {
+    const MAX_BACKOFF: Duration = Duration::from_secs(10);
 
-    builder = builder.pool_size(PoolSize::PerShard(args.shard_connection_count));
-
-    if !args.username.is_empty() && !args.password.is_empty() {
-        builder = builder.user(&args.username, &args.password);
+    let deadline = Instant::now() + args.wait_for_cluster;
+    let mut backoff = Duration::from_millis(100);
+    loop {
+        match connect(args).await {
+            Err(err) if err.is::<NewSessionError>() && Instant::now() + backoff < deadline => {
+                println!(
+                    "Failed to connect to the cluster, retrying in {}: {}",
+                    format_duration(backoff),
+                    err,
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+            }
+            result => return result,
+        }
     }
+}
 
-    if args.tls_encryption {
-        let ssl_ctx = generate_ssl_context(&args)?;
-        builder = builder.ssl_context(Some(ssl_ctx));
-    }
+async fn connect(args: &ScyllaBenchArgs) -> Result<Session> {
+    let session = if args.cloud_config_path.is_empty() {
+        let mut builder = SessionBuilder::new().known_nodes(&args.nodes);
+
+        if args.tls_encryption {
+            let ssl_ctx = generate_ssl_context(args)?;
+            builder = builder.ssl_context(Some(ssl_ctx));
+        }
+        // Only the default mode builder takes the credentials,
+        // in the cloud mode they come from the bundle
+        if !args.username.is_empty() && !args.password.is_empty() {
+            builder = builder.user(&args.username, &args.password);
+        }
+
+        configure_session_builder(builder, args).build().await?
+    } else {
+        // The nodes, the SNI proxy address, the TLS configuration
+        // and the credentials are all taken from the bundle.
+        let builder = CloudSessionBuilder::new(&args.cloud_config_path)
+            .context("Failed to load the cloud config bundle")?;
+        configure_session_builder(builder, args).build().await?
+    };
+    Ok(session)
+}
 
-    if args.client_compression {
-        builder = builder.compression(Some(Compression::Snappy));
+async fn prepare(
+    args: Arc<ScyllaBenchArgs>,
+    stats: Arc<ShardedStats>,
+    scan_coverage: Arc<ScanCoverage>,
+) -> Result<(Configuration, Arc<Session>)> {
+    // Session building includes the discovery of the topology
+    let connect_start = Instant::now();
+    let session = Arc::new(connect_waiting_for_cluster(&args).await?);
+    let connect_time = connect_start.elapsed();
+
+    if args.shard_coverage {
+        print_shard_coverage(&session, &args);
+    }
+    if args.misrouting.is_some() {
+        check_non_replicas_exist(&session, &args)?;
     }
 
-    let default_exec_profile = ExecutionProfile::builder()
-        .load_balancing_policy(Arc::clone(&args.host_selection_policy))
-        .build();
-    builder = builder.default_execution_profile_handle(default_exec_profile.into_handle());
+    match configure(&session, args.clone(), stats, scan_coverage, connect_time).await {
+        Ok(config) => Ok((config, session)),
+        Err(err) => {
+            // The keyspace might have been created before the failure
+            if args.drop_after {
+                drop_schema_after_run(&session, &args).await;
+            }
+            Err(err)
+        }
+    }
+}
 
-    let session = builder.build().await?;
-    let session = Arc::new(session);
+// Creates the schema and prepares the operations on the connected session.
+async fn configure(
+    session: &Arc<Session>,
+    args: Arc<ScyllaBenchArgs>,
+    stats: Arc<ShardedStats>,
+    scan_coverage: Arc<ScanCoverage>,
+    connect_time: Duration,
+) -> Result<Configuration> {
+    let schema_start = Instant::now();
+    create_schema(session, &args).await?;
+    let schema_time = schema_start.elapsed();
+
+    let prepare_start = Instant::now();
+    let operation_factory =
+        create_operation_factory(Arc::clone(session), stats, scan_coverage, Arc::clone(&args))
+            .await?;
+    let prepare_time = prepare_start.elapsed();
 
-    create_schema(&session, &args).await?;
-    let operation_factory = create_operation_factory(session, stats, Arc::clone(&args)).await?;
+    println!(
+        "Setup:\t\t\t connected in {}, schema created in {}, statements prepared in {}",
+        format_duration(connect_time),
+        format_duration(schema_time),
+        format_duration(prepare_time),
+    );
 
     let max_duration = (args.test_duration > Duration::ZERO).then_some(args.test_duration);
     let rate_limit_per_second = (args.maximum_rate > 0).then_some(args.maximum_rate as f64);
+    let arrival_schedule = if args.replay_timing.is_empty() {
+        None
+    } else {
+        Some(timing_log::read_timing_log(&args.replay_timing)?)
+    };
 
-    Ok(Configuration {
+    let config = Configuration {
         max_duration,
+        warmup: args.warmup,
         concurrency: args.concurrency,
         rate_limit_per_second,
+        rate_jitter: (args.rate_jitter > 0).then_some(args.rate_jitter as f64 / 100.0),
+        arrival_schedule,
         operation_factory,
         max_retries_per_op: args.max_retries_per_op as usize,
-    })
+        worker_start_stagger: args.worker_start_stagger,
+        operation_hook: None,
+        error_ratio_limit: args.error_ratio_limit,
+        max_consecutive_errors: args.max_consecutive_errors,
+        retry_filter: args
+            .retry_on
+            .clone()
+            .map(|retry_on| retry_on as Arc<dyn RetryFilter>),
+    };
+    Ok(config)
+}
+
+fn configure_session_builder<K: SessionBuilderKind>(
+    mut builder: GenericSessionBuilder<K>,
+    args: &ScyllaBenchArgs,
+) -> GenericSessionBuilder<K> {
+    builder = builder.pool_size(args.pool_size());
+
+    builder = builder.compression(args.client_compression);
+
+    if let Some(keepalive) = args.keepalive {
+        builder = builder.keepalive_interval(keepalive);
+    }
+
+    let default_exec_profile = ExecutionProfile::builder()
+        .load_balancing_policy(Arc::clone(&args.host_selection_policy))
+        .build();
+    builder.default_execution_profile_handle(default_exec_profile.into_handle())
 }
 
 fn generate_ssl_context(args: &ScyllaBenchArgs) -> Result<SslContext> {
@@ -169,6 +636,9 @@ fn generate_ssl_context(args: &ScyllaBenchArgs) -> Result<SslContext> {
 
     if args.host_verification {
         context_builder.set_verify(SslVerifyMode::PEER);
+        context_builder
+            .verify_param_mut()
+            .set_host(&args.server_name)?;
     } else {
         context_builder.set_verify(SslVerifyMode::NONE);
     }
@@ -186,7 +656,7 @@ fn generate_ssl_context(args: &ScyllaBenchArgs) -> Result<SslContext> {
         context_builder.set_private_key_file(client_key_file, SslFiletype::PEM)?;
     }
 
-    // TODO: Set server name (for SNI)
+    // TODO: Send the server name as SNI
     // I'm afraid it is impossible to do with the current driver.
     // The hostname must be set on the Ssl object which is created
     // by the driver just before creating a connection, and is not available
@@ -195,35 +665,50 @@ fn generate_ssl_context(args: &ScyllaBenchArgs) -> Result<SslContext> {
     // I believe it's this method:
     // https://docs.rs/openssl/latest/openssl/ssl/struct.Ssl.html#method.set_hostname
 
-    // Silence "unused" warnings for now
-    let _ = &args.server_name;
-
     Ok(context_builder.build())
 }
 
 async fn create_schema(session: &Session, args: &ScyllaBenchArgs) -> Result<()> {
-    let create_keyspace_query_str = format!(
-        "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
-        {{'class': 'SimpleStrategy', 'replication_factor': {}}}",
-        args.keyspace_name, args.replication_factor,
-    );
-    session.query_unpaged(create_keyspace_query_str, ()).await?;
+    let mut tablets_options = Vec::new();
+    if let Some(enabled) = args.tablets {
+        tablets_options.push(format!("'enabled': {}", enabled));
+    }
+    if args.initial_tablets > 0 {
+        tablets_options.push(format!("'initial': {}", args.initial_tablets));
+    }
+    for keyspace in &args.keyspaces {
+        let mut create_keyspace_query_str = format!(
+            "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
+            {{'class': 'SimpleStrategy', 'replication_factor': {}}} \
+            AND durable_writes = {}",
+            keyspace.name, keyspace.replication_factor, args.durable_writes,
+        );
+        if !tablets_options.is_empty() {
+            create_keyspace_query_str +=
+                &format!(" AND tablets = {{{}}}", tablets_options.join(", "));
+        }
+        session.query_unpaged(create_keyspace_query_str, ()).await?;
+    }
     session.use_keyspace(&args.keyspace_name, true).await?;
     session.await_schema_agreement().await?;
 
-    let create_regular_table_query_str = format!(
-        "CREATE TABLE IF NOT EXISTS {} \
-        (pk bigint, ck bigint, v blob, PRIMARY KEY (pk, ck)) \
-        WITH compression = {{ }}",
-        args.table_name,
-    );
-    let q1 = session.query_unpaged(create_regular_table_query_str, ());
+    let compression = table_compression_str(args);
+    let compaction = table_compaction_str(args);
+    let q1 = future::try_join_all(args.table_names().into_iter().map(|table_name| {
+        let create_regular_table_query_str = format!(
+            "CREATE TABLE IF NOT EXISTS {} \
+            (pk bigint, ck bigint, v blob, PRIMARY KEY (pk, ck)) \
+            WITH compression = {}{}",
+            table_name, compression, compaction,
+        );
+        session.query_unpaged(create_regular_table_query_str, ())
+    }));
 
     let create_counter_table_query_str = format!(
         "CREATE TABLE IF NOT EXISTS {} \
         (pk bigint, ck bigint, c1 counter, c2 counter, c3 counter, c4 counter, c5 counter, PRIMARY KEY (pk, ck)) \
-        WITH compression = {{ }}",
-        args.counter_table_name,
+        WITH compression = {}",
+        args.counter_table_name, compression,
     );
     let q2 = session.query_unpaged(create_counter_table_query_str, ());
 
@@ -233,9 +718,48 @@ async fn create_schema(session: &Session, args: &ScyllaBenchArgs) -> Result<()>
     Ok(())
 }
 
+// The cleanup is best-effort and shouldn't hide the result of the run
+async fn drop_schema_after_run(session: &Session, args: &ScyllaBenchArgs) {
+    if let Err(err) = drop_schema(session, args).await {
+        eprintln!("Failed to drop the keyspace: {:?}", err);
+    }
+}
+
+async fn drop_schema(session: &Session, args: &ScyllaBenchArgs) -> Result<()> {
+    for keyspace in &args.keyspaces {
+        let drop_keyspace_query_str = format!("DROP KEYSPACE IF EXISTS {}", keyspace.name);
+        session.query_unpaged(drop_keyspace_query_str, ()).await?;
+    }
+    session.await_schema_agreement().await?;
+    Ok(())
+}
+
+fn table_compression_str(args: &ScyllaBenchArgs) -> String {
+    if args.compression_chunk_length_kb == 0 {
+        // Compression is disabled by default, like in the original scylla-bench
+        return "{ }".to_owned();
+    }
+    format!(
+        "{{'sstable_compression': 'LZ4Compressor', 'chunk_length_in_kb': {}}}",
+        args.compression_chunk_length_kb,
+    )
+}
+
+fn table_compaction_str(args: &ScyllaBenchArgs) -> String {
+    if args.twcs_window.is_zero() {
+        return String::new();
+    }
+    format!(
+        " AND compaction = {{'class': 'TimeWindowCompactionStrategy', \
+        'compaction_window_unit': 'MINUTES', 'compaction_window_size': {}}}",
+        args.twcs_window.as_secs() / 60,
+    )
+}
+
 async fn create_operation_factory(
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    scan_coverage: Arc<ScanCoverage>,
     args: Arc<ScyllaBenchArgs>,
 ) -> Result<Arc<dyn OperationFactory>> {
     match &args.mode {
@@ -276,13 +800,28 @@ async fn create_operation_factory(
             Ok(Arc::new(factory))
         }
         Mode::Scan => {
-            let factory = ScanOperationFactory::new(session, stats, args).await?;
+            let factory = ScanOperationFactory::new(session, stats, scan_coverage, args).await?;
+            Ok(Arc::new(factory))
+        }
+        Mode::Delete => {
+            let workload_factory = create_workload_factory(&args)?;
+            let factory =
+                DeleteOperationFactory::new(session, stats, workload_factory, args).await?;
             Ok(Arc::new(factory))
         }
+        Mode::Prepare => Ok(Arc::new(PrepareOperationFactory::new(session, stats, args))),
     }
 }
 
 fn create_workload_factory(args: &ScyllaBenchArgs) -> Result<Box<dyn WorkloadFactory>> {
+    let factory = create_key_workload_factory(args)?;
+    Ok(match args.token_range {
+        Some(range) => Box::new(TokenRangeFactory::new(factory, range)),
+        None => factory,
+    })
+}
+
+fn create_key_workload_factory(args: &ScyllaBenchArgs) -> Result<Box<dyn WorkloadFactory>> {
     match (&args.workload, &args.mode) {
         (WorkloadType::Sequential, _) => {
             let seq_config = SequentialConfig {
@@ -290,8 +829,23 @@ fn create_workload_factory(args: &ScyllaBenchArgs) -> Result<Box<dyn WorkloadFac
                 partition_offset: args.partition_offset,
                 pks: args.partition_count,
                 cks_per_pk: args.clustering_row_count,
+                load_mode: args.load_mode,
+                access_order: args.access_order,
+                ck_order: args.ck_order,
+                workers: args.concurrency,
+                resume_from: args.resume_from,
             };
-            Ok(Box::new(SequentialFactory::new(seq_config)?))
+            if args.print_pk_ranges {
+                println!("{}", seq_config.pk_ranges_line());
+            }
+            let factory = SequentialFactory::new(seq_config)?;
+            if !args.checkpoint_file.is_empty() {
+                tokio::task::spawn(write_checkpoints(
+                    factory.progress(),
+                    args.checkpoint_file.clone(),
+                ));
+            }
+            Ok(Box::new(factory))
         }
         (WorkloadType::Uniform, _) => {
             let uni_config = UniformConfig {
@@ -325,6 +879,15 @@ fn create_workload_factory(args: &ScyllaBenchArgs) -> Result<Box<dyn WorkloadFac
         (WorkloadType::Timeseries, _) => Err(anyhow::anyhow!(
             "Timeseries workload supports only write and read modes"
         )),
+        (WorkloadType::Timebucket, Mode::Write) => Ok(Box::new(TimeBucketWriteFactory::new(
+            time_bucket_config(args),
+        )?)),
+        (WorkloadType::Timebucket, Mode::Read) => Ok(Box::new(TimeBucketReadFactory::new(
+            time_bucket_config(args),
+        )?)),
+        (WorkloadType::Timebucket, _) => Err(anyhow::anyhow!(
+            "Timebucket workload supports only write and read modes"
+        )),
         (workload, mode) => {
             // TODO: Implement more later
             Err(anyhow::anyhow!(
@@ -335,3 +898,33 @@ fn create_workload_factory(args: &ScyllaBenchArgs) -> Result<Box<dyn WorkloadFac
         }
     }
 }
+
+fn time_bucket_config(args: &ScyllaBenchArgs) -> TimeBucketConfig {
+    TimeBucketConfig {
+        series: args.partition_count,
+        rows_per_bucket: args.clustering_row_count,
+        bucket_nanos: args.time_bucket.as_nanos() as u64,
+        bucket_count: args.time_bucket_count,
+        start_nanos: args.start_timestamp,
+    }
+}
+
+// Periodically stores the sequence number from which the sequential workload
+// can be resumed. The file is replaced atomically, so that it is never left
+// half-written if the tool gets killed.
+async fn write_checkpoints(progress: SequentialProgress, path: String) {
+    let tmp_path = format!("{}.tmp", path);
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticker.tick().await;
+        let seq = progress.completed_seq();
+        let result = async {
+            tokio::fs::write(&tmp_path, format!("{}\n", seq)).await?;
+            tokio::fs::rename(&tmp_path, &path).await
+        }
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(error = %err, path = %path, "failed to write the checkpoint file");
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rand::Rng;
+use scylla::execution_profile::ExecutionProfileHandle;
+use scylla::load_balancing::{FallbackPlan, LoadBalancingPolicy, RoutingInfo};
+use scylla::prepared_statement::PreparedStatement;
+use scylla::routing::Shard;
+use scylla::transport::{ClusterData, NodeRef};
+use scylla::ExecutionProfile;
+
+/// Sends a fraction of the requests to coordinators which aren't replicas
+/// of the requested partition, so that the latency of the extra hop saved
+/// by the token-aware routing can be measured within a single run.
+pub struct Misrouting {
+    fraction: f64,
+    profile: ExecutionProfileHandle,
+}
+
+impl Misrouting {
+    pub fn new(fraction: f64) -> Self {
+        let profile = ExecutionProfile::builder()
+            .load_balancing_policy(Arc::new(NonReplicaPolicy))
+            .build()
+            .into_handle();
+        Self { fraction, profile }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// Randomly picks whether the statement should be misrouted and if so,
+    /// makes it use the non-replica coordinators. Returns whether it was picked.
+    pub fn misroute(&self, statement: &mut PreparedStatement, rng: &mut impl Rng) -> bool {
+        if !rng.gen_bool(self.fraction) {
+            return false;
+        }
+        statement.set_execution_profile_handle(Some(self.profile.clone()));
+        true
+    }
+}
+
+/// Prefers the nodes which don't own the token of the request, in random order.
+/// The replicas are still tried last, so that the requests succeed even when
+/// the non-replicas are down. The run is refused upfront if every node
+/// is a replica, i.e. with RF equal to the number of nodes.
+#[derive(Debug)]
+struct NonReplicaPolicy;
+
+impl LoadBalancingPolicy for NonReplicaPolicy {
+    fn pick<'a>(
+        &'a self,
+        request: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> Option<(NodeRef<'a>, Option<Shard>)> {
+        self.fallback(request, cluster).next()
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        request: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> FallbackPlan<'a> {
+        let replicas: HashSet<_> = match (request.token, request.table) {
+            (Some(token), Some(table)) => cluster
+                .get_token_endpoints(table.ks_name(), table.table_name(), token)
+                .into_iter()
+                .map(|(node, _)| node.host_id)
+                .collect(),
+            _ => HashSet::new(),
+        };
+
+        let nodes = cluster.get_nodes_info();
+        let start = if nodes.is_empty() {
+            0
+        } else {
+            rand::thread_rng().gen_range(0..nodes.len())
+        };
+        let (non_replicas, replicas): (Vec<_>, Vec<_>) = nodes[start..]
+            .iter()
+            .chain(nodes[..start].iter())
+            .filter(|node| node.is_enabled())
+            .partition(|node| !replicas.contains(&node.host_id));
+        Box::new(
+            non_replicas
+                .into_iter()
+                .chain(replicas)
+                .map(|node| (node, None)),
+        )
+    }
+
+    fn name(&self) -> String {
+        "NonReplicaPolicy".to_owned()
+    }
+}
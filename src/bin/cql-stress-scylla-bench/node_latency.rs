@@ -0,0 +1,319 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use hdrhistogram::Histogram;
+use parking_lot::Mutex;
+use scylla::history::{AttemptId, HistoryListener, QueryId, SpeculativeId};
+use scylla::retry_policy::RetryDecision;
+use scylla::transport::errors::QueryError;
+use thread_local::ThreadLocal;
+
+use crate::gocompat::strconv::format_duration;
+
+tokio::task_local! {
+    // The attempts started by the requests of the current operation
+    // which haven't finished yet, see in_flight_scope
+    static IN_FLIGHT: RefCell<Vec<(usize, SocketAddr, Instant)>>;
+}
+
+/// Runs the requests of an operation, keeping their in-flight attempts
+/// local to it. If the future is dropped before the requests finish,
+/// e.g. by the operation deadline, the unfinished attempts are dropped with it.
+pub async fn in_flight_scope<T>(fut: impl Future<Output = T>) -> T {
+    IN_FLIGHT.scope(RefCell::new(Vec::new()), fut).await
+}
+
+/// Tracks the latency of requests grouped by the node which coordinated them.
+///
+/// Works as a history listener attached to the statements, so every attempt
+/// of a request, including retries and speculative executions, is attributed
+/// to the node it was sent to.
+#[derive(Debug, Default)]
+pub struct NodeLatencyTracker {
+    next_id: AtomicUsize,
+    // The in-flight attempts of the requests made outside in_flight_scope,
+    // e.g. by the tasks which the driver spawns to fetch the pages
+    in_flight: Mutex<HashMap<usize, (SocketAddr, Instant)>>,
+    // Sharded per thread, so that the requests don't contend on a single lock
+    nodes: ThreadLocal<Mutex<HashMap<SocketAddr, NodeStats>>>,
+}
+
+#[derive(Debug, Clone)]
+struct NodeStats {
+    latency: Histogram<u64>,
+    errors: u64,
+}
+
+impl NodeLatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_id(&self) -> usize {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn attempt_started(&self, attempt_id: usize, node: SocketAddr) {
+        let attempt = (attempt_id, node, Instant::now());
+        let in_scope = IN_FLIGHT.try_with(|in_flight| in_flight.borrow_mut().push(attempt));
+        if in_scope.is_err() {
+            self.in_flight.lock().insert(attempt_id, (node, attempt.2));
+        }
+    }
+
+    fn attempt_finished(&self, attempt_id: AttemptId, failed: bool) {
+        let in_scope = IN_FLIGHT
+            .try_with(|in_flight| {
+                let mut in_flight = in_flight.borrow_mut();
+                let idx = in_flight
+                    .iter()
+                    .position(|(id, _, _)| *id == attempt_id.0)?;
+                let (_, node, start) = in_flight.swap_remove(idx);
+                Some((node, start))
+            })
+            .ok()
+            .flatten();
+        let Some((node, start)) = in_scope.or_else(|| self.in_flight.lock().remove(&attempt_id.0))
+        else {
+            return;
+        };
+        self.record(node, start.elapsed(), failed);
+    }
+
+    fn record(&self, node: SocketAddr, latency: Duration, failed: bool) {
+        let mut nodes = self.nodes.get_or_default().lock();
+        let stats = nodes.entry(node).or_insert_with(|| NodeStats {
+            latency: Histogram::new(3).unwrap(),
+            errors: 0,
+        });
+        if failed {
+            stats.errors += 1;
+        } else {
+            stats.latency.saturating_record(latency.as_nanos() as u64);
+        }
+    }
+
+    // Combines the stats of the shards, sorted by the node so that the output is stable
+    fn combined(&self) -> BTreeMap<SocketAddr, NodeStats> {
+        let mut combined = BTreeMap::<SocketAddr, NodeStats>::new();
+        for shard in self.nodes.iter() {
+            for (node, stats) in shard.lock().iter() {
+                match combined.get_mut(node) {
+                    Some(total) => {
+                        total.latency.add(&stats.latency).unwrap();
+                        total.errors += stats.errors;
+                    }
+                    None => {
+                        combined.insert(*node, stats.clone());
+                    }
+                }
+            }
+        }
+        combined
+    }
+
+    /// Prints the latency of each node, along with its datacenter.
+    /// If the local datacenter is given, also prints how many requests
+    /// were coordinated by the nodes of the local and remote datacenters.
+    pub fn print_summary(
+        &self,
+        node_dcs: &HashMap<IpAddr, String>,
+        local_dc: Option<&str>,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        let nodes = self.combined();
+        let node_dc =
+            |node: &SocketAddr| node_dcs.get(&node.ip()).map_or("unknown", String::as_str);
+
+        writeln!(out, "Per-node latency:")?;
+        for (node, stats) in nodes.iter() {
+            let quantile = |q| Duration::from_nanos(stats.latency.value_at_quantile(q));
+            writeln!(
+                out,
+                "  {} ({}):\tops {}\terrors {}\tp50 {}\tp99 {}\tmax {}",
+                node,
+                node_dc(node),
+                stats.latency.len(),
+                stats.errors,
+                format_duration(quantile(0.5)),
+                format_duration(quantile(0.99)),
+                format_duration(Duration::from_nanos(stats.latency.max())),
+            )?;
+        }
+
+        if let Some(local_dc) = local_dc {
+            let (mut local, mut remote, mut unknown) = (0, 0, 0);
+            for (node, stats) in nodes.iter() {
+                let requests = stats.latency.len() + stats.errors;
+                match node_dcs.get(&node.ip()) {
+                    Some(dc) if dc == local_dc => local += requests,
+                    Some(_) => remote += requests,
+                    None => unknown += requests,
+                }
+            }
+            let percent = |count| 100.0 * count as f64 / (local + remote + unknown).max(1) as f64;
+            write!(
+                out,
+                "Coordinator DC:\t\tlocal {} ({:.1}%)\tremote {} ({:.1}%)",
+                local,
+                percent(local),
+                remote,
+                percent(remote),
+            )?;
+            // The nodes whose datacenter the driver doesn't know
+            // can't be told to be either local or remote
+            if unknown > 0 {
+                write!(out, "\tunknown {} ({:.1}%)", unknown, percent(unknown))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl HistoryListener for NodeLatencyTracker {
+    fn log_query_start(&self) -> QueryId {
+        QueryId(self.next_id())
+    }
+
+    fn log_query_success(&self, _query_id: QueryId) {}
+
+    fn log_query_error(&self, _query_id: QueryId, _error: &QueryError) {}
+
+    fn log_new_speculative_fiber(&self, _query_id: QueryId) -> SpeculativeId {
+        SpeculativeId(self.next_id())
+    }
+
+    fn log_attempt_start(
+        &self,
+        _query_id: QueryId,
+        _speculative_id: Option<SpeculativeId>,
+        node_addr: SocketAddr,
+    ) -> AttemptId {
+        let attempt_id = self.next_id();
+        self.attempt_started(attempt_id, node_addr);
+        AttemptId(attempt_id)
+    }
+
+    fn log_attempt_success(&self, attempt_id: AttemptId) {
+        self.attempt_finished(attempt_id, false);
+    }
+
+    fn log_attempt_error(
+        &self,
+        attempt_id: AttemptId,
+        _error: &QueryError,
+        _retry_decision: &RetryDecision,
+    ) {
+        self.attempt_finished(attempt_id, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_latency_tracker() {
+        let tracker = NodeLatencyTracker::new();
+        let node1: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let node2: SocketAddr = "127.0.0.2:9042".parse().unwrap();
+
+        let query = tracker.log_query_start();
+        let attempt = tracker.log_attempt_start(query, None, node1);
+        tracker.log_attempt_success(attempt);
+        tracker.log_query_success(query);
+
+        let query = tracker.log_query_start();
+        let attempt = tracker.log_attempt_start(query, None, node2);
+        tracker.log_attempt_error(
+            attempt,
+            &QueryError::TimeoutError,
+            &RetryDecision::RetryNextNode(None),
+        );
+        let attempt = tracker.log_attempt_start(query, None, node1);
+        tracker.log_attempt_success(attempt);
+        tracker.log_query_success(query);
+
+        tracker.record(node2, Duration::from_millis(10), false);
+
+        let nodes = tracker.combined();
+        assert_eq!(nodes[&node1].latency.len(), 2);
+        assert_eq!(nodes[&node1].errors, 0);
+        assert_eq!(nodes[&node2].latency.len(), 1);
+        assert_eq!(nodes[&node2].errors, 1);
+        assert!(tracker.in_flight.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_scope() {
+        let tracker = NodeLatencyTracker::new();
+        let node: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+
+        in_flight_scope(async {
+            let query = tracker.log_query_start();
+            let attempt = tracker.log_attempt_start(query, None, node);
+            tracker.log_attempt_success(attempt);
+        })
+        .await;
+        assert_eq!(tracker.combined()[&node].latency.len(), 1);
+
+        // An attempt abandoned together with its operation,
+        // e.g. because of the deadline, isn't left behind
+        let abandoned = in_flight_scope(async {
+            let query = tracker.log_query_start();
+            tracker.log_attempt_start(query, None, node);
+            std::future::pending::<()>().await;
+        });
+        let timeout = tokio::time::timeout(Duration::from_millis(1), abandoned).await;
+        assert!(timeout.is_err());
+        assert!(tracker.in_flight.lock().is_empty());
+        assert_eq!(tracker.combined()[&node].latency.len(), 1);
+    }
+
+    #[test]
+    fn test_coordinator_dc_summary() {
+        let tracker = NodeLatencyTracker::new();
+        let node1: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let node2: SocketAddr = "127.0.0.2:9042".parse().unwrap();
+        for _ in 0..3 {
+            tracker.record(node1, Duration::from_millis(1), false);
+        }
+        tracker.record(node2, Duration::from_millis(10), false);
+
+        let node_dcs = HashMap::from([
+            (node1.ip(), "dc1".to_owned()),
+            (node2.ip(), "dc2".to_owned()),
+        ]);
+        let mut out = Vec::new();
+        tracker
+            .print_summary(&node_dcs, Some("dc1"), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("127.0.0.1:9042 (dc1):"));
+        assert!(out.contains("127.0.0.2:9042 (dc2):"));
+        assert!(out.contains("local 3 (75.0%)\tremote 1 (25.0%)"));
+
+        assert!(!out.contains("unknown"));
+
+        let mut out = Vec::new();
+        tracker.print_summary(&node_dcs, None, &mut out).unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("Coordinator DC"));
+
+        // A node whose datacenter isn't known isn't counted as a remote one
+        let node_dcs = HashMap::from([(node1.ip(), "dc1".to_owned())]);
+        let mut out = Vec::new();
+        tracker
+            .print_summary(&node_dcs, Some("dc1"), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("127.0.0.2:9042 (unknown):"));
+        assert!(out.contains("local 3 (75.0%)\tremote 0 (0.0%)\tunknown 1 (25.0%)\n"));
+    }
+}
@@ -1,13 +1,23 @@
+use std::collections::BTreeMap;
 use std::ops::ControlFlow;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
-use scylla::{prepared_statement::PreparedStatement, Session};
+use scylla::{
+    batch::{Batch, BatchType},
+    execution_profile::ExecutionProfileHandle,
+    prepared_statement::PreparedStatement,
+    statement::Consistency,
+    Session,
+};
 use tracing::error;
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
 
 use crate::args::ScyllaBenchArgs;
+use crate::counter_tracker::CounterUpdateTracker;
+use crate::node_latency::NodeLatencyTracker;
 use crate::stats::ShardedStats;
 use crate::workload::{Workload, WorkloadFactory};
 
@@ -15,14 +25,26 @@ pub(crate) struct CounterUpdateOperationFactory {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
     statement: PreparedStatement,
+    batch_profile: ExecutionProfileHandle,
     workload_factory: Box<dyn WorkloadFactory>,
+    counter_tracker: Option<Arc<CounterUpdateTracker>>,
+    node_latency: Option<Arc<NodeLatencyTracker>>,
+    batch_size: u64,
+    downgrade_on_unavailable: bool,
+    operation_deadline: Option<Duration>,
 }
 
 struct CounterUpdateOperation {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
     statement: PreparedStatement,
+    batch_profile: ExecutionProfileHandle,
     workload: Box<dyn Workload>,
+    counter_tracker: Option<Arc<CounterUpdateTracker>>,
+    node_latency: Option<Arc<NodeLatencyTracker>>,
+    batch_size: u64,
+    downgrade_on_unavailable: bool,
+    operation_deadline: Option<Duration>,
 }
 
 impl CounterUpdateOperationFactory {
@@ -38,13 +60,29 @@ impl CounterUpdateOperationFactory {
             args.counter_table_name,
         );
         let mut statement = session.prepare(statement_str).await?;
+        super::set_idempotence(&mut statement, super::StatementKind::CounterUpdate, &args);
         statement.set_consistency(args.consistency_level);
         statement.set_request_timeout(Some(args.timeout));
+        super::set_node_latency_listener(&mut statement, &args);
+        // The batches don't have a request timeout of their own in this driver
+        // version, so it's set through a copy of the default execution profile
+        let batch_profile = session
+            .get_default_execution_profile_handle()
+            .pointee_to_builder()
+            .request_timeout(Some(args.timeout))
+            .build()
+            .into_handle();
         Ok(Self {
             session,
             stats,
             statement,
+            batch_profile,
             workload_factory,
+            counter_tracker: args.counter_tracker.clone(),
+            node_latency: args.node_latency.clone(),
+            batch_size: args.counter_batch_size,
+            downgrade_on_unavailable: args.downgrade_on_unavailable,
+            operation_deadline: args.operation_deadline,
         })
     }
 }
@@ -55,7 +93,13 @@ impl OperationFactory for CounterUpdateOperationFactory {
             session: Arc::clone(&self.session),
             stats: Arc::clone(&self.stats),
             statement: self.statement.clone(),
+            batch_profile: self.batch_profile.clone(),
             workload: self.workload_factory.create(),
+            counter_tracker: self.counter_tracker.clone(),
+            node_latency: self.node_latency.clone(),
+            batch_size: self.batch_size,
+            downgrade_on_unavailable: self.downgrade_on_unavailable,
+            operation_deadline: self.operation_deadline,
         })
     }
 }
@@ -63,13 +107,24 @@ impl OperationFactory for CounterUpdateOperationFactory {
 make_runnable!(CounterUpdateOperation);
 impl CounterUpdateOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
-        // Counter updates always use one key
-        let (pk, cks) = match self.workload.generate_keys(1) {
+        let (pk, cks) = match self.workload.generate_keys(self.batch_size as usize) {
             Some((pk, cks)) => (pk, cks),
             None => return Ok(ControlFlow::Break(())),
         };
 
-        let result = self.write_single(pk, cks[0]).await;
+        let deadline = self.operation_deadline;
+        let mut result = super::with_deadline(deadline, self.write(pk, &cks, None)).await;
+        self.record_updates(pk, &cks, &result);
+        let mut downgraded = false;
+        let downgraded_cl = match &result {
+            Err(err) if self.downgrade_on_unavailable => super::downgraded_consistency(err),
+            _ => None,
+        };
+        if let Some(cl) = downgraded_cl {
+            result = super::with_deadline(deadline, self.write(pk, &cks, Some(cl))).await;
+            self.record_updates(pk, &cks, &result);
+            downgraded = result.is_ok();
+        }
 
         if let Err(err) = result.as_ref() {
             error!(
@@ -80,8 +135,15 @@ impl CounterUpdateOperation {
             );
         }
 
-        let mut stats = self.stats.get_shard_mut();
+        let mut stats = self.stats.get_shard_mut_for(ctx.warmup);
         stats.account_op(ctx, &result, cks.len());
+        stats.account_timeout(&result);
+        if downgraded {
+            stats.downgraded_operations += 1;
+        }
+        if result.is_ok() {
+            stats.account_partition_rows(pk, cks.len());
+        }
 
         result?;
         Ok(ControlFlow::Continue(()))
@@ -89,14 +151,122 @@ impl CounterUpdateOperation {
 }
 
 impl CounterUpdateOperation {
-    async fn write_single(&mut self, pk: i64, ck: i64) -> Result<()> {
+    async fn write(&mut self, pk: i64, cks: &[i64], cl: Option<Consistency>) -> Result<()> {
+        match cks {
+            [ck] => self.write_single(pk, *ck, cl).await,
+            _ => self.write_batch(pk, cks, cl).await,
+        }
+    }
+
+    // Counter updates aren't idempotent, so every attempt is counted,
+    // including the failed ones which might have been applied anyway.
+    // It's done outside of the write, so that the attempts cut off
+    // by the operation deadline are counted too.
+    fn record_updates(&self, pk: i64, cks: &[i64], result: &Result<()>) {
+        if let Some(tracker) = &self.counter_tracker {
+            for ck in cks {
+                tracker.record_update(pk, *ck, result.is_ok());
+            }
+        }
+    }
+
+    async fn write_single(&self, pk: i64, ck: i64, cl: Option<Consistency>) -> Result<()> {
+        let statement = super::with_consistency(&self.statement, cl);
         // execute_npaged, since it's an UPDATE statement.
         self.session
-            .execute_unpaged(
-                &self.statement,
-                (ck + 1, ck + 2, ck + 3, ck + 4, ck + 5, pk, ck),
-            )
+            .execute_unpaged(&statement, counter_update_values(pk, ck, 1))
             .await?;
         Ok(())
     }
+
+    // Applies the increments of all rows in a single counter batch,
+    // like an application which aggregates the updates of a partition
+    async fn write_batch(&self, pk: i64, cks: &[i64], cl: Option<Consistency>) -> Result<()> {
+        // The workload may generate the same row more than once,
+        // so the increments of a row are aggregated into one statement
+        let mut updates = BTreeMap::<i64, i64>::new();
+        for ck in cks {
+            *updates.entry(*ck).or_default() += 1;
+        }
+
+        let mut batch = new_counter_batch(
+            cl.unwrap_or_else(|| self.statement.get_consistency().unwrap()),
+            &self.batch_profile,
+            self.node_latency.as_ref(),
+        );
+        let mut values = Vec::with_capacity(updates.len());
+        for (ck, count) in updates {
+            batch.append_statement(self.statement.clone());
+            values.push(counter_update_values(pk, ck, count));
+        }
+        self.session.batch(&batch, values).await?;
+        Ok(())
+    }
+}
+
+fn new_counter_batch(
+    consistency: Consistency,
+    profile: &ExecutionProfileHandle,
+    node_latency: Option<&Arc<NodeLatencyTracker>>,
+) -> Batch {
+    let mut batch = Batch::new(BatchType::Counter);
+    batch.set_consistency(consistency);
+    batch.set_execution_profile_handle(Some(profile.clone()));
+    if let Some(tracker) = node_latency {
+        batch.set_history_listener(Arc::clone(tracker) as _);
+    }
+    batch
+}
+
+// Each update increments the counters c1..=c5 of the row by ck + 1..=ck + 5
+fn counter_update_values(pk: i64, ck: i64, updates: i64) -> (i64, i64, i64, i64, i64, i64, i64) {
+    let [c1, c2, c3, c4, c5] = [1, 2, 3, 4, 5].map(|i| updates * (ck + i));
+    (c1, c2, c3, c4, c5, pk, ck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_update_values() {
+        assert_eq!(counter_update_values(7, 10, 1), (11, 12, 13, 14, 15, 7, 10));
+        assert_eq!(counter_update_values(7, 10, 3), (33, 36, 39, 42, 45, 7, 10));
+    }
+
+    #[test]
+    fn test_counter_batch_has_request_timeout() {
+        let profile = scylla::ExecutionProfile::builder()
+            .request_timeout(Some(Duration::from_secs(7)))
+            .build()
+            .into_handle();
+        let batch = new_counter_batch(Consistency::Quorum, &profile, None);
+        assert_eq!(batch.get_consistency(), Some(Consistency::Quorum));
+        // The driver doesn't expose the timeout of a profile other than
+        // through its debug representation
+        let batch_profile = batch.get_execution_profile_handle().unwrap();
+        assert!(format!("{:?}", batch_profile.pointee_to_builder())
+            .contains("request_timeout: Some(Some(7s))"));
+    }
+
+    #[test]
+    fn test_validate_counter_row_data() {
+        let validate = |(c1, c2, c3, c4, c5, pk, ck)| {
+            super::super::validate_counter_row_data(pk, ck, c1, c2, c3, c4, c5)
+        };
+
+        for ck in [0, 1, 10, 1000] {
+            for updates in [1, 2, 7] {
+                validate(counter_update_values(7, ck, updates)).unwrap();
+            }
+        }
+
+        let (c1, c2, c3, c4, c5, pk, ck) = counter_update_values(7, 10, 3);
+        assert!(validate((c1 + 1, c2, c3, c4, c5, pk, ck)).is_err());
+        assert!(validate((c1, c2, c3, c4, c5 - 1, pk, ck)).is_err());
+        // The counters of another row
+        assert!(validate((c1, c2, c3, c4, c5, pk, ck + 1)).is_err());
+        // A row which was never updated isn't returned by the reads
+        assert!(validate((0, 0, 0, 0, 0, pk, ck)).is_err());
+    }
 }
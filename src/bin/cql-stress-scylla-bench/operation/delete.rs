@@ -0,0 +1,146 @@
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use scylla::{prepared_statement::PreparedStatement, statement::Consistency, Session};
+use tracing::error;
+
+use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+
+use crate::args::ScyllaBenchArgs;
+use crate::stats::ShardedStats;
+use crate::workload::{Workload, WorkloadFactory};
+
+pub(crate) struct DeleteOperationFactory {
+    session: Arc<Session>,
+    stats: Arc<ShardedStats>,
+    statement: PreparedStatement,
+    workload_factory: Box<dyn WorkloadFactory>,
+    range_size: u64,
+    whole_partitions: bool,
+    downgrade_on_unavailable: bool,
+    operation_deadline: Option<Duration>,
+}
+
+struct DeleteOperation {
+    session: Arc<Session>,
+    stats: Arc<ShardedStats>,
+    statement: PreparedStatement,
+    workload: Box<dyn Workload>,
+    range_size: u64,
+    whole_partitions: bool,
+    downgrade_on_unavailable: bool,
+    operation_deadline: Option<Duration>,
+}
+
+impl DeleteOperationFactory {
+    pub async fn new(
+        session: Arc<Session>,
+        stats: Arc<ShardedStats>,
+        workload_factory: Box<dyn WorkloadFactory>,
+        args: Arc<ScyllaBenchArgs>,
+    ) -> Result<Self> {
+        // A range covering all of the rows of the partition
+        // is deleted with a single partition tombstone
+        let whole_partitions = args.delete_range_size >= args.clustering_row_count;
+        let statement_str = if whole_partitions {
+            format!("DELETE FROM {} WHERE pk = ?", args.table_name)
+        } else {
+            format!(
+                "DELETE FROM {} WHERE pk = ? AND ck >= ? AND ck < ?",
+                args.table_name,
+            )
+        };
+        let mut statement = session.prepare(statement_str).await?;
+        super::set_idempotence(&mut statement, super::StatementKind::Delete, &args);
+        statement.set_consistency(args.consistency_level);
+        statement.set_request_timeout(Some(args.timeout));
+        super::set_node_latency_listener(&mut statement, &args);
+        Ok(Self {
+            session,
+            stats,
+            statement,
+            workload_factory,
+            range_size: args.delete_range_size,
+            whole_partitions,
+            downgrade_on_unavailable: args.downgrade_on_unavailable,
+            operation_deadline: args.operation_deadline,
+        })
+    }
+}
+
+impl OperationFactory for DeleteOperationFactory {
+    fn create(&self) -> Box<dyn Operation> {
+        Box::new(DeleteOperation {
+            session: Arc::clone(&self.session),
+            stats: Arc::clone(&self.stats),
+            statement: self.statement.clone(),
+            workload: self.workload_factory.create(),
+            range_size: self.range_size,
+            whole_partitions: self.whole_partitions,
+            downgrade_on_unavailable: self.downgrade_on_unavailable,
+            operation_deadline: self.operation_deadline,
+        })
+    }
+}
+
+make_runnable!(DeleteOperation);
+impl DeleteOperation {
+    async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+        // The first generated clustering key marks the beginning of the deleted range.
+        // Asking for the whole range lets the sequential workload advance past it.
+        let (pk, cks) = match self.workload.generate_keys(self.range_size as usize) {
+            Some((pk, cks)) => (pk, cks),
+            None => return Ok(ControlFlow::Break(())),
+        };
+
+        let deadline = self.operation_deadline;
+        let mut result = super::with_deadline(deadline, self.delete(pk, cks[0], None)).await;
+        let mut downgraded = false;
+        let downgraded_cl = match &result {
+            Err(err) if self.downgrade_on_unavailable => super::downgraded_consistency(err),
+            _ => None,
+        };
+        if let Some(cl) = downgraded_cl {
+            result = super::with_deadline(deadline, self.delete(pk, cks[0], Some(cl))).await;
+            downgraded = result.is_ok();
+        }
+
+        if let Err(err) = result.as_ref() {
+            error!(
+                error = %err,
+                partition_key = pk,
+                clustering_key = cks[0],
+                range_size = self.range_size,
+                "delete error",
+            );
+        }
+
+        let mut stats = self.stats.get_shard_mut_for(ctx.warmup);
+        stats.account_op(ctx, &result, cks.len());
+        stats.account_timeout(&result);
+        if downgraded {
+            stats.downgraded_operations += 1;
+        }
+
+        result?;
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+impl DeleteOperation {
+    async fn delete(&mut self, pk: i64, ck: i64, cl: Option<Consistency>) -> Result<()> {
+        let statement = super::with_consistency(&self.statement, cl);
+        // execute_unpaged, since it's a DELETE statement.
+        if self.whole_partitions {
+            self.session.execute_unpaged(&statement, (pk,)).await?;
+        } else {
+            let ck_end = ck.saturating_add(self.range_size as i64);
+            self.session
+                .execute_unpaged(&statement, (pk, ck, ck_end))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,28 @@
 pub mod counter_update;
+pub mod delete;
+pub mod prepare;
 pub mod read;
 pub mod scan;
 pub mod write;
 
+use std::borrow::Cow;
 use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use rand::RngCore;
+use scylla::history::HistoryListener;
+use scylla::prepared_statement::PreparedStatement;
+use scylla::statement::Consistency;
+use scylla::transport::errors::{DbError, QueryError};
 use sha2::{Digest, Sha256};
-use tracing::error;
+use tracing::{error, warn};
+
+use cql_stress::configuration::RetryFilter;
+
+use crate::args::ScyllaBenchArgs;
 
 const GENERATED_DATA_HEADER_SIZE: usize = 24;
 const GENERATED_DATA_MIN_SIZE: usize = GENERATED_DATA_HEADER_SIZE + 33;
@@ -49,6 +63,34 @@ fn generate_row_data(pk: i64, ck: i64, size: usize) -> Vec<u8> {
     }
 }
 
+// Overwrites the keys stored in the data generated by generate_row_data,
+// which allows generating the rest of it, including the payload checksum,
+// before the keys of the row are known.
+fn set_row_data_keys(data: &mut [u8], pk: i64, ck: i64) {
+    let size = data.len();
+    if size == 0 {
+    } else if size < GENERATED_DATA_HEADER_SIZE {
+        // The keys might have been truncated to the size of the data
+        let keys_len = std::cmp::min(8, size - 1);
+        data[1..1 + keys_len].copy_from_slice(&(pk ^ ck).to_le_bytes()[..keys_len]);
+    } else {
+        data[8..16].copy_from_slice(&pk.to_le_bytes());
+        data[16..24].copy_from_slice(&ck.to_le_bytes());
+    }
+}
+
+// Computes the checksum of a single row, whose wrapping sum over all rows
+// is independent of the order in which they were written, so that it can be
+// recomputed by reading the table back.
+fn row_checksum(pk: i64, ck: i64, data: &[u8]) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(pk.to_be_bytes());
+    hasher.update(ck.to_be_bytes());
+    hasher.update(data);
+    let hash = hasher.finalize();
+    u64::from_be_bytes(hash[..8].try_into().unwrap())
+}
+
 fn validate_row_data(pk: i64, ck: i64, data: &[u8]) -> Result<()> {
     let size = data.len();
     let original_data = data;
@@ -129,15 +171,18 @@ fn validate_counter_row_data(
     c4: i64,
     c5: i64,
 ) -> Result<()> {
-    let update_num = if ck == 0 { c2 } else { c1 / ck };
-    let ok = c1 != ck * update_num
-        || c2 != c1 + update_num
-        || c3 != c2 + update_num
-        || c4 != c3 + update_num
-        || c5 != c4 + update_num;
+    // Without the counter state file the number of updates of the row isn't known,
+    // but each of them increments the counters c1..=c5 by ck + 1..=ck + 5,
+    // so all the counters must be the same multiple of those increments.
+    // This holds regardless of how many times the workload touched the row.
+    let updates = c2.wrapping_sub(c1);
+    let consistent = [c1, c2, c3, c4, c5]
+        .iter()
+        .zip(1..)
+        .all(|(c, i)| *c == updates.wrapping_mul(ck + i));
 
     anyhow::ensure!(
-        ok,
+        consistent && updates > 0,
         "Corrupt counter data: invalid counter values, \
         pk: {}, ck: {}, c1: {}, c2: {}, c3: {}, c4: {}, c5: {}",
         pk,
@@ -152,10 +197,235 @@ fn validate_counter_row_data(
     Ok(())
 }
 
+// If the error is an UNAVAILABLE error, returns the highest consistency level
+// which can be satisfied by the replicas reported as alive by the coordinator.
+// Mirrors the behavior of the downgrading consistency retry policy.
+fn downgraded_consistency(err: &anyhow::Error) -> Option<Consistency> {
+    let (consistency, alive) = match err.downcast_ref::<QueryError>()? {
+        QueryError::DbError(
+            DbError::Unavailable {
+                consistency, alive, ..
+            },
+            _,
+        ) => (*consistency, *alive),
+        _ => return None,
+    };
+
+    let downgraded = match alive {
+        a if a >= 3 => Consistency::Three,
+        2 => Consistency::Two,
+        1 if consistency == Consistency::EachQuorum => Consistency::LocalOne,
+        1 => Consistency::One,
+        _ => return None,
+    };
+
+    warn!(
+        requested = ?consistency,
+        downgraded = ?downgraded,
+        alive,
+        "not enough replicas alive, retrying with a lower consistency",
+    );
+    Some(downgraded)
+}
+
+// Whether the error is a WRITE_TIMEOUT error, after which the write
+// might have been applied anyway.
+fn is_write_timeout(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<QueryError>(),
+        Some(QueryError::DbError(DbError::WriteTimeout { .. }, _)),
+    )
+}
+
+/// Kind of a statement, which decides whether it is idempotent,
+/// i.e. whether the driver may retry it or execute it speculatively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StatementKind {
+    Read,
+    Write,
+    Delete,
+    CounterUpdate,
+}
+
+impl StatementKind {
+    fn is_idempotent(self, args: &ScyllaBenchArgs) -> bool {
+        match self {
+            StatementKind::Read | StatementKind::Delete => true,
+            StatementKind::Write => args.idempotent_writes,
+            // A counter update applied twice increments the counters twice,
+            // so it must never be retried by the driver
+            StatementKind::CounterUpdate => false,
+        }
+    }
+}
+
+// Marks the statement as idempotent if its kind allows it. All of the
+// statements should go through here, so that the policy is kept in one place.
+fn set_idempotence(statement: &mut PreparedStatement, kind: StatementKind, args: &ScyllaBenchArgs) {
+    statement.set_is_idempotent(kind.is_idempotent(args));
+}
+
+// Attaches the per-node latency tracker to the statement, if it is enabled.
+fn set_node_latency_listener(statement: &mut PreparedStatement, args: &ScyllaBenchArgs) {
+    if let Some(tracker) = &args.node_latency {
+        statement.set_history_listener(Arc::clone(tracker) as Arc<dyn HistoryListener>);
+    }
+}
+
+// Returns the statement with its consistency overridden, if requested.
+// Avoids cloning the statement in the common case.
+fn with_consistency(
+    statement: &PreparedStatement,
+    cl: Option<Consistency>,
+) -> Cow<'_, PreparedStatement> {
+    match cl {
+        Some(cl) => {
+            let mut statement = statement.clone();
+            statement.set_consistency(cl);
+            Cow::Owned(statement)
+        }
+        None => Cow::Borrowed(statement),
+    }
+}
+
+/// Reported when an operation doesn't complete before its client-side deadline.
+#[derive(Debug)]
+pub struct ClientTimeoutError(Duration);
+
+impl Display for ClientTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "operation did not complete within the client-side deadline of {:?}",
+            self.0,
+        )
+    }
+}
+
+impl std::error::Error for ClientTimeoutError {}
+
+// Bounds the execution time of the future by the deadline, if there is any.
+// Unlike the driver's request timeout, this also catches stalls which happen
+// inside the driver.
+async fn with_deadline<T>(
+    deadline: Option<Duration>,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    // The per-node latency of the attempts cut off by the deadline
+    // must not be kept waiting for them to finish
+    let fut = crate::node_latency::in_flight_scope(fut);
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline, fut)
+            .await
+            .unwrap_or_else(|_| Err(ClientTimeoutError(deadline).into())),
+        None => fut.await,
+    }
+}
+
+/// Category of the errors, used to select the retried operations, see -retry-on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorCategory {
+    // Server-side timeouts, as well as the driver and client-side ones
+    Timeout,
+    Unavailable,
+    // The coordinator is overloaded, rate limiting or still bootstrapping
+    Overloaded,
+    // Read or write failures reported by the replicas, and server errors
+    Failure,
+    // Errors which would repeat, e.g. syntax errors or missing permissions
+    Invalid,
+    // Broken connections and other errors on the client-coordinator path
+    Connection,
+    Other,
+}
+
+impl ErrorCategory {
+    const ALL: [ErrorCategory; 7] = [
+        ErrorCategory::Timeout,
+        ErrorCategory::Unavailable,
+        ErrorCategory::Overloaded,
+        ErrorCategory::Failure,
+        ErrorCategory::Invalid,
+        ErrorCategory::Connection,
+        ErrorCategory::Other,
+    ];
+
+    pub(crate) fn of(err: &anyhow::Error) -> Self {
+        if err.is::<ClientTimeoutError>() {
+            return ErrorCategory::Timeout;
+        }
+        let Some(err) = err.downcast_ref::<QueryError>() else {
+            return ErrorCategory::Other;
+        };
+        match err {
+            QueryError::DbError(db_err, _) => match db_err {
+                DbError::ReadTimeout { .. } | DbError::WriteTimeout { .. } => {
+                    ErrorCategory::Timeout
+                }
+                DbError::Unavailable { .. } => ErrorCategory::Unavailable,
+                DbError::Overloaded
+                | DbError::RateLimitReached { .. }
+                | DbError::IsBootstrapping => ErrorCategory::Overloaded,
+                DbError::ReadFailure { .. }
+                | DbError::WriteFailure { .. }
+                | DbError::FunctionFailure { .. }
+                | DbError::TruncateError
+                | DbError::ServerError => ErrorCategory::Failure,
+                DbError::SyntaxError
+                | DbError::Invalid
+                | DbError::AlreadyExists { .. }
+                | DbError::AuthenticationError
+                | DbError::Unauthorized
+                | DbError::ConfigError => ErrorCategory::Invalid,
+                _ => ErrorCategory::Other,
+            },
+            QueryError::TimeoutError | QueryError::RequestTimeout(_) => ErrorCategory::Timeout,
+            QueryError::BadQuery(_) => ErrorCategory::Invalid,
+            _ => ErrorCategory::Connection,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Unavailable => "unavailable",
+            ErrorCategory::Overloaded => "overloaded",
+            ErrorCategory::Failure => "failure",
+            ErrorCategory::Invalid => "invalid",
+            ErrorCategory::Connection => "connection",
+            ErrorCategory::Other => "other",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(ErrorCategory::name).collect();
+                anyhow::anyhow!(
+                    "Unknown error category: {}; supported categories are: {}",
+                    s,
+                    names.join(", "),
+                )
+            })
+    }
+}
+
+/// Retries only the operations which failed with an error of the given categories.
+pub(crate) struct RetryOn(pub Vec<ErrorCategory>);
+
+impl RetryFilter for RetryOn {
+    fn should_retry(&self, err: &anyhow::Error) -> bool {
+        self.0.contains(&ErrorCategory::of(err))
+    }
+}
+
 #[derive(Default)]
 pub struct ReadContext {
     pub errors: u64,
     pub rows_read: u64,
+    pub rows_validated: u64,
 }
 
 impl ReadContext {
@@ -168,11 +438,11 @@ impl ReadContext {
         );
         self.errors += 1;
     }
-    pub fn failed_scan(&mut self, err: &impl Display, first: i64, last: i64) {
+    pub fn failed_scan(&mut self, err: &impl Display, first: impl Display, last: impl Display) {
         error!(
             error = %err,
-            first_token = first,
-            last_token = last,
+            first_token = %first,
+            last_token = %last,
             "scan error",
         );
         self.errors += 1;
@@ -187,15 +457,49 @@ impl ReadContext {
         );
         self.errors += 1;
     }
+    pub fn row_count_mismatch(&mut self, pk: i64, expected: u64, actual: u64) {
+        eprintln!(
+            "data corruption in pk({}): expected {} rows, read {}",
+            pk, expected, actual
+        );
+        error!(
+            partition_key = pk,
+            expected_rows = expected,
+            actual_rows = actual,
+            "unexpected number of rows",
+        );
+        self.errors += 1;
+    }
     pub fn row_read(&mut self) {
         self.rows_read += 1;
     }
+    pub fn row_validated(&mut self) {
+        self.rows_validated += 1;
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use scylla::transport::errors::WriteType;
+
     use super::*;
 
+    #[test]
+    fn test_row_checksum() {
+        let rows = [(1, 2, vec![1u8, 2, 3]), (1, 3, vec![]), (4, 2, vec![0; 10])];
+        let sum = |rows: &[(i64, i64, Vec<u8>)]| {
+            rows.iter().fold(0u64, |acc, (pk, ck, data)| {
+                acc.wrapping_add(row_checksum(*pk, *ck, data))
+            })
+        };
+        let mut reversed = rows.clone();
+        reversed.reverse();
+        assert_eq!(sum(&rows), sum(&reversed));
+
+        assert_ne!(row_checksum(1, 2, &[]), row_checksum(2, 1, &[]));
+        assert_ne!(row_checksum(1, 2, &[0]), row_checksum(1, 2, &[1]));
+    }
+
     #[test]
     fn test_generate_validate_data() {
         let pk = 123;
@@ -222,4 +526,138 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_set_row_data_keys() {
+        let pk = 123;
+        let ck = 456;
+        for size in 0..=100 {
+            let mut data = generate_row_data(0, 0, size);
+            set_row_data_keys(&mut data, pk, ck);
+            validate_row_data(pk, ck, &data).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_downgraded_consistency() {
+        let unavailable = |alive: i32| -> anyhow::Error {
+            QueryError::DbError(
+                DbError::Unavailable {
+                    consistency: Consistency::Quorum,
+                    required: 3,
+                    alive,
+                },
+                String::new(),
+            )
+            .into()
+        };
+
+        assert_eq!(
+            downgraded_consistency(&unavailable(4)),
+            Some(Consistency::Three),
+        );
+        assert_eq!(
+            downgraded_consistency(&unavailable(2)),
+            Some(Consistency::Two),
+        );
+        assert_eq!(
+            downgraded_consistency(&unavailable(1)),
+            Some(Consistency::One),
+        );
+        assert_eq!(downgraded_consistency(&unavailable(0)), None);
+        assert_eq!(
+            downgraded_consistency(&anyhow::anyhow!("some other error")),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_error_category() {
+        let db_error = |err| -> anyhow::Error { QueryError::DbError(err, String::new()).into() };
+        let category = |err| ErrorCategory::of(&err);
+
+        assert_eq!(
+            category(db_error(DbError::Unavailable {
+                consistency: Consistency::Quorum,
+                required: 3,
+                alive: 1,
+            })),
+            ErrorCategory::Unavailable,
+        );
+        assert_eq!(
+            category(db_error(DbError::Overloaded)),
+            ErrorCategory::Overloaded
+        );
+        assert_eq!(category(db_error(DbError::Invalid)), ErrorCategory::Invalid);
+        assert_eq!(
+            category(QueryError::TimeoutError.into()),
+            ErrorCategory::Timeout
+        );
+        assert_eq!(
+            category(ClientTimeoutError(Duration::from_secs(1)).into()),
+            ErrorCategory::Timeout,
+        );
+        assert_eq!(
+            category(anyhow::anyhow!("some other error")),
+            ErrorCategory::Other
+        );
+
+        for c in ErrorCategory::ALL {
+            assert_eq!(ErrorCategory::parse(c.name()).unwrap(), c);
+        }
+        assert!(ErrorCategory::parse("timeouts").is_err());
+
+        let retry_on = RetryOn(vec![ErrorCategory::Timeout, ErrorCategory::Unavailable]);
+        assert!(retry_on.should_retry(&QueryError::TimeoutError.into()));
+        assert!(!retry_on.should_retry(&db_error(DbError::Invalid)));
+    }
+
+    #[test]
+    fn test_is_write_timeout() {
+        let write_timeout: anyhow::Error = QueryError::DbError(
+            DbError::WriteTimeout {
+                consistency: Consistency::Quorum,
+                received: 1,
+                required: 2,
+                write_type: WriteType::Simple,
+            },
+            String::new(),
+        )
+        .into();
+        let read_timeout: anyhow::Error = QueryError::DbError(
+            DbError::ReadTimeout {
+                consistency: Consistency::Quorum,
+                received: 1,
+                required: 2,
+                data_present: false,
+            },
+            String::new(),
+        )
+        .into();
+
+        assert!(is_write_timeout(&write_timeout));
+        assert!(!is_write_timeout(&read_timeout));
+        assert!(!is_write_timeout(&anyhow::anyhow!("some other error")));
+    }
+
+    #[test]
+    fn test_statement_idempotence() {
+        let parse = |s: &str| {
+            crate::args::parse_scylla_bench_args(s.split_ascii_whitespace(), false).unwrap()
+        };
+        let kinds = [
+            StatementKind::Read,
+            StatementKind::Write,
+            StatementKind::Delete,
+            StatementKind::CounterUpdate,
+        ];
+
+        let args = parse("scylla-bench -workload=uniform -mode=write");
+        let idempotent = kinds.map(|kind| kind.is_idempotent(&args));
+        assert_eq!(idempotent, [true, true, true, false]);
+
+        let args = parse("scylla-bench -workload=uniform -mode=write -idempotent-writes=false");
+        let idempotent = kinds.map(|kind| kind.is_idempotent(&args));
+        assert_eq!(idempotent, [true, false, true, false]);
+    }
 }
@@ -0,0 +1,113 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use scylla::Session;
+use tracing::error;
+
+use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+
+use crate::args::ScyllaBenchArgs;
+use crate::stats::ShardedStats;
+
+// Prepares variants of a statement instead of executing any, in order
+// to measure the latency of preparation and to stress the prepared
+// statement cache of the cluster.
+pub(crate) struct PrepareOperationFactory {
+    session: Arc<Session>,
+    stats: Arc<ShardedStats>,
+    args: Arc<ScyllaBenchArgs>,
+    next_variant: Arc<AtomicU64>,
+}
+
+struct PrepareOperation {
+    session: Arc<Session>,
+    stats: Arc<ShardedStats>,
+    args: Arc<ScyllaBenchArgs>,
+    next_variant: Arc<AtomicU64>,
+}
+
+impl PrepareOperationFactory {
+    pub fn new(
+        session: Arc<Session>,
+        stats: Arc<ShardedStats>,
+        args: Arc<ScyllaBenchArgs>,
+    ) -> Self {
+        Self {
+            session,
+            stats,
+            args,
+            next_variant: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl OperationFactory for PrepareOperationFactory {
+    fn create(&self) -> Box<dyn Operation> {
+        Box::new(PrepareOperation {
+            session: Arc::clone(&self.session),
+            stats: Arc::clone(&self.stats),
+            args: Arc::clone(&self.args),
+            next_variant: Arc::clone(&self.next_variant),
+        })
+    }
+}
+
+make_runnable!(PrepareOperation);
+impl PrepareOperation {
+    async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+        let mut variant = self.next_variant.fetch_add(1, Ordering::Relaxed);
+        // With no limit on the variants, every statement is new to the cluster,
+        // so that the cached ones keep getting evicted
+        if self.args.prepare_variants != 0 {
+            variant %= self.args.prepare_variants;
+        }
+        let statement_str = statement_variant(&self.args.table_name, variant);
+
+        let result = super::with_deadline(self.args.operation_deadline, async {
+            self.session.prepare(statement_str).await?;
+            Ok(())
+        })
+        .await;
+
+        if let Err(err) = &result {
+            error!(error = %err, variant, "prepare error");
+        }
+
+        let mut stats_lock = self.stats.get_shard_mut_for(ctx.warmup);
+        let stats = &mut *stats_lock;
+        stats.operations += 1;
+        if result.is_err() {
+            stats.errors += 1;
+        }
+        stats.account_timeout(&result);
+        stats_lock.account_latency(ctx);
+
+        result?;
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+// The statements differ only by a literal, which is enough for them
+// to be prepared and cached separately.
+fn statement_variant(table_name: &str, variant: u64) -> String {
+    format!(
+        "SELECT ck, v FROM {} WHERE pk = ? AND ck = {}",
+        table_name, variant
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_variant() {
+        assert_eq!(
+            statement_variant("test", 42),
+            "SELECT ck, v FROM test WHERE pk = ? AND ck = 42"
+        );
+        assert_ne!(statement_variant("test", 1), statement_variant("test", 2));
+    }
+}
@@ -1,15 +1,23 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::ControlFlow;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use futures::{stream, StreamExt, TryStreamExt};
+use rand::distributions::{Distribution as _, WeightedIndex};
+use rand::Rng;
 use scylla::cql_to_rust::FromRow;
 use scylla::frame::value::Counter;
+use scylla::statement::Consistency;
 use scylla::{prepared_statement::PreparedStatement, Session};
+use tracing::warn;
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
 
-use crate::args::{OrderBy, ScyllaBenchArgs};
+use crate::args::{OrderBy, ScyllaBenchArgs, WorkloadType};
+use crate::counter_tracker::CounterUpdateTracker;
+use crate::distribution::RngGen;
 use crate::operation::ReadContext;
 use crate::stats::ShardedStats;
 use crate::workload::{Workload, WorkloadFactory};
@@ -33,15 +41,56 @@ pub(crate) struct ReadOperationFactory {
 struct ReadOperation {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    // The statements of each keyspace one after another,
+    // one for each of the select-order-by orders
     statements: Vec<PreparedStatement>,
+    order_count: usize,
+    // If set, the keyspace of each read is picked randomly by its weight
+    keyspace_choice: Option<WeightedIndex<u64>>,
+    gen: RngGen,
     workload: Box<dyn Workload>,
     read_kind: ReadKind,
     read_restriction: ReadRestrictionKind,
     validate_data: bool,
+    // If set, the counters are expected to have exactly the values
+    // resulting from the updates recorded by counter_update
+    counter_tracker: Option<Arc<CounterUpdateTracker>>,
+    // If set, the number of rows returned by each read is validated
+    // against the number of rows the partition is expected to contain
+    expected_rows_per_partition: Option<u64>,
+    // If set, the reads which fail validation are repeated once, optionally
+    // at the given consistency level, and only the persistent mismatches
+    // are reported as data corruption
+    reread_on_mismatch: bool,
+    reread_consistency_level: Option<Consistency>,
+    // If set, each key is read at ONE and then at ALL,
+    // and the keys for which the results differ are reported
+    consistency_audit: bool,
+    downgrade_on_unavailable: bool,
+    operation_deadline: Option<Duration>,
+    args: Arc<ScyllaBenchArgs>,
 
     current_statement_idx: usize,
 }
 
+// A validation failure found by a read, reported once it's known
+// that the read won't be repeated
+enum Mismatch {
+    Row { ck: i64, err: anyhow::Error },
+    RowCount { expected: u64, actual: u64 },
+}
+
+impl Mismatch {
+    fn report(&self, rctx: &mut ReadContext, pk: i64) {
+        match self {
+            Mismatch::Row { ck, err } => rctx.data_corruption(pk, *ck, err),
+            Mismatch::RowCount { expected, actual } => {
+                rctx.row_count_mismatch(pk, *expected, *actual)
+            }
+        }
+    }
+}
+
 impl ReadOperationFactory {
     pub async fn new(
         session: Arc<Session>,
@@ -50,7 +99,9 @@ impl ReadOperationFactory {
         workload_factory: Box<dyn WorkloadFactory>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
-        let read_restriction = if args.in_restriction {
+        let read_restriction = if args.full_partition_read {
+            ReadRestrictionKind::FullPartition
+        } else if args.in_restriction {
             ReadRestrictionKind::InRestriction {
                 cks_to_select: args.rows_per_request,
             }
@@ -68,12 +119,29 @@ impl ReadOperationFactory {
             }
         };
 
-        let statements = stream::iter(&args.select_order_by)
-            .then(|order_by| {
-                prepare_statement(&session, &args, read_kind, &read_restriction, order_by)
-            })
-            .try_collect::<Vec<_>>()
-            .await?;
+        let table_names = match read_kind {
+            ReadKind::Regular => (0..args.keyspaces.len())
+                .map(|ks_idx| args.table_in_keyspace(ks_idx, &args.table_name))
+                .collect(),
+            ReadKind::Counter => vec![args.counter_table_name.clone()],
+        };
+        let statements = stream::iter(table_names.iter().flat_map(|table_name| {
+            args.select_order_by
+                .iter()
+                .map(move |order_by| (table_name, order_by))
+        }))
+        .then(|(table_name, order_by)| {
+            prepare_statement(
+                &session,
+                &args,
+                read_kind,
+                &read_restriction,
+                table_name,
+                order_by,
+            )
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
 
         Ok(Self {
             session,
@@ -92,6 +160,7 @@ async fn prepare_statement(
     args: &ScyllaBenchArgs,
     read_kind: ReadKind,
     read_restriction: &ReadRestrictionKind,
+    table_name: &str,
     order_by: &OrderBy,
 ) -> Result<PreparedStatement> {
     let selector = read_restriction.get_selector_string();
@@ -101,21 +170,22 @@ async fn prepare_statement(
     let mut statement_str = match read_kind {
         ReadKind::Regular => format!(
             "SELECT ck, v FROM {} WHERE pk = ? {} {} {}",
-            args.table_name, selector, order_by, limit,
+            table_name, selector, order_by, limit,
         ),
         ReadKind::Counter => format!(
             "SELECT ck, c1, c2, c3, c4, c5 FROM {} WHERE pk = ? {} {} {}",
-            args.counter_table_name, selector, order_by, limit,
+            table_name, selector, order_by, limit,
         ),
     };
     if args.bypass_cache {
         statement_str += " BYPASS CACHE";
     }
     let mut statement = session.prepare(statement_str).await?;
-    statement.set_is_idempotent(true);
+    super::set_idempotence(&mut statement, super::StatementKind::Read, args);
     statement.set_page_size(args.page_size.try_into()?);
-    statement.set_consistency(args.consistency_level);
+    statement.set_consistency(args.read_consistency_level());
     statement.set_request_timeout(Some(args.timeout));
+    super::set_node_latency_listener(&mut statement, args);
 
     Ok(statement)
 }
@@ -134,10 +204,29 @@ impl OperationFactory for ReadOperationFactory {
             session: Arc::clone(&self.session),
             stats: Arc::clone(&self.stats),
             statements: self.statements.clone(),
+            order_count: self.args.select_order_by.len(),
+            keyspace_choice: self.args.keyspace_choice(),
+            gen: RngGen::new(rand::thread_rng().gen()),
             workload: self.workload_factory.create(),
             read_kind: self.read_kind,
             read_restriction: self.read_restriction,
             validate_data: self.args.validate_data,
+            counter_tracker: self.args.counter_tracker.clone(),
+            // The timeseries workload doesn't write a fixed set of clustering
+            // rows, the timebucket workload uses timestamps as the clustering
+            // keys and the counter rows are created by random updates,
+            // so the row count can't be predicted for them
+            expected_rows_per_partition: (self.args.validate_data
+                && matches!(self.read_kind, ReadKind::Regular)
+                && self.args.workload != WorkloadType::Timeseries
+                && self.args.workload != WorkloadType::Timebucket)
+                .then_some(self.args.clustering_row_count),
+            reread_on_mismatch: self.args.reread_on_mismatch,
+            reread_consistency_level: self.args.reread_consistency_level,
+            consistency_audit: self.args.consistency_audit,
+            downgrade_on_unavailable: self.args.downgrade_on_unavailable,
+            operation_deadline: self.args.operation_deadline,
+            args: Arc::clone(&self.args),
 
             current_statement_idx: 0,
         })
@@ -160,34 +249,191 @@ impl ReadOperation {
             values.push(*ck);
         }
 
-        let stmt = self.statements[self.current_statement_idx].clone();
-        self.current_statement_idx = (self.current_statement_idx + 1) % self.statements.len();
+        let order_count = self.order_count;
+        let ks_idx = match &self.keyspace_choice {
+            Some(choice) => choice.sample(&mut self.gen),
+            None => 0,
+        };
+        let mut stmt = self.statements[ks_idx * order_count + self.current_statement_idx].clone();
+        self.current_statement_idx = (self.current_statement_idx + 1) % order_count;
+        let misrouted = match &self.args.misrouting {
+            Some(misrouting) => misrouting.misroute(&mut stmt, &mut self.gen),
+            None => false,
+        };
+
+        if self.consistency_audit {
+            return self.audit(ctx, pk, &cks, &stmt, values).await;
+        }
+
+        let deadline = self.operation_deadline;
+        let mut mismatches = Vec::new();
+        let mut result = super::with_deadline(
+            deadline,
+            self.do_execute(&mut rctx, &mut mismatches, pk, stmt.clone(), values.clone()),
+        )
+        .await;
+        let mut downgraded = false;
+        let downgraded_cl = match &result {
+            Err(err) if self.downgrade_on_unavailable => super::downgraded_consistency(err),
+            _ => None,
+        };
+        if let Some(cl) = downgraded_cl {
+            rctx = ReadContext::default();
+            mismatches.clear();
+            stmt = super::with_consistency(&stmt, Some(cl)).into_owned();
+            result = super::with_deadline(
+                deadline,
+                self.do_execute(&mut rctx, &mut mismatches, pk, stmt.clone(), values.clone()),
+            )
+            .await;
+            downgraded = result.is_ok();
+        }
 
-        let result = self.do_execute(&mut rctx, pk, stmt, values).await;
+        // The mismatch might be caused by a write which is still in progress
+        // or hasn't reached all of the replicas yet, so give it another chance
+        let mut transient_mismatch = false;
+        if self.reread_on_mismatch && result.is_ok() && !mismatches.is_empty() {
+            let stmt = super::with_consistency(&stmt, self.reread_consistency_level);
+            let mut reread_rctx = ReadContext::default();
+            let mut reread_mismatches = Vec::new();
+            let reread_result = super::with_deadline(
+                deadline,
+                self.do_execute(
+                    &mut reread_rctx,
+                    &mut reread_mismatches,
+                    pk,
+                    stmt.into_owned(),
+                    values,
+                ),
+            )
+            .await;
+            // If the re-read failed, the original mismatches weren't disproved
+            if reread_result.is_ok() {
+                transient_mismatch = reread_mismatches.is_empty();
+                mismatches = reread_mismatches;
+                rctx = reread_rctx;
+            }
+            result = reread_result;
+        }
 
+        for mismatch in &mismatches {
+            mismatch.report(&mut rctx, pk);
+        }
         if let Err(err) = &result {
             rctx.failed_read(err, pk, &cks);
         }
 
-        let mut stats_lock = self.stats.get_shard_mut();
+        let mut stats_lock = self.stats.get_shard_mut_for(ctx.warmup);
         let stats = &mut *stats_lock;
         stats.operations += 1;
         stats.errors += rctx.errors;
         stats.clustering_rows += rctx.rows_read;
+        stats.account_timeout(&result);
+        if downgraded {
+            stats.downgraded_operations += 1;
+        }
+        if transient_mismatch {
+            stats.transient_mismatches += 1;
+        }
         stats_lock.account_latency(ctx);
+        stats_lock.account_routing_latency(misrouted, ctx);
 
         result
     }
 }
 
 impl ReadOperation {
+    async fn audit(
+        &mut self,
+        ctx: &OperationContext,
+        pk: i64,
+        cks: &[i64],
+        stmt: &PreparedStatement,
+        values: Vec<i64>,
+    ) -> Result<ControlFlow<()>> {
+        let mut rctx = ReadContext::default();
+
+        let stmt_one = super::with_consistency(stmt, Some(Consistency::One)).into_owned();
+        let stmt_all = super::with_consistency(stmt, Some(Consistency::All)).into_owned();
+        let result = super::with_deadline(self.operation_deadline, async {
+            let one = self.read_rows(&mut rctx, stmt_one, values.clone()).await?;
+            let all = self.read_rows(&mut rctx, stmt_all, values).await?;
+            anyhow::Ok((one, all))
+        })
+        .await;
+
+        let mut divergent = false;
+        match &result {
+            Ok((one, all)) => {
+                if self.validate_data {
+                    for (ck, v) in all {
+                        if let Err(err) = super::validate_row_data(pk, *ck, v) {
+                            rctx.data_corruption(pk, *ck, &err);
+                        }
+                    }
+                }
+                if let Some(divergence) = Divergence::between(one, all) {
+                    eprintln!("replicas diverged in pk({}): {}", pk, divergence);
+                    warn!(
+                        partition_key = pk,
+                        missing_rows = divergence.missing,
+                        different_rows = divergence.different,
+                        unexpected_rows = divergence.unexpected,
+                        "replicas diverged",
+                    );
+                    divergent = true;
+                }
+            }
+            Err(err) => rctx.failed_read(err, pk, cks),
+        }
+
+        let mut stats_lock = self.stats.get_shard_mut_for(ctx.warmup);
+        let stats = &mut *stats_lock;
+        stats.operations += 1;
+        stats.errors += rctx.errors;
+        stats.clustering_rows += rctx.rows_read;
+        stats.account_timeout(&result);
+        if divergent {
+            stats.divergent_keys += 1;
+        }
+        stats_lock.account_latency(ctx);
+
+        result.map(|_| ControlFlow::Continue(()))
+    }
+
+    async fn read_rows(
+        &self,
+        rctx: &mut ReadContext,
+        stmt: PreparedStatement,
+        values: Vec<i64>,
+    ) -> Result<Vec<(i64, Vec<u8>)>> {
+        let mut iter = self
+            .session
+            .execute_iter(stmt, values)
+            .await?
+            .into_typed::<(i64, Vec<u8>)>();
+        let mut rows = Vec::new();
+        while let Some(row) = iter.try_next().await? {
+            rctx.row_read();
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
     async fn do_execute(
         &mut self,
         rctx: &mut ReadContext,
+        mismatches: &mut Vec<Mismatch>,
         pk: i64,
         stmt: PreparedStatement,
         values: Vec<i64>,
     ) -> Result<ControlFlow<()>> {
+        let expected_rows = self.expected_rows_per_partition.map(|rows_per_partition| {
+            self.read_restriction
+                .expected_row_count(&values[1..], rows_per_partition)
+        });
+        let rows_read_before = rctx.rows_read;
+
         let mut iter = self.session.execute_iter(stmt, values).await?;
 
         while let Some(row) = iter.try_next().await? {
@@ -197,7 +443,7 @@ impl ReadOperation {
                     let (ck, v) = <(i64, Vec<u8>) as FromRow>::from_row(row)?;
                     if self.validate_data {
                         if let Err(err) = super::validate_row_data(pk, ck, &v) {
-                            rctx.data_corruption(pk, ck, &err);
+                            mismatches.push(Mismatch::Row { ck, err });
                         }
                     }
                 }
@@ -207,26 +453,84 @@ impl ReadOperation {
                             row,
                         )?;
                     if self.validate_data {
-                        if let Err(err) =
-                            super::validate_counter_row_data(pk, ck, c1.0, c2.0, c3.0, c4.0, c5.0)
-                        {
-                            rctx.data_corruption(pk, ck, &err);
+                        let result = match &self.counter_tracker {
+                            Some(tracker) => {
+                                tracker.validate(pk, ck, [c1.0, c2.0, c3.0, c4.0, c5.0])
+                            }
+                            None => super::validate_counter_row_data(
+                                pk, ck, c1.0, c2.0, c3.0, c4.0, c5.0,
+                            ),
+                        };
+                        if let Err(err) = result {
+                            mismatches.push(Mismatch::Row { ck, err });
                         }
                     }
                 }
             }
         }
 
+        if let Some(expected) = expected_rows {
+            let actual = rctx.rows_read - rows_read_before;
+            if actual != expected {
+                mismatches.push(Mismatch::RowCount { expected, actual });
+            }
+        }
+
         Ok(ControlFlow::Continue(()))
     }
 }
 
+// Describes how the rows read at ONE differ from the rows read at ALL
+#[derive(Debug, PartialEq)]
+struct Divergence {
+    // Rows returned only at ALL
+    missing: u64,
+    // Rows returned at both levels, but with different values
+    different: u64,
+    // Rows returned only at ONE, e.g. not yet deleted from some replica
+    unexpected: u64,
+}
+
+impl Divergence {
+    fn between(one: &[(i64, Vec<u8>)], all: &[(i64, Vec<u8>)]) -> Option<Self> {
+        let mut one: BTreeMap<_, _> = one.iter().map(|(ck, v)| (*ck, v)).collect();
+        let mut divergence = Divergence {
+            missing: 0,
+            different: 0,
+            unexpected: 0,
+        };
+        for (ck, v) in all {
+            match one.remove(ck) {
+                None => divergence.missing += 1,
+                Some(v_one) if v_one != v => divergence.different += 1,
+                Some(_) => {}
+            }
+        }
+        divergence.unexpected = one.len() as u64;
+
+        (divergence.missing + divergence.different + divergence.unexpected > 0)
+            .then_some(divergence)
+    }
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rows missing, {} rows different and {} unexpected rows at ONE compared to ALL",
+            self.missing, self.different, self.unexpected,
+        )
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum ReadRestrictionKind {
     InRestriction { cks_to_select: u64 },
     BothBounds { cks_to_select: u64 },
     OnlyLowerBound { limit: u64 },
     NoBounds { limit: u64 },
+    // All of the rows of the partition, fetched page by page
+    FullPartition,
 }
 
 impl ReadRestrictionKind {
@@ -242,7 +546,9 @@ impl ReadRestrictionKind {
             }
             ReadRestrictionKind::BothBounds { .. } => "AND ck >= ? AND ck < ?".to_owned(),
             ReadRestrictionKind::OnlyLowerBound { .. } => "AND ck >= ?".to_string(),
-            ReadRestrictionKind::NoBounds { .. } => "".to_string(),
+            ReadRestrictionKind::NoBounds { .. } | ReadRestrictionKind::FullPartition => {
+                "".to_string()
+            }
         }
     }
 
@@ -256,6 +562,30 @@ impl ReadRestrictionKind {
         }
     }
 
+    /// Returns the number of rows that the read for the given clustering keys
+    /// should return, assuming that each partition contains exactly
+    /// `rows_per_partition` rows with clustering keys `0..rows_per_partition`.
+    fn expected_row_count(&self, cks: &[i64], rows_per_partition: u64) -> u64 {
+        let rows_from = |ck: i64| rows_per_partition.saturating_sub(ck.max(0) as u64);
+        match *self {
+            ReadRestrictionKind::InRestriction { cks_to_select } => {
+                if cks_to_select == 0 {
+                    return rows_per_partition;
+                }
+                // The zeros appended by generate_values may duplicate the generated keys
+                let cks: BTreeSet<_> = cks
+                    .iter()
+                    .filter(|ck| (0..rows_per_partition as i64).contains(*ck))
+                    .collect();
+                cks.len() as u64
+            }
+            ReadRestrictionKind::BothBounds { .. } => rows_from(cks[0]) - rows_from(cks[1]),
+            ReadRestrictionKind::OnlyLowerBound { limit } => rows_from(cks[0]).min(limit),
+            ReadRestrictionKind::NoBounds { limit } => rows_per_partition.min(limit),
+            ReadRestrictionKind::FullPartition => rows_per_partition,
+        }
+    }
+
     fn generate_values(&self, workload: &mut dyn Workload) -> Option<(i64, Vec<i64>)> {
         match *self {
             ReadRestrictionKind::InRestriction { cks_to_select } => {
@@ -273,10 +603,70 @@ impl ReadRestrictionKind {
                 let (pk, cks) = workload.generate_keys(1)?;
                 Some((pk, cks))
             }
-            ReadRestrictionKind::NoBounds { .. } => {
+            ReadRestrictionKind::NoBounds { .. } | ReadRestrictionKind::FullPartition => {
                 let (pk, cks) = workload.generate_keys(0)?;
                 Some((pk, cks))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divergence() {
+        let all = [(1, vec![1]), (2, vec![2]), (3, vec![3])];
+        assert_eq!(Divergence::between(&all, &all), None);
+
+        let one = [(0, vec![0]), (2, vec![2]), (3, vec![4])];
+        assert_eq!(
+            Divergence::between(&one, &all),
+            Some(Divergence {
+                missing: 1,
+                different: 1,
+                unexpected: 1,
+            })
+        );
+        assert_eq!(
+            Divergence::between(&[], &all),
+            Some(Divergence {
+                missing: 3,
+                different: 0,
+                unexpected: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_expected_row_count() {
+        let rows = 10;
+
+        let kind = ReadRestrictionKind::OnlyLowerBound { limit: 4 };
+        assert_eq!(kind.expected_row_count(&[0], rows), 4);
+        assert_eq!(kind.expected_row_count(&[8], rows), 2);
+        assert_eq!(kind.expected_row_count(&[10], rows), 0);
+
+        let kind = ReadRestrictionKind::NoBounds { limit: 4 };
+        assert_eq!(kind.expected_row_count(&[], rows), 4);
+        let kind = ReadRestrictionKind::NoBounds { limit: 100 };
+        assert_eq!(kind.expected_row_count(&[], rows), 10);
+
+        let kind = ReadRestrictionKind::BothBounds { cks_to_select: 4 };
+        assert_eq!(kind.expected_row_count(&[2, 6], rows), 4);
+        assert_eq!(kind.expected_row_count(&[8, 12], rows), 2);
+        assert_eq!(kind.expected_row_count(&[12, 16], rows), 0);
+
+        let kind = ReadRestrictionKind::InRestriction { cks_to_select: 4 };
+        assert_eq!(kind.expected_row_count(&[3, 5, 5, 11], rows), 2);
+        assert_eq!(kind.expected_row_count(&[3, 0, 0, 0], rows), 2);
+        let kind = ReadRestrictionKind::InRestriction { cks_to_select: 0 };
+        assert_eq!(kind.expected_row_count(&[], rows), 10);
+
+        let kind = ReadRestrictionKind::FullPartition;
+        assert_eq!(kind.expected_row_count(&[], rows), 10);
+        assert_eq!(kind.get_selector_string(), "");
+        assert_eq!(kind.get_limit_string(), "");
+    }
+}
@@ -1,19 +1,68 @@
+use std::fmt::Display;
 use std::ops::ControlFlow;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use futures::TryStreamExt;
+use scylla::frame::response::result::CqlValue;
+use scylla::frame::value::CqlVarint;
 use scylla::{prepared_statement::PreparedStatement, Session};
+use tokio::time::Instant;
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
 
-use crate::args::ScyllaBenchArgs;
+use crate::args::{Partitioner, ScyllaBenchArgs};
 use crate::operation::ReadContext;
+use crate::scan_coverage::ScanCoverage;
 use crate::stats::ShardedStats;
 
 struct SharedState {
     pub next_range_idx: AtomicU64,
+    pub coverage: Arc<ScanCoverage>,
+    pub row_pacer: Option<RowPacer>,
+}
+
+// Spaces out the rows streamed by all of the scans, so that max-rate
+// is respected within a single range, which may span many pages.
+// The rows are pulled from the iterator only as fast as the rate allows,
+// so the next pages are fetched from the server at the same pace.
+struct RowPacer {
+    base: Instant,
+    interval_nanos: u64,
+    next_nanos: AtomicU64,
+}
+
+impl RowPacer {
+    fn new(rows_per_second: u64) -> Self {
+        Self {
+            base: Instant::now(),
+            interval_nanos: (1_000_000_000 / rows_per_second).max(1),
+            next_nanos: AtomicU64::new(0),
+        }
+    }
+
+    // Reserves the time slot for the next row. The slots which passed
+    // without being used are skipped, so a stall isn't followed by a burst.
+    fn next_slot(&self, now: Instant) -> Instant {
+        let now_nanos = (now - self.base).as_nanos() as u64;
+        let slot = self
+            .next_nanos
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                Some(next.max(now_nanos) + self.interval_nanos)
+            })
+            .unwrap();
+        self.base + Duration::from_nanos(slot.max(now_nanos))
+    }
+
+    async fn wait(&self) {
+        let now = Instant::now();
+        let slot = self.next_slot(now);
+        if slot > now {
+            tokio::time::sleep_until(slot).await;
+        }
+    }
 }
 
 pub(crate) struct ScanOperationFactory {
@@ -38,6 +87,7 @@ impl ScanOperationFactory {
     pub async fn new(
         session: Arc<Session>,
         stats: Arc<ShardedStats>,
+        coverage: Arc<ScanCoverage>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
         let statement_str = format!(
@@ -45,11 +95,16 @@ impl ScanOperationFactory {
             args.table_name,
         );
         let mut statement = session.prepare(statement_str).await?;
-        statement.set_consistency(args.consistency_level);
+        super::set_idempotence(&mut statement, super::StatementKind::Read, &args);
+        statement.set_page_size(args.page_size.try_into()?);
+        statement.set_consistency(args.read_consistency_level());
         statement.set_request_timeout(Some(args.timeout));
+        super::set_node_latency_listener(&mut statement, &args);
 
         let shared_state = Arc::new(SharedState {
             next_range_idx: AtomicU64::new(0),
+            coverage,
+            row_pacer: (args.maximum_rate > 0).then(|| RowPacer::new(args.maximum_rate)),
         });
 
         Ok(Self {
@@ -88,26 +143,30 @@ impl ScanOperation {
 
         let range_idx = range_idx % self.args.range_count;
 
-        let calc_bound = |idx: u64| {
-            let shifted = (idx as u128) << 64;
-            let biased = shifted / self.args.range_count as u128;
-            biased as i64 + i64::MIN
-        };
-
-        let range_begin = calc_bound(range_idx);
-        let range_end = calc_bound(range_idx + 1);
+        let (range_begin, range_end) =
+            token_range(self.args.partitioner, range_idx, self.args.range_count);
 
-        let result = self.do_execute(&mut rctx, range_begin, range_end).await;
+        let result = super::with_deadline(
+            self.args.operation_deadline,
+            self.do_execute(&mut rctx, range_begin, range_end),
+        )
+        .await;
 
-        if let Err(err) = &result {
-            rctx.failed_scan(err, range_begin, range_end);
+        match &result {
+            Ok(_) => self.shared_state.coverage.range_completed(range_idx),
+            Err(err) => {
+                self.shared_state.coverage.range_failed(range_idx);
+                rctx.failed_scan(err, range_begin, range_end);
+            }
         }
 
-        let mut stats_lock = self.stats.get_shard_mut();
+        let mut stats_lock = self.stats.get_shard_mut_for(ctx.warmup);
         let stats = &mut *stats_lock;
         stats.operations += 1;
         stats.errors += rctx.errors;
         stats.clustering_rows += rctx.rows_read;
+        stats.validated_rows += rctx.rows_validated;
+        stats.account_timeout(&result);
         stats_lock.account_latency(ctx);
 
         result
@@ -118,25 +177,153 @@ impl ScanOperation {
     async fn do_execute(
         &mut self,
         rctx: &mut ReadContext,
-        first: i64,
-        last: i64,
+        first: Token,
+        last: Token,
     ) -> Result<ControlFlow<()>> {
+        let bounds = vec![first.to_cql_value(), last.to_cql_value()];
         let iter = self
             .session
-            .execute_iter(self.statement.clone(), (first, last))
+            .execute_iter(self.statement.clone(), bounds)
             .await?;
 
         let mut iter = iter.into_typed::<(i64, i64, Vec<u8>)>();
 
+        // The iterator fetches the next page only after the rows of the previous
+        // one are consumed, and stops after the last page, which may be partial.
+        let mut row_idx = 0u64;
         while let Some((pk, ck, v)) = iter.try_next().await? {
+            if let Some(pacer) = &self.shared_state.row_pacer {
+                pacer.wait().await;
+            }
             rctx.row_read();
-            if self.args.validate_data {
+            if self.args.validate_data && row_idx.is_multiple_of(self.args.scan_validate_every) {
+                rctx.row_validated();
                 if let Err(err) = super::validate_row_data(pk, ck, &v) {
                     rctx.data_corruption(pk, ck, &err);
                 }
             }
+            row_idx += 1;
         }
 
         Ok(ControlFlow::Continue(()))
     }
 }
+
+/// A token of one of the supported partitioners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    // Murmur3Partitioner tokens are bigints
+    Murmur3(i64),
+    // RandomPartitioner tokens are varints in range [0, 2^127]
+    Random(u128),
+}
+
+impl Token {
+    fn to_cql_value(self) -> CqlValue {
+        match self {
+            Token::Murmur3(t) => CqlValue::BigInt(t),
+            Token::Random(t) => {
+                // Two's complement big-endian encoding; the leading zero
+                // byte keeps the value positive.
+                let mut bytes = vec![0u8];
+                bytes.extend_from_slice(&t.to_be_bytes());
+                CqlValue::Varint(CqlVarint::from_signed_bytes_be(bytes))
+            }
+        }
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Murmur3(t) => write!(f, "{}", t),
+            Token::Random(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+// Returns the inclusive bounds of the range_idx-th out of range_count
+// equal parts of the partitioner's token ring.
+fn token_range(partitioner: Partitioner, range_idx: u64, range_count: u64) -> (Token, Token) {
+    let (ring_size, to_token): (u128, fn(u128) -> Token) = match partitioner {
+        Partitioner::Murmur3 => (1 << 64, |offset| {
+            Token::Murmur3((offset as i128 + i64::MIN as i128) as i64)
+        }),
+        Partitioner::Random => ((1 << 127) + 1, Token::Random),
+    };
+
+    let calc_bound = |idx: u64| {
+        let (idx, count) = (idx as u128, range_count as u128);
+        // Split the multiplication so that it doesn't overflow
+        ring_size / count * idx + ring_size % count * idx / count
+    };
+
+    let first = calc_bound(range_idx);
+    let last = calc_bound(range_idx + 1) - 1;
+    (to_token(first), to_token(last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_ranges_cover_ring(partitioner: Partitioner, range_count: u64, min: Token, max: Token) {
+        let ranges: Vec<_> = (0..range_count)
+            .map(|idx| token_range(partitioner, idx, range_count))
+            .collect();
+        assert_eq!(ranges.first().unwrap().0, min);
+        assert_eq!(ranges.last().unwrap().1, max);
+        for pair in ranges.windows(2) {
+            let next_first = match (pair[0].1, pair[1].0) {
+                (Token::Murmur3(last), Token::Murmur3(first)) => first as i128 - last as i128,
+                (Token::Random(last), Token::Random(first)) => (first - last) as i128,
+                _ => unreachable!(),
+            };
+            assert_eq!(next_first, 1);
+        }
+    }
+
+    #[test]
+    fn test_row_pacer() {
+        let pacer = RowPacer::new(1000);
+        let start = pacer.base;
+
+        // Consecutive rows are spaced by the interval
+        assert_eq!(pacer.next_slot(start), start);
+        assert_eq!(pacer.next_slot(start), start + Duration::from_millis(1));
+        assert_eq!(pacer.next_slot(start), start + Duration::from_millis(2));
+
+        // Unused slots are skipped
+        let later = start + Duration::from_secs(1);
+        assert_eq!(pacer.next_slot(later), later);
+        assert_eq!(pacer.next_slot(later), later + Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_murmur3_token_ranges() {
+        for range_count in [1, 2, 3, 7, 1000] {
+            check_ranges_cover_ring(
+                Partitioner::Murmur3,
+                range_count,
+                Token::Murmur3(i64::MIN),
+                Token::Murmur3(i64::MAX),
+            );
+        }
+        assert_eq!(
+            token_range(Partitioner::Murmur3, 1, 2),
+            (Token::Murmur3(0), Token::Murmur3(i64::MAX)),
+        );
+    }
+
+    #[test]
+    fn test_random_token_ranges() {
+        for range_count in [1, 2, 3, 7, 1000] {
+            check_ranges_cover_ring(
+                Partitioner::Random,
+                range_count,
+                Token::Random(0),
+                Token::Random(1 << 127),
+            );
+        }
+    }
+}
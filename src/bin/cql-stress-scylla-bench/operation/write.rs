@@ -1,41 +1,82 @@
 use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
+use futures::{stream, StreamExt, TryStreamExt};
+use rand::distributions::{Distribution as _, WeightedIndex};
 use rand::Rng;
 use scylla::{
     batch::{Batch, BatchType},
     prepared_statement::PreparedStatement,
+    statement::Consistency,
     Session,
 };
-use tracing::error;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{error, warn};
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
 
-use crate::args::ScyllaBenchArgs;
+use crate::args::{ScyllaBenchArgs, TableSelection};
 use crate::distribution::{Distribution, RngGen};
+use crate::node_latency::NodeLatencyTracker;
+use crate::row_checksums::RowChecksumTracker;
 use crate::stats::ShardedStats;
 use crate::workload::{Workload, WorkloadFactory};
 
 pub(crate) struct WriteOperationFactory {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
-    statement: PreparedStatement,
+    statements: Vec<PreparedStatement>,
+    audit_statements: Vec<PreparedStatement>,
     workload_factory: Box<dyn WorkloadFactory>,
     args: Arc<ScyllaBenchArgs>,
+    generated_bytes: Arc<AtomicU64>,
+    in_flight: Option<Arc<Semaphore>>,
 }
 
 struct WriteOperation {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
-    statement: PreparedStatement,
+    statements: Vec<PreparedStatement>,
+    audit_statements: Vec<PreparedStatement>,
+    table_selection: TableSelection,
+    // Number of tables in each keyspace; the statements hold the tables
+    // of each keyspace one after another
+    table_count: u64,
+    // If set, the keyspace of each write is picked randomly by its weight
+    keyspace_choice: Option<WeightedIndex<u64>>,
     workload: Box<dyn Workload>,
     clustering_row_size_dist: Arc<dyn Distribution>,
     rows_per_op: u64,
+    max_batch_bytes: usize,
     validate_data: bool,
+    downgrade_on_unavailable: bool,
+    write_timeout_as_success: bool,
+    operation_deadline: Option<Duration>,
+    audit_interval: u64,
+    node_latency: Option<Arc<NodeLatencyTracker>>,
+    pregenerated_rows: Option<mpsc::Receiver<Vec<u8>>>,
+    // Bytes of data generated by all of the workers
+    generated_bytes: Arc<AtomicU64>,
+    max_bytes: u64,
+    // Bytes of data generated by the last attempt of the current operation,
+    // added to generated_bytes once per operation
+    attempt_bytes: u64,
+    // If set, the clustering keys and checksums of the rows
+    // generated by the current operation
+    data_checksum: Option<Vec<(i64, u64)>>,
+    row_checksums: Option<Arc<RowChecksumTracker>>,
+    // If set, the writes are sent without waiting for their acknowledgement,
+    // and the semaphore bounds the number of the writes in flight
+    in_flight: Option<Arc<Semaphore>>,
 
     gen: RngGen,
+    current_table_idx: usize,
 }
 
 impl WriteOperationFactory {
@@ -45,37 +86,98 @@ impl WriteOperationFactory {
         workload_factory: Box<dyn WorkloadFactory>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
-        let statement_str = format!(
-            "INSERT INTO {} (pk, ck, v) VALUES (?, ?, ?)",
-            args.table_name,
-        );
-        let mut statement = session.prepare(statement_str).await?;
-        statement.set_is_idempotent(true);
-        statement.set_consistency(args.consistency_level);
-        statement.set_request_timeout(Some(args.timeout));
+        let statements = stream::iter(args.table_names())
+            .then(|table_name| prepare_statement(&session, &args, table_name))
+            .try_collect::<Vec<_>>()
+            .await?;
+        let audit_statements = if args.write_audit_interval > 0 {
+            stream::iter(args.table_names())
+                .then(|table_name| prepare_audit_statement(&session, &args, table_name))
+                .try_collect::<Vec<_>>()
+                .await?
+        } else {
+            Vec::new()
+        };
 
         Ok(Self {
             session,
             stats,
-            statement,
+            statements,
+            audit_statements,
             workload_factory,
+            generated_bytes: Arc::new(AtomicU64::new(0)),
+            in_flight: args.in_flight_writes.clone(),
             args,
         })
     }
 }
 
+async fn prepare_statement(
+    session: &Session,
+    args: &ScyllaBenchArgs,
+    table_name: String,
+) -> Result<PreparedStatement> {
+    let statement_str = format!("INSERT INTO {} (pk, ck, v) VALUES (?, ?, ?)", table_name);
+    let mut statement = session.prepare(statement_str).await?;
+    super::set_idempotence(&mut statement, super::StatementKind::Write, args);
+    statement.set_consistency(args.consistency_level);
+    statement.set_request_timeout(Some(args.timeout));
+    super::set_node_latency_listener(&mut statement, args);
+
+    Ok(statement)
+}
+
+async fn prepare_audit_statement(
+    session: &Session,
+    args: &ScyllaBenchArgs,
+    table_name: String,
+) -> Result<PreparedStatement> {
+    let statement_str = format!("SELECT v FROM {} WHERE pk = ? AND ck = ?", table_name);
+    let mut statement = session.prepare(statement_str).await?;
+    super::set_idempotence(&mut statement, super::StatementKind::Read, args);
+    statement.set_consistency(args.write_audit_consistency_level);
+    statement.set_request_timeout(Some(args.timeout));
+    super::set_node_latency_listener(&mut statement, args);
+
+    Ok(statement)
+}
+
 impl OperationFactory for WriteOperationFactory {
     fn create(&self) -> Box<dyn Operation> {
         Box::new(WriteOperation {
             session: Arc::clone(&self.session),
             stats: Arc::clone(&self.stats),
-            statement: self.statement.clone(),
+            statements: self.statements.clone(),
+            audit_statements: self.audit_statements.clone(),
+            table_selection: self.args.table_selection,
+            table_count: self.args.table_count,
+            keyspace_choice: self.args.keyspace_choice(),
             workload: self.workload_factory.create(),
             clustering_row_size_dist: Arc::clone(&self.args.clustering_row_size_dist),
             rows_per_op: self.args.rows_per_request,
+            max_batch_bytes: self.args.max_batch_bytes as usize,
             validate_data: self.args.validate_data,
+            downgrade_on_unavailable: self.args.downgrade_on_unavailable,
+            write_timeout_as_success: self.args.write_timeout_as_success,
+            operation_deadline: self.args.operation_deadline,
+            audit_interval: self.args.write_audit_interval,
+            node_latency: self.args.node_latency.clone(),
+            pregenerated_rows: (self.args.pregenerate_buffer > 0).then(|| {
+                spawn_row_pregenerator(
+                    Arc::clone(&self.args.clustering_row_size_dist),
+                    self.args.validate_data,
+                    self.args.pregenerate_buffer as usize,
+                )
+            }),
+            generated_bytes: Arc::clone(&self.generated_bytes),
+            max_bytes: self.args.max_bytes,
+            attempt_bytes: 0,
+            data_checksum: self.args.data_checksum.then(Vec::new),
+            row_checksums: self.args.row_checksums.clone(),
+            in_flight: self.in_flight.clone(),
 
             gen: RngGen::new(rand::thread_rng().gen()),
+            current_table_idx: 0,
         })
     }
 }
@@ -83,16 +185,51 @@ impl OperationFactory for WriteOperationFactory {
 make_runnable!(WriteOperation);
 impl WriteOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+        if self.max_bytes > 0
+            && self.generated_bytes.load(AtomicOrdering::Relaxed) >= self.max_bytes
+        {
+            return Ok(ControlFlow::Break(()));
+        }
+
         let (pk, cks) = match self.workload.generate_keys(self.rows_per_op as usize) {
             Some((pk, cks)) => (pk, cks),
             None => return Ok(ControlFlow::Break(())),
         };
 
-        let result = match cks.len().cmp(&1) {
-            Ordering::Equal => self.write_single(pk, cks[0]).await,
-            Ordering::Greater => self.write_batch(pk, &cks).await,
-            Ordering::Less => Ok(()),
+        self.current_table_idx = self.select_table(ctx, pk);
+
+        if let Some(in_flight) = &self.in_flight {
+            self.send(ctx, Arc::clone(in_flight), pk, &cks).await?;
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        let deadline = self.operation_deadline;
+        let mut result = super::with_deadline(deadline, self.write(pk, &cks, None)).await;
+        let mut downgraded = false;
+        let downgraded_cl = match &result {
+            Err(err) if self.downgrade_on_unavailable => super::downgraded_consistency(err),
+            _ => None,
         };
+        if let Some(cl) = downgraded_cl {
+            result = super::with_deadline(deadline, self.write(pk, &cks, Some(cl))).await;
+            downgraded = result.is_ok();
+        }
+        // Counted once, even if the write was repeated at a lower consistency
+        self.generated_bytes
+            .fetch_add(self.attempt_bytes, AtomicOrdering::Relaxed);
+
+        // The timed out write might have been applied, so it's neither
+        // counted as an error nor retried
+        let uncertain = self.write_timeout_as_success
+            && matches!(&result, Err(err) if super::is_write_timeout(err));
+        if uncertain {
+            warn!(
+                partition_key = pk,
+                clustering_keys = ?cks,
+                "write timed out, counting it as a success",
+            );
+            result = Ok(0);
+        }
 
         if let Err(err) = result.as_ref() {
             error!(
@@ -103,8 +240,49 @@ impl WriteOperation {
             );
         }
 
-        let mut stats = self.stats.get_shard_mut();
-        stats.account_op(ctx, &result, cks.len());
+        {
+            let mut stats = self.stats.get_shard_mut_for(ctx.warmup);
+            stats.account_op(ctx, &result, cks.len());
+            stats.account_timeout(&result);
+            if downgraded {
+                stats.downgraded_operations += 1;
+            }
+            if uncertain {
+                stats.uncertain_writes += 1;
+            }
+            if let Ok(batches_sent) = result {
+                stats.split_batches += batches_sent.saturating_sub(1) as u64;
+                stats.account_partition_rows(pk, cks.len());
+            }
+        }
+        // The uncertain writes might not have been applied,
+        // so they can't be included in the checksum
+        if let (Some(checksums), Some(tracker), Ok(_), false) =
+            (&self.data_checksum, &self.row_checksums, &result, uncertain)
+        {
+            tracker.record_write(self.current_table_idx, pk, checksums);
+        }
+
+        // The audit is done after the latency of the write was accounted for,
+        // so that the latency of the read doesn't end up in the histogram
+        if result.is_ok()
+            && self.audit_interval > 0
+            && ctx.operation_id.is_multiple_of(self.audit_interval)
+        {
+            // Audit the last row, which was sent last in the case of batches
+            let audit_result = self.audit(pk, *cks.last().unwrap()).await;
+            let mut stats = self.stats.get_shard_mut_for(ctx.warmup);
+            stats.audited_writes += 1;
+            if let Err(err) = audit_result {
+                error!(
+                    error = %err,
+                    partition_key = pk,
+                    clustering_key = cks.last().unwrap(),
+                    "write couldn't be confirmed",
+                );
+                stats.unconfirmed_writes += 1;
+            }
+        }
 
         result?;
         Ok(ControlFlow::Continue(()))
@@ -112,35 +290,294 @@ impl WriteOperation {
 }
 
 impl WriteOperation {
-    async fn write_single(&mut self, pk: i64, ck: i64) -> Result<()> {
-        let data = self.generate_row(pk, ck);
-        // execute_unpaged, since it's an INSERT statement.
-        self.session
-            .execute_unpaged(&self.statement, (pk, ck, data))
-            .await?;
+    fn select_table(&mut self, ctx: &OperationContext, pk: i64) -> usize {
+        let table_count = self.table_count;
+        let idx = match self.table_selection {
+            TableSelection::RoundRobin => ctx.operation_id % table_count,
+            TableSelection::Hash => {
+                let mut hasher = DefaultHasher::new();
+                pk.hash(&mut hasher);
+                hasher.finish() % table_count
+            }
+        };
+        let ks_idx = match &self.keyspace_choice {
+            Some(choice) => choice.sample(&mut self.gen),
+            None => 0,
+        };
+        ks_idx * table_count as usize + idx as usize
+    }
+
+    // Returns the number of requests that were sent
+    async fn write(&mut self, pk: i64, cks: &[i64], cl: Option<Consistency>) -> Result<usize> {
+        // The rows are generated again if the write is repeated
+        if let Some(checksums) = &mut self.data_checksum {
+            checksums.clear();
+        }
+        let rows = self.generate_rows(pk, cks);
+        let statement = super::with_consistency(&self.statements[self.current_table_idx], cl);
+        write_rows(
+            &self.session,
+            &statement,
+            self.node_latency.as_ref(),
+            &rows,
+            self.max_batch_bytes,
+        )
+        .await
+    }
+
+    // Sends the rows without waiting for the acknowledgement. The write
+    // is accounted for by a background task once it completes, and only
+    // then the write releases its place among the writes in flight.
+    async fn send(
+        &mut self,
+        ctx: &OperationContext,
+        in_flight: Arc<Semaphore>,
+        pk: i64,
+        cks: &[i64],
+    ) -> Result<()> {
+        let permit = in_flight.acquire_owned().await?;
+        let rows = self.generate_rows(pk, cks);
+        self.generated_bytes
+            .fetch_add(self.attempt_bytes, AtomicOrdering::Relaxed);
+        let session = Arc::clone(&self.session);
+        let stats = Arc::clone(&self.stats);
+        let statement = self.statements[self.current_table_idx].clone();
+        let node_latency = self.node_latency.clone();
+        let max_batch_bytes = self.max_batch_bytes;
+        let deadline = self.operation_deadline;
+        let (actual_start_time, scheduled_start_time, warmup) =
+            (ctx.actual_start_time, ctx.scheduled_start_time, ctx.warmup);
+
+        self.stats.get_shard_mut_for(ctx.warmup).sent_writes += 1;
+        tokio::task::spawn(async move {
+            let write = write_rows(
+                &session,
+                &statement,
+                node_latency.as_ref(),
+                &rows,
+                max_batch_bytes,
+            );
+            let result = super::with_deadline(deadline, write).await;
+            drop(permit);
+
+            if let Err(err) = result.as_ref() {
+                error!(
+                    error = %err,
+                    partition_key = pk,
+                    clustering_keys = ?rows.iter().map(|(_, ck, _)| ck).collect::<Vec<_>>(),
+                    "write error",
+                );
+            }
+
+            let mut stats = stats.get_shard_mut_for(warmup);
+            stats.operations += 1;
+            match &result {
+                Ok(batches_sent) => {
+                    stats.clustering_rows += rows.len() as u64;
+                    stats.split_batches += batches_sent.saturating_sub(1) as u64;
+                    stats.account_partition_rows(pk, rows.len());
+                    stats.account_latency_since(actual_start_time, scheduled_start_time);
+                }
+                Err(_) => stats.errors += 1,
+            }
+            stats.account_timeout(&result);
+        });
         Ok(())
     }
 
-    async fn write_batch(&mut self, pk: i64, cks: &[i64]) -> Result<()> {
-        let mut batch = Batch::new(BatchType::Unlogged);
-        batch.set_is_idempotent(true);
-        batch.set_consistency(self.statement.get_consistency().unwrap());
-        let mut vals = Vec::with_capacity(cks.len());
-        for ck in cks {
-            let data = self.generate_row(pk, *ck);
-            batch.append_statement(self.statement.clone());
-            vals.push((pk, ck, data));
+    fn generate_rows(&mut self, pk: i64, cks: &[i64]) -> Vec<Row> {
+        self.attempt_bytes = 0;
+        cks.iter()
+            .map(|ck| (pk, *ck, self.generate_row(pk, *ck)))
+            .collect()
+    }
+
+    // Reads the row back at the audit consistency level and checks that it's there.
+    async fn audit(&self, pk: i64, ck: i64) -> Result<()> {
+        let statement = &self.audit_statements[self.current_table_idx];
+        let (data,) = self
+            .session
+            .execute_unpaged(statement, (pk, ck))
+            .await?
+            .maybe_first_row_typed::<(Vec<u8>,)>()?
+            .ok_or_else(|| anyhow::anyhow!("the written row is missing"))?;
+        if self.validate_data {
+            super::validate_row_data(pk, ck, &data)?;
         }
-        self.session.batch(&batch, vals).await?;
         Ok(())
     }
 
     fn generate_row(&mut self, pk: i64, ck: i64) -> Vec<u8> {
-        let clen = self.clustering_row_size_dist.get_u64(&mut self.gen) as usize;
-        if self.validate_data {
-            super::generate_row_data(pk, ck, clen)
-        } else {
-            vec![0; clen]
+        // Generate the row here if the pregenerator can't keep up
+        let pregenerated = self
+            .pregenerated_rows
+            .as_mut()
+            .and_then(|r| r.try_recv().ok());
+        let data = match pregenerated {
+            Some(mut data) => {
+                if self.validate_data {
+                    super::set_row_data_keys(&mut data, pk, ck);
+                }
+                data
+            }
+            None => {
+                let clen = self.clustering_row_size_dist.get_u64(&mut self.gen) as usize;
+                if self.validate_data {
+                    super::generate_row_data(pk, ck, clen)
+                } else {
+                    vec![0; clen]
+                }
+            }
+        };
+        self.attempt_bytes += data.len() as u64;
+        if let Some(checksums) = &mut self.data_checksum {
+            checksums.push((ck, super::row_checksum(pk, ck, &data)));
+        }
+        data
+    }
+}
+
+// Generates the data of the written rows on a background task, so that
+// the worker only has to fill in the keys. The data is generated with
+// placeholder keys, because the keys aren't known in advance.
+// The task stops once the receiver is dropped.
+fn spawn_row_pregenerator(
+    clustering_row_size_dist: Arc<dyn Distribution>,
+    validate_data: bool,
+    buffer: usize,
+) -> mpsc::Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel(buffer);
+    let mut gen = RngGen::new(rand::thread_rng().gen());
+    tokio::task::spawn(async move {
+        loop {
+            let clen = clustering_row_size_dist.get_u64(&mut gen) as usize;
+            let data = if validate_data {
+                super::generate_row_data(0, 0, clen)
+            } else {
+                vec![0; clen]
+            };
+            if sender.send(data).await.is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+type Row = (i64, i64, Vec<u8>);
+
+// Writes a single row with the statement, and multiple rows in a batch,
+// or in multiple consecutive batches if their estimated size exceeds the limit.
+// Returns the number of requests that were sent.
+async fn write_rows(
+    session: &Session,
+    statement: &PreparedStatement,
+    node_latency: Option<&Arc<NodeLatencyTracker>>,
+    rows: &[Row],
+    max_batch_bytes: usize,
+) -> Result<usize> {
+    match rows.len().cmp(&1) {
+        Ordering::Equal => {
+            // execute_unpaged, since it's an INSERT statement.
+            session.execute_unpaged(statement, &rows[0]).await?;
+            Ok(1)
+        }
+        Ordering::Greater => {
+            let chunks = split_batch(rows, max_batch_bytes);
+            for chunk in chunks.iter() {
+                write_batch(session, statement, node_latency, chunk).await?;
+            }
+            Ok(chunks.len())
+        }
+        Ordering::Less => Ok(0),
+    }
+}
+
+async fn write_batch(
+    session: &Session,
+    statement: &PreparedStatement,
+    node_latency: Option<&Arc<NodeLatencyTracker>>,
+    rows: &[Row],
+) -> Result<()> {
+    let mut batch = Batch::new(BatchType::Unlogged);
+    batch.set_is_idempotent(statement.get_is_idempotent());
+    batch.set_consistency(statement.get_consistency().unwrap());
+    if let Some(tracker) = node_latency {
+        batch.set_history_listener(Arc::clone(tracker) as _);
+    }
+    for _ in rows {
+        batch.append_statement(statement.clone());
+    }
+    session.batch(&batch, rows).await?;
+    Ok(())
+}
+
+// Estimates the size of a written row. The server compares the size
+// of the batch's data against its batch size thresholds.
+fn estimated_row_size(row: &Row) -> usize {
+    2 * std::mem::size_of::<i64>() + row.2.len()
+}
+
+// Splits the rows into consecutive chunks whose estimated size doesn't
+// exceed max_bytes. A row larger than the limit is put in its own chunk.
+// A limit of 0 means no limit.
+fn split_batch(rows: &[Row], max_bytes: usize) -> Vec<&[Row]> {
+    if max_bytes == 0 {
+        return vec![rows];
+    }
+    let mut chunks = Vec::new();
+    let (mut start, mut size) = (0, 0);
+    for (idx, row) in rows.iter().enumerate() {
+        let row_size = estimated_row_size(row);
+        if idx > start && size + row_size > max_bytes {
+            chunks.push(&rows[start..idx]);
+            (start, size) = (idx, 0);
+        }
+        size += row_size;
+    }
+    chunks.push(&rows[start..]);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distribution::Fixed;
+    use crate::operation::{set_row_data_keys, validate_row_data};
+
+    use super::*;
+
+    fn rows_with_sizes(data_sizes: &[usize]) -> Vec<Row> {
+        data_sizes
+            .iter()
+            .enumerate()
+            .map(|(ck, size)| (0, ck as i64, vec![0; *size]))
+            .collect()
+    }
+
+    fn chunk_lengths(chunks: &[&[Row]]) -> Vec<usize> {
+        chunks.iter().map(|c| c.len()).collect()
+    }
+
+    #[test]
+    fn test_split_batch() {
+        // Every row is 16 bytes of keys + data
+        let rows = rows_with_sizes(&[84, 84, 84, 184, 0]);
+
+        assert_eq!(chunk_lengths(&split_batch(&rows, 0)), vec![5]);
+        assert_eq!(chunk_lengths(&split_batch(&rows, 10_000)), vec![5]);
+        assert_eq!(chunk_lengths(&split_batch(&rows, 200)), vec![2, 1, 1, 1]);
+        assert_eq!(chunk_lengths(&split_batch(&rows, 216)), vec![2, 1, 2]);
+        assert_eq!(chunk_lengths(&split_batch(&rows, 100)), vec![1, 1, 1, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_row_pregenerator() {
+        let mut receiver = spawn_row_pregenerator(Arc::new(Fixed(100)), true, 4);
+        for ck in 0..10 {
+            let mut data = receiver.recv().await.unwrap();
+            assert_eq!(data.len(), 100);
+            set_row_data_keys(&mut data, 1, ck);
+            validate_row_data(1, ck, &data).unwrap();
         }
     }
 }
@@ -0,0 +1,88 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, Layer};
+
+// The driver transparently reprepares a statement on the connection
+// which returned an Unprepared error and doesn't report it in any other way
+// than a debug-level log message.
+const DRIVER_CONNECTION_TARGET: &str = "scylla::transport::connection";
+const UNPREPARED_MARKER: &str = "DbError::Unprepared";
+
+/// Counts statement reprepares performed by the driver.
+///
+/// Works as a tracing layer which intercepts the driver's log messages
+/// emitted right before a statement is reprepared.
+#[derive(Clone, Default)]
+pub struct ReprepareCounter {
+    count: Arc<AtomicU64>,
+}
+
+impl ReprepareCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of reprepares observed so far.
+    pub fn get(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns a filter which lets through only the driver events
+    /// that the counter is interested in.
+    pub fn filter() -> Targets {
+        Targets::new().with_target(DRIVER_CONNECTION_TARGET, Level::DEBUG)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ReprepareCounter {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = UnpreparedVisitor { matched: false };
+        event.record(&mut visitor);
+        if visitor.matched {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+struct UnpreparedVisitor {
+    matched: bool,
+}
+
+impl Visit for UnpreparedVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" && format!("{:?}", value).contains(UNPREPARED_MARKER) {
+            self.matched = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn test_reprepare_counter() {
+        let counter = ReprepareCounter::new();
+        let subscriber = tracing_subscriber::registry()
+            .with(counter.clone().with_filter(ReprepareCounter::filter()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(
+                target: "scylla::transport::connection",
+                "Connection::execute: Got DbError::Unprepared - repreparing statement with id {:?}",
+                [1u8, 2, 3],
+            );
+            tracing::debug!(target: "scylla::transport::connection", "unrelated message");
+            tracing::debug!(target: "other_crate", "Got DbError::Unprepared");
+        });
+
+        assert_eq!(counter.get(), 1);
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::args::{show_mode, show_workload, ScyllaBenchArgs};
+use crate::stats::Stats;
+
+const CREATE_RUNS_TABLE: &str = "CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    finished_at_ms INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    mode TEXT NOT NULL,
+    workload TEXT NOT NULL,
+    operations INTEGER NOT NULL,
+    rows INTEGER NOT NULL,
+    errors INTEGER NOT NULL,
+    client_timeouts INTEGER NOT NULL,
+    ops_per_second REAL NOT NULL,
+    rows_per_second REAL NOT NULL,
+    latency_p50_ns INTEGER,
+    latency_p90_ns INTEGER,
+    latency_p99_ns INTEGER,
+    latency_p999_ns INTEGER,
+    latency_max_ns INTEGER,
+    tags TEXT NOT NULL,
+    command_line TEXT NOT NULL
+)";
+
+/// The results of a run, as stored in the results database.
+pub struct RunSummary {
+    pub finished_at: SystemTime,
+    pub duration: Duration,
+    pub mode: &'static str,
+    pub workload: &'static str,
+    pub operations: u64,
+    pub rows: u64,
+    pub errors: u64,
+    pub client_timeouts: u64,
+    // p50, p90, p99, p99.9 and max, if the latency was measured
+    pub latency_ns: Option<[u64; 5]>,
+    pub tags: String,
+    pub command_line: String,
+}
+
+impl RunSummary {
+    pub fn new(args: &ScyllaBenchArgs, stats: &Stats, duration: Duration) -> Self {
+        let latency_ns = stats.get_histogram(args.latency_type).map(|histogram| {
+            let to_nanos = |d: u64| d * stats.latency_resolution;
+            let [p50, p90, p99, p999] =
+                [0.5, 0.9, 0.99, 0.999].map(|q| to_nanos(histogram.value_at_quantile(q)));
+            [p50, p90, p99, p999, to_nanos(histogram.max())]
+        });
+        Self {
+            finished_at: SystemTime::now(),
+            duration,
+            mode: show_mode(&args.mode),
+            workload: show_workload(&args.workload),
+            operations: stats.operations,
+            rows: stats.clustering_rows,
+            errors: stats.errors,
+            client_timeouts: stats.client_timeouts,
+            latency_ns,
+            tags: args.results_tags.clone(),
+            command_line: std::env::args().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Appends the summary of the run to the results database,
+/// creating the database if it doesn't exist.
+pub fn append_run(path: &str, summary: &RunSummary) -> Result<()> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open the results database {}", path))?;
+    insert_run(&conn, summary)
+        .with_context(|| format!("Failed to append the run to the results database {}", path))
+}
+
+fn insert_run(conn: &Connection, summary: &RunSummary) -> Result<()> {
+    conn.execute(CREATE_RUNS_TABLE, [])?;
+
+    let secs = summary.duration.as_secs_f64();
+    let latency = |idx: usize| summary.latency_ns.map(|l| l[idx] as i64);
+    conn.execute(
+        "INSERT INTO runs (finished_at_ms, duration_ms, mode, workload, operations, rows, \
+        errors, client_timeouts, ops_per_second, rows_per_second, latency_p50_ns, \
+        latency_p90_ns, latency_p99_ns, latency_p999_ns, latency_max_ns, tags, command_line) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            summary
+                .finished_at
+                .duration_since(SystemTime::UNIX_EPOCH)?
+                .as_millis() as i64,
+            summary.duration.as_millis() as i64,
+            summary.mode,
+            summary.workload,
+            summary.operations as i64,
+            summary.rows as i64,
+            summary.errors as i64,
+            summary.client_timeouts as i64,
+            summary.operations as f64 / secs,
+            summary.rows as f64 / secs,
+            latency(0),
+            latency(1),
+            latency(2),
+            latency(3),
+            latency(4),
+            summary.tags,
+            summary.command_line,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(latency_ns: Option<[u64; 5]>) -> RunSummary {
+        RunSummary {
+            finished_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            duration: Duration::from_secs(10),
+            mode: "write",
+            workload: "uniform",
+            operations: 1000,
+            rows: 2000,
+            errors: 1,
+            client_timeouts: 0,
+            latency_ns,
+            tags: "commit=abc,cluster=3x".to_owned(),
+            command_line: "scylla-bench -mode=write -workload=uniform".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_append_runs() {
+        let conn = Connection::open_in_memory().unwrap();
+        insert_run(&conn, &summary(Some([1, 2, 3, 4, 5]))).unwrap();
+        insert_run(&conn, &summary(None)).unwrap();
+
+        let runs: Vec<(String, f64, Option<i64>, String)> = conn
+            .prepare("SELECT mode, ops_per_second, latency_p99_ns, tags FROM runs ORDER BY id")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            runs,
+            [
+                (
+                    "write".to_owned(),
+                    100.0,
+                    Some(3),
+                    "commit=abc,cluster=3x".to_owned()
+                ),
+                (
+                    "write".to_owned(),
+                    100.0,
+                    None,
+                    "commit=abc,cluster=3x".to_owned()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+
+/// Keeps the checksum of the last successful write of each row, so that
+/// -data-checksum describes the contents of the table even if some of the
+/// rows were overwritten, possibly with different data.
+///
+/// The rows are identified by the index of their table and their keys.
+#[derive(Debug, Default)]
+pub struct RowChecksumTracker {
+    rows: Mutex<HashMap<(usize, i64, i64), u64>>,
+}
+
+impl RowChecksumTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_write(&self, table_idx: usize, pk: i64, rows: &[(i64, u64)]) {
+        let mut written = self.rows.lock();
+        for (ck, checksum) in rows {
+            written.insert((table_idx, pk, *ck), *checksum);
+        }
+    }
+
+    /// Returns the wrapping sum of the checksums and the number of rows.
+    pub fn summary(&self) -> (u64, u64) {
+        let written = self.rows.lock();
+        let checksum = written
+            .values()
+            .fold(0u64, |sum, checksum| sum.wrapping_add(*checksum));
+        (checksum, written.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overwritten_rows_are_counted_once() {
+        let tracker = RowChecksumTracker::new();
+        tracker.record_write(0, 1, &[(0, 10), (1, 20)]);
+        tracker.record_write(0, 1, &[(1, 5)]);
+        tracker.record_write(1, 1, &[(1, u64::MAX)]);
+        assert_eq!(tracker.summary(), (14, 3));
+    }
+}
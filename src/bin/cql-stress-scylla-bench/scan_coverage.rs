@@ -0,0 +1,108 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use anyhow::Result;
+
+/// Tracks which token ranges were scanned during a scan run.
+///
+/// Used to verify the completeness of full table scans: a range whose
+/// every scan attempt failed is reported as skipped.
+pub struct ScanCoverage {
+    completions: Vec<AtomicU32>,
+    failures: Vec<AtomicU32>,
+}
+
+/// Summary of the token ring coverage, computed at the end of the run.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CoverageSummary {
+    pub range_count: usize,
+    /// Ranges scanned successfully at least once.
+    pub covered: usize,
+    /// Ranges which were attempted, but never scanned successfully.
+    pub skipped: usize,
+    /// Ranges scanned successfully more than once.
+    pub rescanned: usize,
+}
+
+impl ScanCoverage {
+    pub fn new(range_count: u64) -> Self {
+        let new_counters = || (0..range_count).map(|_| AtomicU32::new(0)).collect();
+        Self {
+            completions: new_counters(),
+            failures: new_counters(),
+        }
+    }
+
+    pub fn range_completed(&self, range_idx: u64) {
+        self.completions[range_idx as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn range_failed(&self, range_idx: u64) {
+        self.failures[range_idx as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn summary(&self) -> CoverageSummary {
+        let mut summary = CoverageSummary {
+            range_count: self.completions.len(),
+            covered: 0,
+            skipped: 0,
+            rescanned: 0,
+        };
+        for (completions, failures) in self.completions.iter().zip(&self.failures) {
+            match completions.load(Ordering::Relaxed) {
+                0 if failures.load(Ordering::Relaxed) > 0 => summary.skipped += 1,
+                0 => {}
+                1 => summary.covered += 1,
+                _ => {
+                    summary.covered += 1;
+                    summary.rescanned += 1;
+                }
+            }
+        }
+        summary
+    }
+}
+
+impl CoverageSummary {
+    pub fn print(&self, out: &mut impl Write) -> Result<()> {
+        let percent = 100.0 * self.covered as f64 / self.range_count as f64;
+        writeln!(
+            out,
+            "Ring coverage:\t{:.2}% ({}/{} ranges)",
+            percent, self.covered, self.range_count,
+        )?;
+        if self.skipped != 0 {
+            writeln!(out, "Skipped ranges:\t{}", self.skipped)?;
+        }
+        if self.rescanned != 0 {
+            writeln!(out, "Rescanned ranges:\t{}", self.rescanned)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_coverage_summary() {
+        let coverage = ScanCoverage::new(5);
+        coverage.range_completed(0);
+        coverage.range_failed(1);
+        coverage.range_completed(1);
+        coverage.range_failed(2);
+        coverage.range_completed(3);
+        coverage.range_completed(3);
+
+        assert_eq!(
+            coverage.summary(),
+            CoverageSummary {
+                range_count: 5,
+                covered: 3,
+                skipped: 1,
+                rescanned: 1,
+            },
+        );
+    }
+}
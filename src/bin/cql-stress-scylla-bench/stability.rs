@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+/// Detects when the run has reached a stable state.
+///
+/// The run is considered stable when, in the last `required_intervals`
+/// reporting intervals, both the throughput and the p99 latency stayed
+/// within a band whose width is at most `tolerance` times their mean.
+pub struct StabilityDetector {
+    required_intervals: usize,
+    tolerance: f64,
+    history: VecDeque<IntervalSample>,
+}
+
+struct IntervalSample {
+    operations: u64,
+    p99_latency: Option<u64>,
+}
+
+impl StabilityDetector {
+    pub fn new(required_intervals: u64, tolerance_percent: u64) -> Self {
+        Self {
+            required_intervals: required_intervals as usize,
+            tolerance: tolerance_percent as f64 / 100.0,
+            history: VecDeque::with_capacity(required_intervals as usize + 1),
+        }
+    }
+
+    /// Records the measurements from a single reporting interval
+    /// and returns whether the run is stable.
+    ///
+    /// `p99_latency` should be `None` if latency is not measured,
+    /// in which case only the throughput is taken into account.
+    pub fn observe(&mut self, operations: u64, p99_latency: Option<u64>) -> bool {
+        self.history.push_back(IntervalSample {
+            operations,
+            p99_latency,
+        });
+        if self.history.len() > self.required_intervals {
+            self.history.pop_front();
+        }
+        if self.history.len() < self.required_intervals {
+            return false;
+        }
+
+        let operations_stable = self.is_within_band(self.history.iter().map(|s| s.operations));
+        let latency_stable = match self
+            .history
+            .iter()
+            .map(|s| s.p99_latency)
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(latencies) => self.is_within_band(latencies.into_iter()),
+            None => true,
+        };
+        operations_stable && latency_stable
+    }
+
+    fn is_within_band(&self, values: impl Iterator<Item = u64> + Clone) -> bool {
+        let (min, max) = values
+            .clone()
+            .fold((u64::MAX, 0), |(min, max), v| (min.min(v), max.max(v)));
+        let count = values.clone().count();
+        let mean = values.sum::<u64>() as f64 / count as f64;
+
+        // A stalled run is not a stable one
+        mean > 0.0 && (max - min) as f64 <= self.tolerance * mean
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_after_required_intervals() {
+        let mut detector = StabilityDetector::new(3, 10);
+        assert!(!detector.observe(1000, Some(100)));
+        assert!(!detector.observe(1020, Some(102)));
+        assert!(detector.observe(990, Some(98)));
+    }
+
+    #[test]
+    fn test_unstable_throughput() {
+        let mut detector = StabilityDetector::new(3, 10);
+        detector.observe(1000, None);
+        detector.observe(1500, None);
+        assert!(!detector.observe(1000, None));
+        assert!(!detector.observe(1000, None));
+
+        // The outlier leaves the window
+        assert!(detector.observe(1000, None));
+    }
+
+    #[test]
+    fn test_unstable_latency() {
+        let mut detector = StabilityDetector::new(2, 5);
+        detector.observe(1000, Some(100));
+        assert!(!detector.observe(1000, Some(200)));
+        assert!(!detector.observe(1000, Some(100)));
+        assert!(detector.observe(1000, Some(101)));
+    }
+
+    #[test]
+    fn test_stalled_run_is_not_stable() {
+        let mut detector = StabilityDetector::new(2, 5);
+        detector.observe(0, None);
+        assert!(!detector.observe(0, None));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::Write;
 use std::ops::Range;
 use std::path::Path;
@@ -14,6 +15,7 @@ use cql_stress::sharded_stats;
 use crate::args::ScyllaBenchArgs;
 use crate::gocompat::strconv::format_duration;
 use crate::histogram_log_writer::{HistogramLogOptions, HistogramLogWriter};
+use crate::operation::{ClientTimeoutError, ErrorCategory};
 
 pub type ShardedStats = sharded_stats::ShardedStats<StatsFactory>;
 
@@ -21,6 +23,8 @@ pub struct StatsFactory {
     measure_latency: bool,
     latency_sig_fig: u8,
     latency_resolution: u64,
+    record_partition_widths: bool,
+    measure_routing: bool,
 }
 
 impl StatsFactory {
@@ -29,6 +33,8 @@ impl StatsFactory {
             measure_latency: args.measure_latency,
             latency_sig_fig: args.hdr_latency_sig_fig as u8,
             latency_resolution: args.hdr_latency_resolution,
+            record_partition_widths: args.report_partition_widths,
+            measure_routing: args.misrouting.is_some(),
         }
     }
 
@@ -45,10 +51,29 @@ impl sharded_stats::StatsFactory for StatsFactory {
             operations: 0,
             clustering_rows: 0,
             errors: 0,
+            downgraded_operations: 0,
+            split_batches: 0,
+            audited_writes: 0,
+            unconfirmed_writes: 0,
+            uncertain_writes: 0,
+            sent_writes: 0,
+            transient_mismatches: 0,
+            divergent_keys: 0,
+            validated_rows: 0,
+            data_checksum: 0,
+            checksummed_rows: 0,
+            timeouts: 0,
+            client_timeouts: 0,
+            reprepares: 0,
             latencies: self.measure_latency.then(|| LatencyHistograms {
                 raw: self.create_histogram(),
                 co_fixed: self.create_histogram(),
             }),
+            partition_widths: self.record_partition_widths.then(HashMap::new),
+            routing_latencies: self.measure_routing.then(|| RoutingHistograms {
+                routed: self.create_histogram(),
+                misrouted: self.create_histogram(),
+            }),
 
             latency_resolution: self.latency_resolution,
         }
@@ -59,9 +84,41 @@ pub struct Stats {
     pub operations: u64,
     pub clustering_rows: u64,
     pub errors: u64,
+    pub downgraded_operations: u64,
+    // Additional batches sent because of the batch size limit
+    pub split_batches: u64,
+    // Written rows read back by the write audit, and those which couldn't be confirmed
+    pub audited_writes: u64,
+    pub unconfirmed_writes: u64,
+    // Timed out writes counted as successes, because they might have been applied
+    pub uncertain_writes: u64,
+    // Writes sent without waiting for the acknowledgement, see -fire-and-forget;
+    // they are counted as operations only once they complete
+    pub sent_writes: u64,
+    // Reads which failed validation, but whose re-read passed it
+    pub transient_mismatches: u64,
+    // Keys for which the reads at ONE and ALL returned different rows
+    pub divergent_keys: u64,
+    // Rows whose data was validated, reported only by the scans
+    pub validated_rows: u64,
+    // Order-independent checksum of the written rows, see -data-checksum;
+    // filled in from the RowChecksumTracker at the end of the run
+    pub data_checksum: u64,
+    pub checksummed_rows: u64,
+    // Failed attempts which timed out, on the server, in the driver or on the client,
+    // and the subset of them which exceeded the -operation-deadline
+    pub timeouts: u64,
+    pub client_timeouts: u64,
+    // Statement reprepares done by the driver, filled in only for the final summary
+    pub reprepares: u64,
 
     pub latencies: Option<LatencyHistograms>,
 
+    // Number of clustering rows generated for each partition key
+    pub partition_widths: Option<HashMap<i64, u64>>,
+
+    pub routing_latencies: Option<RoutingHistograms>,
+
     // Do not change in workloads, this should be constant
     pub latency_resolution: u64,
 }
@@ -72,33 +129,84 @@ pub struct LatencyHistograms {
     pub co_fixed: Histogram<u64>,
 }
 
+pub struct RoutingHistograms {
+    // Raw latency of the requests sent to a replica by the token-aware routing,
+    // and of those deliberately sent to a non-replica, see -misrouted-fraction
+    pub routed: Histogram<u64>,
+    pub misrouted: Histogram<u64>,
+}
+
 impl sharded_stats::Stats for Stats {
     fn clear(&mut self) {
         self.operations = 0;
         self.clustering_rows = 0;
         self.errors = 0;
+        self.downgraded_operations = 0;
+        self.split_batches = 0;
+        self.audited_writes = 0;
+        self.unconfirmed_writes = 0;
+        self.uncertain_writes = 0;
+        self.sent_writes = 0;
+        self.transient_mismatches = 0;
+        self.divergent_keys = 0;
+        self.validated_rows = 0;
+        self.data_checksum = 0;
+        self.checksummed_rows = 0;
+        self.timeouts = 0;
+        self.client_timeouts = 0;
+        self.reprepares = 0;
         if let Some(ls) = &mut self.latencies {
             ls.raw.reset();
             ls.co_fixed.reset();
         }
+        if let Some(pws) = &mut self.partition_widths {
+            pws.clear();
+        }
+        if let Some(rs) = &mut self.routing_latencies {
+            rs.routed.reset();
+            rs.misrouted.reset();
+        }
     }
 
     fn combine(&mut self, other: &Self) {
         self.operations += other.operations;
         self.clustering_rows += other.clustering_rows;
         self.errors += other.errors;
+        self.downgraded_operations += other.downgraded_operations;
+        self.split_batches += other.split_batches;
+        self.audited_writes += other.audited_writes;
+        self.unconfirmed_writes += other.unconfirmed_writes;
+        self.uncertain_writes += other.uncertain_writes;
+        self.sent_writes += other.sent_writes;
+        self.transient_mismatches += other.transient_mismatches;
+        self.divergent_keys += other.divergent_keys;
+        self.validated_rows += other.validated_rows;
+        self.data_checksum = self.data_checksum.wrapping_add(other.data_checksum);
+        self.checksummed_rows += other.checksummed_rows;
+        self.timeouts += other.timeouts;
+        self.client_timeouts += other.client_timeouts;
+        self.reprepares += other.reprepares;
         if let (Some(ls1), Some(ls2)) = (&mut self.latencies, &other.latencies) {
             ls1.raw.add(&ls2.raw).unwrap();
             ls1.co_fixed.add(&ls2.co_fixed).unwrap();
         }
+        if let (Some(pws1), Some(pws2)) = (&mut self.partition_widths, &other.partition_widths) {
+            for (pk, rows) in pws2 {
+                *pws1.entry(*pk).or_insert(0) += rows;
+            }
+        }
+        if let (Some(rs1), Some(rs2)) = (&mut self.routing_latencies, &other.routing_latencies) {
+            rs1.routed.add(&rs2.routed).unwrap();
+            rs1.misrouted.add(&rs2.misrouted).unwrap();
+        }
     }
 }
 
 impl Stats {
-    pub fn account_op(&mut self, ctx: &OperationContext, result: &Result<()>, rows: usize) {
+    pub fn account_op<T>(&mut self, ctx: &OperationContext, result: &Result<T>, rows: usize) {
         self.operations += 1;
         match result {
-            Ok(()) => {
+            Ok(_) => {
                 self.clustering_rows += rows as u64;
                 self.account_latency(ctx);
             }
@@ -108,15 +216,54 @@ impl Stats {
         }
     }
 
+    pub fn account_timeout<T>(&mut self, result: &Result<T>) {
+        if let Err(err) = result {
+            if ErrorCategory::of(err) == ErrorCategory::Timeout {
+                self.timeouts += 1;
+            }
+            if err.is::<ClientTimeoutError>() {
+                self.client_timeouts += 1;
+            }
+        }
+    }
+
     pub fn account_latency(&mut self, ctx: &OperationContext) {
+        self.account_latency_since(ctx.actual_start_time, ctx.scheduled_start_time);
+    }
+
+    // Like account_latency, for the operations which outlive their context
+    pub fn account_latency_since(
+        &mut self,
+        actual_start_time: Instant,
+        scheduled_start_time: Instant,
+    ) {
         if let Some(ls) = &mut self.latencies {
             let now = Instant::now();
             let _ = ls
                 .raw
-                .record((now - ctx.actual_start_time).as_nanos() as u64 / self.latency_resolution);
-            let _ = ls.co_fixed.record(
-                (now - ctx.scheduled_start_time).as_nanos() as u64 / self.latency_resolution,
-            );
+                .record((now - actual_start_time).as_nanos() as u64 / self.latency_resolution);
+            let _ = ls
+                .co_fixed
+                .record((now - scheduled_start_time).as_nanos() as u64 / self.latency_resolution);
+        }
+    }
+
+    // Records the raw latency into the cohort of the requests routed the same way
+    pub fn account_routing_latency(&mut self, misrouted: bool, ctx: &OperationContext) {
+        if let Some(rs) = &mut self.routing_latencies {
+            let latency = (Instant::now() - ctx.actual_start_time).as_nanos() as u64;
+            let histogram = if misrouted {
+                &mut rs.misrouted
+            } else {
+                &mut rs.routed
+            };
+            let _ = histogram.record(latency / self.latency_resolution);
+        }
+    }
+
+    pub fn account_partition_rows(&mut self, pk: i64, rows: usize) {
+        if let Some(pws) = &mut self.partition_widths {
+            *pws.entry(pk).or_insert(0) += rows as u64;
         }
     }
 
@@ -128,6 +275,14 @@ impl Stats {
         };
         Some(histogram)
     }
+
+    // The histograms record the latencies in units of latency_resolution
+    pub fn latency_at_quantile(&self, typ: LatencyType, quantile: f64) -> Option<Duration> {
+        let histogram = self.get_histogram(typ)?;
+        Some(Duration::from_nanos(
+            histogram.value_at_quantile(quantile) * self.latency_resolution,
+        ))
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -136,19 +291,160 @@ pub enum LatencyType {
     AdjustedForCoordinatorOmission,
 }
 
+/// Format of the results printed for each reporting interval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A human-readable table, as printed by scylla-bench.
+    Text,
+    /// One JSON object per line, for consumers processing the results live.
+    Ndjson,
+}
+
+/// Unit of the rates printed in the final results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateUnit {
+    /// Operations per second, as printed by scylla-bench.
+    #[default]
+    One,
+    Thousands,
+    Millions,
+    /// Thousands or millions, whichever fits the rate.
+    Auto,
+}
+
+impl std::fmt::Display for RateUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RateUnit::One => "ops/s",
+            RateUnit::Thousands => "thousands of ops/s",
+            RateUnit::Millions => "millions of ops/s",
+            RateUnit::Auto => "thousands or millions of ops/s",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Unit of the latencies printed in the final results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatencyUnit {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
+impl LatencyUnit {
+    fn nanos(&self) -> f64 {
+        match self {
+            LatencyUnit::Nanoseconds => 1.0,
+            LatencyUnit::Microseconds => 1e3,
+            LatencyUnit::Milliseconds => 1e6,
+            LatencyUnit::Seconds => 1e9,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            LatencyUnit::Nanoseconds => "ns",
+            LatencyUnit::Microseconds => "μs",
+            LatencyUnit::Milliseconds => "ms",
+            LatencyUnit::Seconds => "s",
+        }
+    }
+
+    // The largest unit in which the latency is at least 1
+    fn fitting(latency: Duration) -> Self {
+        [
+            LatencyUnit::Seconds,
+            LatencyUnit::Milliseconds,
+            LatencyUnit::Microseconds,
+        ]
+        .into_iter()
+        .find(|unit| latency.as_nanos() as f64 >= unit.nanos())
+        .unwrap_or(LatencyUnit::Nanoseconds)
+    }
+}
+
+impl std::fmt::Display for LatencyUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.suffix())
+    }
+}
+
+/// Formatting of the values printed in the final results. It doesn't affect
+/// the results printed during the run, nor the measurement itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SummaryFormat {
+    pub rate_unit: RateUnit,
+    // If not set, the latency is printed like during the run
+    pub latency_unit: Option<LatencyUnit>,
+    // If not set, the values are printed with full precision
+    pub significant_figures: Option<usize>,
+}
+
+impl SummaryFormat {
+    fn format_rate(&self, rate: f64) -> String {
+        let (divisor, suffix) = match self.rate_unit {
+            RateUnit::One => (1.0, ""),
+            RateUnit::Thousands => (1e3, "K"),
+            RateUnit::Millions => (1e6, "M"),
+            RateUnit::Auto if rate >= 1e6 => (1e6, "M"),
+            RateUnit::Auto => (1e3, "K"),
+        };
+        format!("{}{}", self.format_number(rate / divisor), suffix)
+    }
+
+    fn format_latency(&self, latency: Duration) -> String {
+        let unit = match (self.latency_unit, self.significant_figures) {
+            (Some(unit), _) => unit,
+            (None, Some(_)) => LatencyUnit::fitting(latency),
+            (None, None) => return format_duration(latency),
+        };
+        format!(
+            "{}{}",
+            self.format_number(latency.as_nanos() as f64 / unit.nanos()),
+            unit.suffix(),
+        )
+    }
+
+    fn format_number(&self, value: f64) -> String {
+        let Some(figures) = self.significant_figures else {
+            return value.to_string();
+        };
+        if value == 0.0 || !value.is_finite() {
+            return value.to_string();
+        }
+        // Position of the last significant digit relative to the decimal point
+        let magnitude = value.abs().log10().floor() as i32;
+        let decimals = figures as i32 - 1 - magnitude;
+        if decimals >= 0 {
+            format!("{:.*}", decimals as usize, value)
+        } else {
+            let scale = 10f64.powi(-decimals);
+            format!("{}", (value / scale).round() * scale)
+        }
+    }
+}
+
 type HistogramWriter = HistogramLogWriter<File>;
 
 // TODO: Should we have two impls, one with latency and another without?
 pub struct StatsPrinter {
     start_time: Instant,
+    // The final results are computed over the time since the warmup
+    measured_start_time: Instant,
     previous_time: Instant,
     latency_type: Option<LatencyType>,
+    output_format: OutputFormat,
     histogram_writer: Option<HistogramWriter>,
+    ascii_histogram_buckets: usize,
+    summary_format: SummaryFormat,
 }
 
 impl StatsPrinter {
     pub async fn new(
         latency_type: Option<LatencyType>,
+        output_format: OutputFormat,
         latency_file_name: Option<&str>,
     ) -> Result<Self> {
         let histogram_writer = if let Some(latency_file_name) = latency_file_name {
@@ -160,13 +456,38 @@ impl StatsPrinter {
         let now = Instant::now();
         Ok(Self {
             start_time: now,
+            measured_start_time: now,
             previous_time: now,
             latency_type,
+            output_format,
             histogram_writer,
+            ascii_histogram_buckets: 0,
+            summary_format: SummaryFormat::default(),
         })
     }
 
+    /// Makes the final results include an ASCII histogram of the latency
+    /// with the given number of buckets (none if zero).
+    pub fn with_ascii_histogram(mut self, buckets: usize) -> Self {
+        self.ascii_histogram_buckets = buckets;
+        self
+    }
+
+    pub fn with_summary_format(mut self, summary_format: SummaryFormat) -> Self {
+        self.summary_format = summary_format;
+        self
+    }
+
+    pub fn with_warmup(mut self, warmup: Duration) -> Self {
+        self.measured_start_time = self.start_time + warmup;
+        self
+    }
+
     pub fn print_header(&self, out: &mut impl Write) -> Result<()> {
+        if self.output_format == OutputFormat::Ndjson {
+            // Each line describes itself
+            return Ok(());
+        }
         if self.latency_type.is_some() {
             writeln!(
                 out,
@@ -198,7 +519,9 @@ impl StatsPrinter {
         let now = Instant::now();
         let time = now - self.start_time;
 
-        if let Some(typ) = self.latency_type {
+        if self.output_format == OutputFormat::Ndjson {
+            self.print_partial_ndjson(SystemTime::now(), time, stats, out)?;
+        } else if let Some(typ) = self.latency_type {
             let histogram = stats.get_histogram(typ).unwrap();
 
             let to_duration =
@@ -252,8 +575,44 @@ impl StatsPrinter {
         Ok(())
     }
 
+    fn print_partial_ndjson(
+        &self,
+        timestamp: SystemTime,
+        time: Duration,
+        stats: &Stats,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        let timestamp = timestamp.duration_since(SystemTime::UNIX_EPOCH)?;
+        write!(
+            out,
+            "{{\"timestamp_ms\":{},\"elapsed_ns\":{},\"ops\":{},\"rows\":{},\"errors\":{}",
+            timestamp.as_millis(),
+            time.as_nanos(),
+            stats.operations,
+            stats.clustering_rows,
+            stats.errors,
+        )?;
+        if let Some(typ) = self.latency_type {
+            let histogram = stats.get_histogram(typ).unwrap();
+            let to_nanos = |d: u64| d * stats.latency_resolution;
+            write!(
+                out,
+                ",\"latency_ns\":{{\"max\":{},\"p99.9\":{},\"p99\":{},\"p95\":{},\"p90\":{},\"p50\":{},\"mean\":{}}}",
+                to_nanos(histogram.max()),
+                to_nanos(histogram.value_at_quantile(0.999)),
+                to_nanos(histogram.value_at_quantile(0.99)),
+                to_nanos(histogram.value_at_quantile(0.95)),
+                to_nanos(histogram.value_at_quantile(0.9)),
+                to_nanos(histogram.value_at_quantile(0.5)),
+                to_nanos(histogram.mean() as u64),
+            )?;
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
     pub fn print_final(&self, stats: &Stats, out: &mut impl Write) -> Result<()> {
-        let time = Instant::now() - self.start_time;
+        let time = Instant::now().saturating_duration_since(self.measured_start_time);
         writeln!(out)?;
         writeln!(out, "Results:")?;
         writeln!(out, "Time (avg):\t{}", format_duration(time))?;
@@ -262,16 +621,105 @@ impl StatsPrinter {
         if stats.errors != 0 {
             writeln!(out, "Total errors:\t{}", stats.errors)?;
         }
+        if stats.timeouts != 0 {
+            writeln!(out, "Timeouts:\t{}", stats.timeouts)?;
+        }
+        if stats.client_timeouts != 0 {
+            writeln!(out, "Client timeouts:\t{}", stats.client_timeouts)?;
+        }
+        if stats.downgraded_operations != 0 {
+            writeln!(out, "Downgraded ops:\t{}", stats.downgraded_operations)?;
+        }
+        if stats.split_batches != 0 {
+            writeln!(out, "Split batches:\t{}", stats.split_batches)?;
+        }
+        if stats.audited_writes != 0 {
+            writeln!(out, "Audited writes:\t{}", stats.audited_writes)?;
+            writeln!(out, "Unconfirmed writes:\t{}", stats.unconfirmed_writes)?;
+        }
+        if stats.uncertain_writes != 0 {
+            writeln!(out, "Uncertain writes:\t{}", stats.uncertain_writes)?;
+        }
+        if stats.sent_writes != 0 {
+            let secs = time.as_secs_f64();
+            writeln!(
+                out,
+                "Sent writes:\t{} ({:.0} op/s)",
+                stats.sent_writes,
+                stats.sent_writes as f64 / secs,
+            )?;
+            writeln!(
+                out,
+                "Acked writes:\t{} ({:.0} op/s)",
+                stats.operations.saturating_sub(stats.errors),
+                stats.operations.saturating_sub(stats.errors) as f64 / secs,
+            )?;
+        }
+        if stats.transient_mismatches != 0 {
+            writeln!(out, "Transient mismatches:\t{}", stats.transient_mismatches)?;
+        }
+        if stats.divergent_keys != 0 {
+            writeln!(out, "Divergent keys:\t{}", stats.divergent_keys)?;
+        }
+        if stats.checksummed_rows != 0 {
+            writeln!(
+                out,
+                "Data checksum:\t{:016x} ({} rows)",
+                stats.data_checksum, stats.checksummed_rows,
+            )?;
+        }
+        if stats.validated_rows != 0 {
+            writeln!(
+                out,
+                "Validated rows:\t{} ({:.2}%)",
+                stats.validated_rows,
+                stats.validated_rows as f64 * 100.0 / stats.clustering_rows as f64,
+            )?;
+        }
+        if stats.reprepares != 0 {
+            writeln!(out, "Reprepares:\t{}", stats.reprepares)?;
+        }
 
         let ops_per_second = stats.operations as f64 / time.as_secs_f64();
-        writeln!(out, "Operations/s:\t{}", ops_per_second)?;
+        writeln!(
+            out,
+            "Operations/s:\t{}",
+            self.summary_format.format_rate(ops_per_second),
+        )?;
 
         let rows_per_second = stats.clustering_rows as f64 / time.as_secs_f64();
-        writeln!(out, "Rows/s:\t\t{}", rows_per_second)?;
+        writeln!(
+            out,
+            "Rows/s:\t\t{}",
+            self.summary_format.format_rate(rows_per_second),
+        )?;
 
         if let Some(ls) = &stats.latencies {
-            self.print_final_latency_histogram("raw latency", &ls.raw, out)?;
-            self.print_final_latency_histogram("c-o fixed latency", &ls.co_fixed, out)?;
+            let resolution = stats.latency_resolution;
+            self.print_final_latency_histogram("raw latency", &ls.raw, resolution, out)?;
+            self.print_final_latency_histogram("c-o fixed latency", &ls.co_fixed, resolution, out)?;
+        }
+
+        if let (Some(typ), true) = (self.latency_type, self.ascii_histogram_buckets > 0) {
+            let name = match typ {
+                LatencyType::Raw => "raw latency",
+                LatencyType::AdjustedForCoordinatorOmission => "c-o fixed latency",
+            };
+            writeln!(out, "{} distribution:", name)?;
+            write_ascii_histogram(
+                stats.get_histogram(typ).unwrap(),
+                stats.latency_resolution,
+                self.ascii_histogram_buckets,
+                out,
+            )?;
+        }
+
+        if let Some(pws) = &stats.partition_widths {
+            self.print_final_partition_widths(pws, out)?;
+        }
+
+        if let Some(rs) = &stats.routing_latencies {
+            self.print_final_routing(rs, stats.latency_resolution, out)?;
         }
 
         // TODO: "critical errors"
@@ -283,29 +731,257 @@ impl StatsPrinter {
         &self,
         name: &str,
         latency: &Histogram<u64>,
+        resolution: u64,
         out: &mut impl Write,
     ) -> Result<()> {
         // TODO: Use non-shortened version of the format_duration
         writeln!(out, "{}:", name)?;
 
-        let p50 = Duration::from_nanos(latency.value_at_quantile(0.5));
-        let p90 = Duration::from_nanos(latency.value_at_quantile(0.9));
-        let p95 = Duration::from_nanos(latency.value_at_quantile(0.95));
-        let p99 = Duration::from_nanos(latency.value_at_quantile(0.99));
-        let p999 = Duration::from_nanos(latency.value_at_quantile(99.9));
-        let max = Duration::from_nanos(latency.max());
-        let mean = Duration::from_nanos(latency.mean() as u64);
-
-        writeln!(out, "  max:\t\t{}", format_duration(max))?;
-        writeln!(out, "  99.9th:\t{}", format_duration(p999))?;
-        writeln!(out, "  99h:\t\t{}", format_duration(p99))?;
-        writeln!(out, "  95h:\t\t{}", format_duration(p95))?;
-        writeln!(out, "  90h:\t\t{}", format_duration(p90))?;
-        writeln!(out, "  median:\t{}", format_duration(p50))?;
-        writeln!(out, "  mean:\t\t{}", format_duration(mean))?;
+        // The histograms record the latency in the units of -hdr-latency-units
+        let to_duration = |d: u64| Duration::from_nanos(d * resolution);
+        let p50 = to_duration(latency.value_at_quantile(0.5));
+        let p90 = to_duration(latency.value_at_quantile(0.9));
+        let p95 = to_duration(latency.value_at_quantile(0.95));
+        let p99 = to_duration(latency.value_at_quantile(0.99));
+        let p999 = to_duration(latency.value_at_quantile(0.999));
+        let max = to_duration(latency.max());
+        let mean = to_duration(latency.mean() as u64);
+
+        let format = |latency| self.summary_format.format_latency(latency);
+        writeln!(out, "  max:\t\t{}", format(max))?;
+        writeln!(out, "  99.9th:\t{}", format(p999))?;
+        writeln!(out, "  99h:\t\t{}", format(p99))?;
+        writeln!(out, "  95h:\t\t{}", format(p95))?;
+        writeln!(out, "  90h:\t\t{}", format(p90))?;
+        writeln!(out, "  median:\t{}", format(p50))?;
+        writeln!(out, "  mean:\t\t{}", format(mean))?;
+
+        Ok(())
+    }
 
+    fn print_final_routing(
+        &self,
+        routing: &RoutingHistograms,
+        resolution: u64,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        writeln!(out, "routing (raw latency):")?;
+        let to_duration = |d: u64| Duration::from_nanos(d * resolution);
+        for (name, latency) in [
+            ("token-aware", &routing.routed),
+            ("misrouted", &routing.misrouted),
+        ] {
+            writeln!(
+                out,
+                "  {}:\tops {}\tp50 {}\tp99 {}\tmean {}",
+                name,
+                latency.len(),
+                format_duration(to_duration(latency.value_at_quantile(0.5))),
+                format_duration(to_duration(latency.value_at_quantile(0.99))),
+                format_duration(to_duration(latency.mean() as u64)),
+            )?;
+        }
+        if !routing.routed.is_empty() && !routing.misrouted.is_empty() {
+            let penalty = routing.misrouted.mean() - routing.routed.mean();
+            writeln!(
+                out,
+                "  extra hop:\t{}{} on average",
+                if penalty < 0.0 { "-" } else { "" },
+                format_duration(to_duration(penalty.abs() as u64)),
+            )?;
+        }
         Ok(())
     }
+
+    fn print_final_partition_widths(
+        &self,
+        widths: &HashMap<i64, u64>,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        writeln!(out, "partition widths (rows):")?;
+        writeln!(out, "  partitions:\t{}", widths.len())?;
+        if widths.is_empty() {
+            return Ok(());
+        }
+
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        for rows in widths.values() {
+            histogram.saturating_record(*rows);
+        }
+
+        writeln!(out, "  min:\t\t{}", histogram.min())?;
+        writeln!(out, "  median:\t{}", histogram.value_at_quantile(0.5))?;
+        writeln!(out, "  99th:\t\t{}", histogram.value_at_quantile(0.99))?;
+        writeln!(out, "  max:\t\t{}", histogram.max())?;
+
+        Ok(())
+    }
+}
+
+/// Formats the headline numbers of the run as a single pipe-delimited line,
+/// which scripts can rely on regardless of the rest of the output:
+///
+/// `RESULT|ops=..|rows=..|errors=..|duration_s=..|achieved_ops=..|p50_us=..|p99_us=..|p999_us=..|max_us=..|status=..`
+///
+/// The fields are never renamed, removed or reordered, new ones may only be
+/// appended. The latencies are of the latency type printed during the run,
+/// and are `-` if the latency isn't measured. The status is `ok`, `error`
+/// if the run failed, in which case the line covers only the operations
+/// finished before the failure, or `targets_not_met` (see -enforce-targets).
+pub fn format_result_line(
+    stats: &Stats,
+    latency_type: Option<LatencyType>,
+    time: Duration,
+    status: &str,
+) -> String {
+    let histogram = latency_type.and_then(|typ| stats.get_histogram(typ));
+    let micros = |value: Option<u64>| match value {
+        Some(value) => (value * stats.latency_resolution / 1000).to_string(),
+        None => "-".to_owned(),
+    };
+    let quantile = |q| histogram.map(|h| h.value_at_quantile(q));
+    format!(
+        "RESULT|ops={}|rows={}|errors={}|duration_s={:.3}|achieved_ops={:.0}\
+        |p50_us={}|p99_us={}|p999_us={}|max_us={}|status={}",
+        stats.operations,
+        stats.clustering_rows,
+        stats.errors,
+        time.as_secs_f64(),
+        stats.operations as f64 / time.as_secs_f64(),
+        micros(quantile(0.5)),
+        micros(quantile(0.99)),
+        micros(quantile(0.999)),
+        micros(histogram.map(|h| h.max())),
+        status,
+    )
+}
+
+/// Version of the schema of the JSON summary. Bump it whenever a field
+/// is removed or changes its meaning; adding fields is backwards compatible.
+pub const JSON_SUMMARY_VERSION: u32 = 1;
+
+/// Writes the summary of the run as a single JSON object, for the tools
+/// which collect the results. `error` is the error which ended the run,
+/// in which case the summary covers only the operations finished before it.
+pub fn write_json_summary(
+    stats: &Stats,
+    latency_type: Option<LatencyType>,
+    time: Duration,
+    error: Option<&str>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let secs = time.as_secs_f64();
+    let per_second = |count: u64| {
+        if secs > 0.0 {
+            count as f64 / secs
+        } else {
+            0.0
+        }
+    };
+    write!(
+        out,
+        "{{\"version\":{},\"completed\":{},\"error\":{},\"duration_s\":{:.3},\
+        \"ops\":{},\"rows\":{},\"errors\":{},\"ops_per_s\":{:.1},\"rows_per_s\":{:.1}",
+        JSON_SUMMARY_VERSION,
+        error.is_none(),
+        error.map_or("null".to_owned(), json_string),
+        secs,
+        stats.operations,
+        stats.clustering_rows,
+        stats.errors,
+        per_second(stats.operations),
+        per_second(stats.clustering_rows),
+    )?;
+    match latency_type.and_then(|typ| stats.get_histogram(typ)) {
+        Some(histogram) => {
+            let to_nanos = |d: u64| d * stats.latency_resolution;
+            write!(
+                out,
+                ",\"latency_ns\":{{\"max\":{},\"p99.9\":{},\"p99\":{},\"p95\":{},\"p90\":{},\"p50\":{},\"mean\":{}}}",
+                to_nanos(histogram.max()),
+                to_nanos(histogram.value_at_quantile(0.999)),
+                to_nanos(histogram.value_at_quantile(0.99)),
+                to_nanos(histogram.value_at_quantile(0.95)),
+                to_nanos(histogram.value_at_quantile(0.9)),
+                to_nanos(histogram.value_at_quantile(0.5)),
+                to_nanos(histogram.mean() as u64),
+            )?;
+        }
+        None => write!(out, ",\"latency_ns\":null")?,
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+// Quotes the string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Width of the longest bar of the ASCII histogram.
+const ASCII_HISTOGRAM_WIDTH: usize = 40;
+
+/// Prints the latency histogram as bars, one for each of the `buckets`.
+/// The buckets are spaced logarithmically between the minimum and the maximum
+/// latency, so that both the bulk of the requests and the tail are visible.
+fn write_ascii_histogram(
+    histogram: &Histogram<u64>,
+    resolution: u64,
+    buckets: usize,
+    out: &mut impl Write,
+) -> Result<()> {
+    if histogram.is_empty() {
+        writeln!(out, "  no requests")?;
+        return Ok(());
+    }
+
+    let low = histogram.min().max(1) as f64;
+    let high = histogram.max().max(1) as f64;
+    let log_range = (high / low).ln();
+    let bound = |i: usize| low * (high / low).powf(i as f64 / buckets as f64);
+
+    let mut counts = vec![0u64; buckets];
+    for v in histogram.iter_recorded() {
+        let value = v.value_iterated_to().max(1) as f64;
+        let idx = if log_range > 0.0 {
+            (buckets as f64 * (value / low).ln() / log_range) as usize
+        } else {
+            0
+        };
+        counts[idx.min(buckets - 1)] += v.count_at_value();
+    }
+
+    let largest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let to_duration = |v: f64| Duration::from_nanos(v.round() as u64 * resolution);
+    for (i, count) in counts.iter().enumerate() {
+        // Round up, so that every non-empty bucket gets a visible bar
+        let width = (*count as usize * ASCII_HISTOGRAM_WIDTH).div_ceil(largest as usize);
+        writeln!(
+            out,
+            "  {:>9} - {:<9} |{:<width$}| {}",
+            format_duration(to_duration(bound(i))),
+            format_duration(to_duration(bound(i + 1))),
+            "#".repeat(width),
+            count,
+            width = ASCII_HISTOGRAM_WIDTH,
+        )?;
+    }
+
+    Ok(())
 }
 
 async fn init_hdr_log_writer(file_name: &str) -> Result<HistogramWriter> {
@@ -358,3 +1034,252 @@ async fn write_to_latencies_file(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use scylla::transport::errors::QueryError;
+    use sharded_stats::StatsFactory as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_print_partial_ndjson() {
+        let factory = StatsFactory {
+            measure_latency: true,
+            latency_sig_fig: 3,
+            latency_resolution: 1000,
+            record_partition_widths: false,
+            measure_routing: false,
+        };
+        let mut stats = factory.create();
+        stats.operations = 10;
+        stats.clustering_rows = 20;
+        stats.errors = 1;
+        let latencies = stats.latencies.as_mut().unwrap();
+        latencies.raw.record(5).unwrap();
+
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let time = Duration::from_secs(3);
+
+        let printer = StatsPrinter::new(None, OutputFormat::Ndjson, None)
+            .await
+            .unwrap();
+        let mut out = Vec::new();
+        printer
+            .print_partial_ndjson(timestamp, time, &stats, &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "{\"timestamp_ms\":1700000000123,\"elapsed_ns\":3000000000,\
+            \"ops\":10,\"rows\":20,\"errors\":1}\n",
+        );
+
+        let printer = StatsPrinter::new(Some(LatencyType::Raw), OutputFormat::Ndjson, None)
+            .await
+            .unwrap();
+        let mut out = Vec::new();
+        printer
+            .print_partial_ndjson(timestamp, time, &stats, &mut out)
+            .unwrap();
+        assert!(String::from_utf8_lossy(&out).ends_with(
+            ",\"errors\":1,\"latency_ns\":{\"max\":5000,\"p99.9\":5000,\"p99\":5000,\
+            \"p95\":5000,\"p90\":5000,\"p50\":5000,\"mean\":5000}}\n"
+        ));
+    }
+
+    #[test]
+    fn test_latency_at_quantile() {
+        let factory = StatsFactory {
+            measure_latency: true,
+            latency_sig_fig: 3,
+            latency_resolution: 1000,
+            record_partition_widths: false,
+            measure_routing: false,
+        };
+        let mut stats = factory.create();
+        let latencies = stats.latencies.as_mut().unwrap();
+        for us in 1..=100 {
+            latencies.raw.record(us).unwrap();
+        }
+
+        assert_eq!(
+            stats.latency_at_quantile(LatencyType::Raw, 0.5),
+            Some(Duration::from_micros(50)),
+        );
+        assert_eq!(
+            stats.latency_at_quantile(LatencyType::Raw, 0.99),
+            Some(Duration::from_micros(99)),
+        );
+        assert_eq!(
+            stats.latency_at_quantile(LatencyType::AdjustedForCoordinatorOmission, 0.5),
+            Some(Duration::ZERO),
+        );
+    }
+
+    #[test]
+    fn test_account_timeout() {
+        let factory = StatsFactory {
+            measure_latency: false,
+            latency_sig_fig: 3,
+            latency_resolution: 1,
+            record_partition_widths: false,
+            measure_routing: false,
+        };
+        let mut stats = factory.create();
+
+        stats.account_timeout(&Ok(()));
+        stats.account_timeout::<()>(&Err(anyhow::anyhow!("oops")));
+        assert_eq!(stats.timeouts, 0);
+
+        stats.account_timeout::<()>(&Err(QueryError::TimeoutError.into()));
+        assert_eq!(stats.timeouts, 1);
+        // Only the timeouts enforced by -operation-deadline are the client ones
+        assert_eq!(stats.client_timeouts, 0);
+    }
+
+    #[test]
+    fn test_write_ascii_histogram() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        histogram.record_n(1, 1).unwrap();
+        histogram.record_n(2, 2).unwrap();
+        histogram.record_n(4, 4).unwrap();
+
+        let mut out = Vec::new();
+        write_ascii_histogram(&histogram, 1000, 2, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            format!(
+                "     1.00μs - 2.00μs    |{:<40}| 1\n     2.00μs - 4.00μs    |{}| 6\n",
+                "#######",
+                "#".repeat(40),
+            ),
+        );
+
+        let mut out = Vec::new();
+        write_ascii_histogram(&Histogram::new(3).unwrap(), 1000, 2, &mut out).unwrap();
+        assert_eq!(String::from_utf8_lossy(&out), "  no requests\n");
+    }
+
+    #[test]
+    fn test_summary_format() {
+        let default = SummaryFormat::default();
+        assert_eq!(default.format_rate(12345.678), "12345.678");
+        assert_eq!(
+            default.format_latency(Duration::from_micros(1234)),
+            format_duration(Duration::from_micros(1234)),
+        );
+
+        let format = SummaryFormat {
+            rate_unit: RateUnit::Auto,
+            latency_unit: None,
+            significant_figures: Some(3),
+        };
+        assert_eq!(format.format_rate(12345.678), "12.3K");
+        assert_eq!(format.format_rate(2_345_678.0), "2.35M");
+        assert_eq!(format.format_latency(Duration::from_micros(1234)), "1.23ms");
+        assert_eq!(format.format_latency(Duration::from_nanos(999)), "999ns");
+
+        let format = SummaryFormat {
+            rate_unit: RateUnit::Thousands,
+            latency_unit: Some(LatencyUnit::Microseconds),
+            significant_figures: Some(2),
+        };
+        assert_eq!(format.format_rate(123456.0), "120K");
+        assert_eq!(format.format_latency(Duration::from_micros(1234)), "1200μs");
+        assert_eq!(format.format_latency(Duration::from_nanos(1500)), "1.5μs");
+    }
+
+    #[test]
+    fn test_format_result_line() {
+        let factory = StatsFactory {
+            measure_latency: true,
+            latency_sig_fig: 3,
+            latency_resolution: 1000,
+            record_partition_widths: false,
+            measure_routing: false,
+        };
+        let mut stats = factory.create();
+        stats.operations = 100;
+        stats.clustering_rows = 200;
+        stats.errors = 2;
+        stats.latencies.as_mut().unwrap().raw.record(1500).unwrap();
+
+        assert_eq!(
+            format_result_line(&stats, Some(LatencyType::Raw), Duration::from_secs(4), "ok"),
+            "RESULT|ops=100|rows=200|errors=2|duration_s=4.000|achieved_ops=25\
+            |p50_us=1500|p99_us=1500|p999_us=1500|max_us=1500|status=ok",
+        );
+        assert_eq!(
+            format_result_line(&stats, None, Duration::from_secs(4), "error"),
+            "RESULT|ops=100|rows=200|errors=2|duration_s=4.000|achieved_ops=25\
+            |p50_us=-|p99_us=-|p999_us=-|max_us=-|status=error",
+        );
+    }
+
+    #[test]
+    fn test_write_json_summary() {
+        let factory = StatsFactory {
+            measure_latency: true,
+            latency_sig_fig: 3,
+            latency_resolution: 1000,
+            record_partition_widths: false,
+            measure_routing: false,
+        };
+        let mut stats = factory.create();
+        stats.operations = 100;
+        stats.clustering_rows = 200;
+        stats.errors = 2;
+        stats.latencies.as_mut().unwrap().raw.record(1500).unwrap();
+
+        let mut out = Vec::new();
+        write_json_summary(&stats, None, Duration::from_secs(4), None, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "{\"version\":1,\"completed\":true,\"error\":null,\"duration_s\":4.000,\
+            \"ops\":100,\"rows\":200,\"errors\":2,\"ops_per_s\":25.0,\"rows_per_s\":50.0,\
+            \"latency_ns\":null}\n",
+        );
+
+        let mut out = Vec::new();
+        write_json_summary(
+            &stats,
+            Some(LatencyType::Raw),
+            Duration::from_secs(4),
+            Some("too many \"errors\"\n"),
+            &mut out,
+        )
+        .unwrap();
+        let out = String::from_utf8_lossy(&out);
+        assert!(out.contains("\"completed\":false,\"error\":\"too many \\\"errors\\\"\\n\""));
+        assert!(out.ends_with(
+            ",\"latency_ns\":{\"max\":1500000,\"p99.9\":1500000,\"p99\":1500000,\
+            \"p95\":1500000,\"p90\":1500000,\"p50\":1500000,\"mean\":1500000}}\n"
+        ));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("\u{1}\t"), "\"\\u0001\\t\"");
+    }
+
+    #[tokio::test]
+    async fn test_print_final_latency_histogram() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        histogram.record_n(1000, 999).unwrap();
+        histogram.record(1500).unwrap();
+
+        let printer = StatsPrinter::new(None, OutputFormat::Text, None)
+            .await
+            .unwrap();
+        let mut out = Vec::new();
+        printer
+            .print_final_latency_histogram("raw latency", &histogram, 1000, &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("  max:\t\t1.50ms\n"));
+        assert!(out.contains("  99.9th:\t1.00ms\n"));
+        assert!(out.contains("  median:\t1.00ms\n"));
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::time::Instant;
+
+use cql_stress::configuration::Configuration;
+
+use crate::args::ScyllaBenchArgs;
+use crate::gocompat::strconv::format_duration;
+use crate::stats::ShardedStats;
+
+/// Measurements from a single step of the latency-vs-throughput sweep.
+pub struct SweepStep {
+    /// `None` for the unthrottled step used to discover the maximum rate.
+    pub target_rate: Option<f64>,
+    pub achieved_rate: f64,
+    pub p50_latency: Duration,
+    pub p99_latency: Duration,
+}
+
+/// Runs the workload at a sequence of target rates and reports the latency
+/// observed at each of them.
+///
+/// The maximum rate is either provided with -max-rate or discovered by
+/// an initial unthrottled step. Step `i` out of `steps` then runs
+/// at `i / steps` of the maximum rate.
+pub async fn run_sweep(
+    template: Configuration,
+    args: &ScyllaBenchArgs,
+    stats: &ShardedStats,
+) -> Result<Vec<SweepStep>> {
+    let mut results = Vec::with_capacity(args.sweep_steps as usize + 1);
+
+    let max_rate = if args.maximum_rate > 0 {
+        args.maximum_rate as f64
+    } else {
+        eprintln!("Discovering the maximum rate");
+        let step = run_step(&template, None, args, stats).await?;
+        let max_rate = step.achieved_rate;
+        results.push(step);
+        max_rate
+    };
+
+    for step_idx in 1..=args.sweep_steps {
+        let target_rate = max_rate * step_idx as f64 / args.sweep_steps as f64;
+        eprintln!("Running at {:.0} ops/s", target_rate);
+        results.push(run_step(&template, Some(target_rate), args, stats).await?);
+    }
+
+    Ok(results)
+}
+
+async fn run_step(
+    template: &Configuration,
+    target_rate: Option<f64>,
+    args: &ScyllaBenchArgs,
+    stats: &ShardedStats,
+) -> Result<SweepStep> {
+    let config = Configuration {
+        max_duration: Some(args.sweep_step_duration),
+        warmup: None,
+        concurrency: template.concurrency,
+        rate_limit_per_second: target_rate,
+        rate_jitter: template.rate_jitter,
+        arrival_schedule: None,
+        operation_factory: Arc::clone(&template.operation_factory),
+        max_retries_per_op: template.max_retries_per_op,
+        worker_start_stagger: template.worker_start_stagger,
+        operation_hook: template.operation_hook.clone(),
+        error_ratio_limit: template.error_ratio_limit,
+        max_consecutive_errors: template.max_consecutive_errors,
+        retry_filter: template.retry_filter.clone(),
+    };
+
+    // Discard anything left over from the previous step
+    stats.get_combined_and_clear();
+
+    let start = Instant::now();
+    let (_ctrl, run_finished) = cql_stress::run::run(config);
+    run_finished
+        .await
+        .context("An error occurred during the sweep step")?;
+    let elapsed = start.elapsed();
+
+    let step_stats = stats.get_combined_and_clear();
+    let latency_at = |quantile| {
+        step_stats
+            .latency_at_quantile(args.latency_type, quantile)
+            .context("Latency must be measured during the sweep")
+    };
+    Ok(SweepStep {
+        target_rate,
+        achieved_rate: step_stats.operations as f64 / elapsed.as_secs_f64(),
+        p50_latency: latency_at(0.5)?,
+        p99_latency: latency_at(0.99)?,
+    })
+}
+
+pub fn print_sweep(steps: &[SweepStep], out: &mut impl Write) -> Result<()> {
+    writeln!(out)?;
+    writeln!(out, "Sweep results:")?;
+    writeln!(
+        out,
+        "{:>12} {:>12} {:>12} {:>12}",
+        "target", "achieved", "p50", "p99"
+    )?;
+    for step in steps {
+        let target = match step.target_rate {
+            Some(rate) => format!("{:.0}", rate),
+            None => "unlimited".to_string(),
+        };
+        writeln!(
+            out,
+            "{:>12} {:>12.0} {:>12} {:>12}",
+            target,
+            step.achieved_rate,
+            format_duration(step.p50_latency),
+            format_duration(step.p99_latency),
+        )?;
+    }
+    Ok(())
+}
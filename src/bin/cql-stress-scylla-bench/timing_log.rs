@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+use cql_stress::configuration::{OperationContext, OperationHook};
+
+/// Records the arrival times of the operations, so that they can be
+/// replayed later with -replay-timing.
+///
+/// The timing log contains one line per operation with its scheduled
+/// start time in nanoseconds since the start of the first operation,
+/// in increasing order.
+///
+/// The arrivals are streamed to the file by a background thread as the run
+/// goes, through a bounded channel which slows the workers down if the file
+/// can't keep up, so that the memory use doesn't grow with the run.
+pub struct TimingRecorder {
+    // Sends (operation ID, scheduled start time) of every attempt,
+    // taken when the recording is finished
+    sender: Mutex<Option<SyncSender<(u64, Instant)>>>,
+    writer: Mutex<Option<JoinHandle<Result<()>>>>,
+}
+
+// The number of arrivals which can wait for the writer thread
+const TIMING_LOG_CHANNEL_SIZE: usize = 64 * 1024;
+
+impl TimingRecorder {
+    pub fn new(path: &str) -> Result<Self> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create the timing log {}", path))?;
+        let (sender, receiver) = mpsc::sync_channel(TIMING_LOG_CHANNEL_SIZE);
+        let path = path.to_owned();
+        let writer = std::thread::spawn(move || {
+            write_arrivals(receiver, &mut BufWriter::new(file))
+                .with_context(|| format!("Failed to write the timing log {}", path))
+        });
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+
+    /// Stops the recording and waits until the log is written.
+    pub fn finish(&self) -> Result<()> {
+        drop(self.sender.lock().take());
+        match self.writer.lock().take() {
+            Some(writer) => writer.join().expect("the timing log writer panicked"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl OperationHook for TimingRecorder {
+    fn before_operation(&self, ctx: &OperationContext) {
+        // Cloned, so that a worker waiting for space in the channel
+        // doesn't hold up the other ones
+        let sender = self.sender.lock().clone();
+        if let Some(sender) = sender {
+            let _ = sender.send((ctx.operation_id, ctx.scheduled_start_time));
+        }
+    }
+}
+
+// Writes the arrivals in the order of their operation IDs. The workers
+// report them slightly out of order, so the arrivals are held back until
+// all of the preceding operations arrived; an arrival which still happened
+// earlier than the one written before it is written with the same time,
+// as the replay requires the times not to decrease.
+fn write_arrivals(
+    arrivals: impl IntoIterator<Item = (u64, Instant)>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut next_op_id = 0;
+    let mut pending = BTreeMap::new();
+    let mut first = None;
+    let mut last = Duration::ZERO;
+    let mut write = |time: Instant| -> Result<()> {
+        let first = *first.get_or_insert(time);
+        last = std::cmp::max(last, time.saturating_duration_since(first));
+        writeln!(out, "{}", last.as_nanos())?;
+        Ok(())
+    };
+
+    for (op_id, time) in arrivals {
+        // Retries are reported with the same operation ID,
+        // only the first attempt marks the arrival of the operation
+        if op_id < next_op_id {
+            continue;
+        }
+        pending.entry(op_id).or_insert(time);
+        while let Some(time) = pending.remove(&next_op_id) {
+            write(time)?;
+            next_op_id += 1;
+        }
+    }
+    // The operations which were issued but never started leave gaps
+    for time in pending.into_values() {
+        write(time)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Reads the arrival schedule from a timing log written by [TimingRecorder].
+pub fn read_timing_log(path: &str) -> Result<Arc<[Duration]>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open the timing log {}", path))?;
+    parse_timing_log(BufReader::new(file))
+        .with_context(|| format!("Failed to read the timing log {}", path))
+}
+
+fn parse_timing_log(input: impl BufRead) -> Result<Arc<[Duration]>> {
+    let mut schedule = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line = line?;
+        let nanos: u64 = line
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid arrival time on line {}: {}", idx + 1, line))?;
+        let arrival = Duration::from_nanos(nanos);
+        if let Some(prev) = schedule.last() {
+            anyhow::ensure!(
+                *prev <= arrival,
+                "Arrival times must not decrease, but line {} does",
+                idx + 1,
+            );
+        }
+        schedule.push(arrival);
+    }
+    anyhow::ensure!(!schedule.is_empty(), "The timing log is empty");
+    Ok(schedule.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_log_roundtrip() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let arrivals = [
+            (1, start + ms(5)),
+            (0, start + ms(2)),
+            (2, start + ms(7)),
+            // A retry of the operation 1
+            (1, start + ms(9)),
+            // Reported out of order, with an earlier time than the operation 2
+            (3, start + ms(6)),
+            // The operation 4 was never started
+            (5, start + ms(8)),
+        ];
+
+        let mut log = Vec::new();
+        write_arrivals(arrivals, &mut log).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&log),
+            "0\n3000000\n5000000\n5000000\n6000000\n"
+        );
+
+        let schedule = parse_timing_log(log.as_slice()).unwrap();
+        assert_eq!(&*schedule, &[ms(0), ms(3), ms(5), ms(5), ms(6)]);
+    }
+
+    #[test]
+    fn test_parse_bad_timing_log() {
+        assert!(parse_timing_log("".as_bytes()).is_err());
+        assert!(parse_timing_log("0\nfoo\n".as_bytes()).is_err());
+        assert!(parse_timing_log("0\n10\n5\n".as_bytes()).is_err());
+    }
+}
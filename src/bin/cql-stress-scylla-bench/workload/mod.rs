@@ -1,11 +1,15 @@
 mod sequential;
+mod time_bucket;
 mod timeseries_read;
 mod timeseries_write;
+mod token_range;
 mod uniform;
 
-pub use sequential::{SequentialConfig, SequentialFactory};
+pub use sequential::{SequentialConfig, SequentialFactory, SequentialProgress};
+pub use time_bucket::{TimeBucketConfig, TimeBucketReadFactory, TimeBucketWriteFactory};
 pub use timeseries_read::{TimeseriesReadConfig, TimeseriesReadFactory};
 pub use timeseries_write::{TimeseriesWriteConfig, TimeseriesWriteFactory};
+pub use token_range::{TokenRange, TokenRangeFactory};
 pub use uniform::{UniformConfig, UniformFactory};
 
 pub trait WorkloadFactory: Sync + Send {
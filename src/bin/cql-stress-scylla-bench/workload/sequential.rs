@@ -1,12 +1,19 @@
+use std::ops::Range;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use anyhow::Result;
 
+use crate::args::{AccessOrder, CkOrder, LoadMode};
+
 use super::{Workload, WorkloadFactory};
 
 struct SharedState {
     pub next_pk: AtomicU64,
+    pub next_worker_idx: AtomicU64,
+    // For each worker, a lower bound on the sequence number of the partition
+    // it is currently writing, or u64::MAX if it doesn't write any.
+    pub in_progress: Vec<AtomicU64>,
 }
 
 /// Creates workloads which write data sequentially.
@@ -20,11 +27,26 @@ pub struct SequentialFactory {
 
 struct Sequential {
     config: SequentialConfig,
-    shared_state: Arc<SharedState>,
+    pk_source: PkSource,
+    shuffle: Option<Shuffle>,
+    ck_shuffle: Option<Shuffle>,
     current_pk: u64,
     current_ck: u64,
 }
 
+enum PkSource {
+    // Partitions are claimed from a counter shared by all workers
+    Interleaved {
+        shared_state: Arc<SharedState>,
+        worker_idx: usize,
+    },
+    // The worker owns a contiguous block of partitions
+    Contiguous {
+        block: Range<u64>,
+        next_pk: u64,
+    },
+}
+
 /// Defines parameters of a sequential workload.
 ///
 /// The data set consists of `pks` partitions, each having `cks_per_pk`
@@ -43,12 +65,106 @@ struct Sequential {
 ///
 /// The whole data set will be written one or more times, depending on
 /// the `iterations` parameter.
+///
+/// With `LoadMode::Contiguous`, the partition range is instead split into
+/// `workers` contiguous blocks and each created workload only writes
+/// the partitions from its own block, which results in dense token ranges
+/// being written by each worker.
+///
+/// With `AccessOrder::Shuffled`, the partitions are visited in a scattered
+/// order instead of an increasing one - within the worker's block
+/// for `LoadMode::Contiguous`, or within the whole range for
+/// `LoadMode::Interleaved`. Each partition is still visited exactly once
+/// per iteration.
+///
+/// Within a partition, the clustering keys are written in the order
+/// given by `ck_order` - ascending, descending, or scattered across
+/// the partition with `CkOrder::Shuffled`. Each clustering key is still
+/// written exactly once per visit of the partition.
+///
+/// With `LoadMode::Interleaved`, the sequence of partitions starts
+/// at `resume_from` instead of zero. This allows to continue an interrupted
+/// run from the point recorded by [SequentialProgress].
 #[derive(Clone)]
 pub struct SequentialConfig {
     pub iterations: u64,
     pub partition_offset: i64,
     pub pks: u64,
     pub cks_per_pk: u64,
+    pub load_mode: LoadMode,
+    pub access_order: AccessOrder,
+    pub ck_order: CkOrder,
+    pub workers: u64,
+    pub resume_from: u64,
+}
+
+impl SequentialConfig {
+    /// Returns the range of the partition keys covered by the workload.
+    pub fn pk_range(&self) -> Range<i64> {
+        self.partition_offset..self.partition_offset + self.pks as i64
+    }
+
+    /// Returns the range of the partition keys written by each worker
+    /// with `LoadMode::Contiguous`, or `None` with `LoadMode::Interleaved`,
+    /// in which the workers claim partitions from the whole range.
+    pub fn worker_pk_ranges(&self) -> Option<Vec<Range<i64>>> {
+        match self.load_mode {
+            LoadMode::Interleaved => None,
+            LoadMode::Contiguous => Some(
+                (0..self.workers)
+                    .map(|idx| {
+                        let block = self.block(idx);
+                        block.start as i64 + self.partition_offset
+                            ..block.end as i64 + self.partition_offset
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Describes the partition key ranges in a single machine-readable line,
+    /// e.g. `pk-ranges: process=0..100 workers=0..50,50..100`. The ranges
+    /// are half-open. The workers are reported as `interleaved` if they
+    /// don't own separate ranges.
+    pub fn pk_ranges_line(&self) -> String {
+        let show = |r: &Range<i64>| format!("{}..{}", r.start, r.end);
+        let workers = match self.worker_pk_ranges() {
+            Some(ranges) => ranges.iter().map(show).collect::<Vec<_>>().join(","),
+            None => "interleaved".to_owned(),
+        };
+        format!(
+            "pk-ranges: process={} workers={}",
+            show(&self.pk_range()),
+            workers,
+        )
+    }
+
+    // The block of partition positions owned by the worker
+    // with `LoadMode::Contiguous`
+    fn block(&self, idx: u64) -> Range<u64> {
+        let block_bound = |idx: u64| (idx as u128 * self.pks as u128 / self.workers as u128) as u64;
+        block_bound(idx)..block_bound(idx + 1)
+    }
+}
+
+/// Allows to observe the progress of a sequential workload.
+pub struct SequentialProgress {
+    shared_state: Arc<SharedState>,
+    end_seq: u64,
+}
+
+impl SequentialProgress {
+    /// Returns the sequence number such that all partitions with lower
+    /// sequence numbers have already been written. It can be passed
+    /// as `resume_from` in order to continue the workload.
+    pub fn completed_seq(&self) -> u64 {
+        let next_pk = self.shared_state.next_pk.load(Ordering::SeqCst);
+        self.shared_state
+            .in_progress
+            .iter()
+            .map(|seq| seq.load(Ordering::SeqCst))
+            .fold(std::cmp::min(next_pk, self.end_seq), std::cmp::min)
+    }
 }
 
 impl SequentialFactory {
@@ -59,8 +175,14 @@ impl SequentialFactory {
             "Clustering key per partition count must be greater than zero",
         );
 
+        anyhow::ensure!(config.workers > 0, "Worker count must be greater than zero");
+
         let shared_state = Arc::new(SharedState {
-            next_pk: AtomicU64::new(0),
+            next_pk: AtomicU64::new(config.resume_from),
+            next_worker_idx: AtomicU64::new(0),
+            in_progress: (0..config.workers)
+                .map(|_| AtomicU64::new(u64::MAX))
+                .collect(),
         });
 
         Ok(Self {
@@ -68,46 +190,156 @@ impl SequentialFactory {
             shared_state,
         })
     }
+
+    pub fn progress(&self) -> SequentialProgress {
+        let end_seq = match self.config.iterations {
+            0 => u64::MAX,
+            iterations => self.config.pks * iterations,
+        };
+        SequentialProgress {
+            shared_state: Arc::clone(&self.shared_state),
+            end_seq,
+        }
+    }
 }
 
 impl WorkloadFactory for SequentialFactory {
     fn create(&self) -> Box<dyn Workload> {
-        Box::new(Sequential::new(
-            self.config.clone(),
-            self.shared_state.clone(),
-        ))
+        let idx = self
+            .shared_state
+            .next_worker_idx
+            .fetch_add(1, Ordering::Relaxed)
+            % self.config.workers;
+        let mut range_len = self.config.pks;
+        let pk_source = match self.config.load_mode {
+            LoadMode::Interleaved => PkSource::Interleaved {
+                shared_state: self.shared_state.clone(),
+                worker_idx: idx as usize,
+            },
+            LoadMode::Contiguous => {
+                let block = self.config.block(idx);
+                range_len = block.end - block.start;
+                PkSource::Contiguous { block, next_pk: 0 }
+            }
+        };
+        let shuffle = match self.config.access_order {
+            AccessOrder::Sequential => None,
+            AccessOrder::Shuffled => Some(Shuffle::new(range_len)),
+        };
+        Box::new(Sequential::new(self.config.clone(), pk_source, shuffle))
     }
 }
 
 impl Sequential {
-    fn new(config: SequentialConfig, shared_state: Arc<SharedState>) -> Self {
+    fn new(config: SequentialConfig, pk_source: PkSource, shuffle: Option<Shuffle>) -> Self {
         // This is dummy state, just in order to trigger choosing pk
         // on first `generate_keys` invocation
         let current_ck = config.cks_per_pk;
+        let ck_shuffle = match config.ck_order {
+            CkOrder::Shuffled => Some(Shuffle::new(config.cks_per_pk)),
+            CkOrder::Ascending | CkOrder::Descending => None,
+        };
         Sequential {
             config,
-            shared_state,
+            pk_source,
+            shuffle,
+            ck_shuffle,
             current_pk: 0,
             current_ck,
         }
     }
+
+    // Returns the position of the next partition to be written, or `None`
+    // if all iterations over the worker's partitions are done.
+    fn next_pk(&mut self) -> Option<u64> {
+        let iterations = self.config.iterations;
+        match &mut self.pk_source {
+            PkSource::Interleaved {
+                shared_state,
+                worker_idx,
+            } => {
+                // Publish a lower bound of the claimed sequence number first,
+                // so that the progress never skips over it
+                let in_progress = &shared_state.in_progress[*worker_idx];
+                in_progress.store(
+                    shared_state.next_pk.load(Ordering::SeqCst),
+                    Ordering::SeqCst,
+                );
+                let pk = shared_state.next_pk.fetch_add(1, Ordering::SeqCst);
+                if iterations > 0 && pk >= self.config.pks * iterations {
+                    in_progress.store(u64::MAX, Ordering::SeqCst);
+                    return None;
+                }
+                in_progress.store(pk, Ordering::SeqCst);
+                let pos = pk % self.config.pks;
+                Some(self.shuffle.as_ref().map_or(pos, |s| s.apply(pos)))
+            }
+            PkSource::Contiguous { block, next_pk } => {
+                let block_len = block.end - block.start;
+                if block_len == 0 || (iterations > 0 && *next_pk >= block_len * iterations) {
+                    return None;
+                }
+                let pos = *next_pk % block_len;
+                *next_pk += 1;
+                Some(block.start + self.shuffle.as_ref().map_or(pos, |s| s.apply(pos)))
+            }
+        }
+    }
+
+    // Returns the clustering key written at the given position within the partition
+    fn ck_at(&self, pos: u64) -> u64 {
+        match self.config.ck_order {
+            CkOrder::Descending => self.config.cks_per_pk - 1 - pos,
+            CkOrder::Ascending | CkOrder::Shuffled => {
+                self.ck_shuffle.as_ref().map_or(pos, |s| s.apply(pos))
+            }
+        }
+    }
+}
+
+// A permutation of 0..len which scatters neighbouring positions
+// across the whole range.
+struct Shuffle {
+    len: u64,
+    stride: u64,
+}
+
+impl Shuffle {
+    fn new(len: u64) -> Self {
+        // Multiplication by a number coprime with `len` is a bijection modulo `len`.
+        // Starting from the golden ratio of the range keeps the consecutive
+        // positions far apart.
+        let mut stride = (len as f64 * 0.618_033_988_7) as u64 | 1;
+        while gcd(stride, len) != 1 {
+            stride += 1;
+        }
+        Self { len, stride }
+    }
+
+    fn apply(&self, pos: u64) -> u64 {
+        (pos as u128 * self.stride as u128 % self.len as u128) as u64
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
 }
 
 impl Workload for Sequential {
     fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
         if self.current_ck >= self.config.cks_per_pk {
             self.current_ck = 0;
-            self.current_pk = self.shared_state.next_pk.fetch_add(1, Ordering::Relaxed);
-            if self.config.iterations > 0
-                && self.current_pk >= self.config.pks * self.config.iterations
-            {
-                return None;
-            }
+            self.current_pk = self.next_pk()?;
         }
 
-        let pk = (self.current_pk % self.config.pks) as i64 + self.config.partition_offset;
+        let pk = self.current_pk as i64 + self.config.partition_offset;
         let ck_end = std::cmp::min(self.current_ck + ck_count as u64, self.config.cks_per_pk);
-        let cks = (self.current_ck..ck_end).map(|x| x as i64).collect();
+        let cks = (self.current_ck..ck_end)
+            .map(|pos| self.ck_at(pos) as i64)
+            .collect();
         self.current_ck = ck_end;
 
         Some((pk, cks))
@@ -138,6 +370,11 @@ mod tests {
                 partition_offset: 0,
                 pks: 3,
                 cks_per_pk: 1,
+                load_mode: LoadMode::Interleaved,
+                access_order: AccessOrder::Sequential,
+                ck_order: CkOrder::Ascending,
+                workers: 1,
+                resume_from: 0,
             },
             1,
             &[(0, vec![0]), (1, vec![0]), (2, vec![0])],
@@ -150,6 +387,11 @@ mod tests {
                 partition_offset: 0,
                 pks: 3,
                 cks_per_pk: 1,
+                load_mode: LoadMode::Interleaved,
+                access_order: AccessOrder::Sequential,
+                ck_order: CkOrder::Ascending,
+                workers: 1,
+                resume_from: 0,
             },
             1,
             &[
@@ -169,6 +411,11 @@ mod tests {
                 partition_offset: 0,
                 pks: 3,
                 cks_per_pk: 2,
+                load_mode: LoadMode::Interleaved,
+                access_order: AccessOrder::Sequential,
+                ck_order: CkOrder::Ascending,
+                workers: 1,
+                resume_from: 0,
             },
             1,
             &[
@@ -188,6 +435,11 @@ mod tests {
                 partition_offset: 0,
                 pks: 2,
                 cks_per_pk: 5,
+                load_mode: LoadMode::Interleaved,
+                access_order: AccessOrder::Sequential,
+                ck_order: CkOrder::Ascending,
+                workers: 1,
+                resume_from: 0,
             },
             3,
             &[
@@ -198,4 +450,189 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn test_sequential_workload_contiguous() {
+        let config = SequentialConfig {
+            iterations: 2,
+            partition_offset: 10,
+            pks: 5,
+            cks_per_pk: 1,
+            load_mode: LoadMode::Contiguous,
+            access_order: AccessOrder::Sequential,
+            ck_order: CkOrder::Ascending,
+            workers: 2,
+            resume_from: 0,
+        };
+        let factory = SequentialFactory::new(config).unwrap();
+        let mut first = factory.create();
+        let mut second = factory.create();
+
+        let generate_all = |workload: &mut Box<dyn Workload>| {
+            let mut pks = Vec::new();
+            while let Some((pk, _)) = workload.generate_keys(1) {
+                pks.push(pk);
+            }
+            pks
+        };
+
+        assert_eq!(generate_all(&mut first), vec![10, 11, 10, 11]);
+        assert_eq!(generate_all(&mut second), vec![12, 13, 14, 12, 13, 14]);
+    }
+
+    #[test]
+    fn test_pk_ranges_line() {
+        let mut config = SequentialConfig {
+            iterations: 1,
+            partition_offset: 10,
+            pks: 5,
+            cks_per_pk: 1,
+            load_mode: LoadMode::Contiguous,
+            access_order: AccessOrder::Sequential,
+            ck_order: CkOrder::Ascending,
+            workers: 2,
+            resume_from: 0,
+        };
+        assert_eq!(
+            config.pk_ranges_line(),
+            "pk-ranges: process=10..15 workers=10..12,12..15",
+        );
+
+        // The ranges tile the whole range, even with more workers than partitions
+        config.workers = 7;
+        let ranges = config.worker_pk_ranges().unwrap();
+        assert_eq!(ranges.len(), 7);
+        assert_eq!(ranges.first().unwrap().start, 10);
+        assert_eq!(ranges.last().unwrap().end, 15);
+        assert!(ranges.windows(2).all(|w| w[0].end == w[1].start));
+
+        config.load_mode = LoadMode::Interleaved;
+        assert_eq!(
+            config.pk_ranges_line(),
+            "pk-ranges: process=10..15 workers=interleaved",
+        );
+    }
+
+    #[test]
+    fn test_sequential_workload_ck_order() {
+        let generate_cks = |ck_order: CkOrder| {
+            let config = SequentialConfig {
+                iterations: 1,
+                partition_offset: 0,
+                pks: 1,
+                cks_per_pk: 10,
+                load_mode: LoadMode::Interleaved,
+                access_order: AccessOrder::Sequential,
+                ck_order,
+                workers: 1,
+                resume_from: 0,
+            };
+            let mut workload = SequentialFactory::new(config).unwrap().create();
+            let mut cks = Vec::new();
+            while let Some((_, batch)) = workload.generate_keys(3) {
+                cks.extend(batch);
+            }
+            cks
+        };
+
+        assert_eq!(
+            generate_cks(CkOrder::Ascending),
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            generate_cks(CkOrder::Descending),
+            (0..10).rev().collect::<Vec<_>>()
+        );
+
+        let mut shuffled = generate_cks(CkOrder::Shuffled);
+        assert_ne!(shuffled, (0..10).collect::<Vec<_>>());
+        shuffled.sort();
+        assert_eq!(shuffled, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sequential_workload_resume() {
+        let config = SequentialConfig {
+            iterations: 1,
+            partition_offset: 0,
+            pks: 5,
+            cks_per_pk: 2,
+            load_mode: LoadMode::Interleaved,
+            access_order: AccessOrder::Sequential,
+            ck_order: CkOrder::Ascending,
+            workers: 2,
+            resume_from: 3,
+        };
+        let factory = SequentialFactory::new(config).unwrap();
+        let progress = factory.progress();
+        let mut first = factory.create();
+        let mut second = factory.create();
+        assert_eq!(progress.completed_seq(), 3);
+
+        assert_eq!(first.generate_keys(1), Some((3, vec![0])));
+        assert_eq!(second.generate_keys(1), Some((4, vec![0])));
+        assert_eq!(progress.completed_seq(), 3);
+
+        assert_eq!(first.generate_keys(1), Some((3, vec![1])));
+        assert_eq!(first.generate_keys(1), None);
+        assert_eq!(progress.completed_seq(), 4);
+
+        assert_eq!(second.generate_keys(1), Some((4, vec![1])));
+        assert_eq!(second.generate_keys(1), None);
+        assert_eq!(progress.completed_seq(), 5);
+    }
+
+    #[test]
+    fn test_sequential_workload_shuffled() {
+        let generate_all = |load_mode: LoadMode, workers: u64| {
+            let config = SequentialConfig {
+                iterations: 1,
+                partition_offset: 0,
+                pks: 20,
+                cks_per_pk: 1,
+                load_mode,
+                access_order: AccessOrder::Shuffled,
+                ck_order: CkOrder::Ascending,
+                workers,
+                resume_from: 0,
+            };
+            let factory = SequentialFactory::new(config).unwrap();
+            (0..workers)
+                .map(|_| {
+                    let mut workload = factory.create();
+                    let mut pks = Vec::new();
+                    while let Some((pk, _)) = workload.generate_keys(1) {
+                        pks.push(pk);
+                    }
+                    pks
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let pks = generate_all(LoadMode::Interleaved, 1).remove(0);
+        let mut sorted = pks.clone();
+        sorted.sort();
+        assert_ne!(pks, sorted);
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+
+        // Each worker stays within its own block
+        let blocks = generate_all(LoadMode::Contiguous, 2);
+        for (idx, pks) in blocks.into_iter().enumerate() {
+            let mut sorted = pks.clone();
+            sorted.sort();
+            assert_ne!(pks, sorted);
+            let start = 10 * idx as i64;
+            assert_eq!(sorted, (start..start + 10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_shuffle_is_permutation() {
+        for len in [1, 2, 7, 10, 64, 1000] {
+            let shuffle = Shuffle::new(len);
+            let mut positions: Vec<_> = (0..len).map(|pos| shuffle.apply(pos)).collect();
+            positions.sort();
+            assert_eq!(positions, (0..len).collect::<Vec<_>>());
+        }
+    }
 }
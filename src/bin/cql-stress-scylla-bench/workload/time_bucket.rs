@@ -0,0 +1,249 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use rand::Rng;
+
+use crate::distribution::RngGen;
+
+use super::{Workload, WorkloadFactory};
+
+/// Defines parameters of a time-bucketed workload.
+///
+/// Each of the `series` writes rows with timestamps spaced evenly over
+/// consecutive time buckets, and each bucket of a series is a separate
+/// partition, as is usual for the tables with the time window compaction
+/// strategy. The clustering key of a row is its timestamp in nanoseconds.
+#[derive(Clone)]
+pub struct TimeBucketConfig {
+    pub series: u64,
+    pub rows_per_bucket: u64,
+    pub bucket_nanos: u64,
+    pub bucket_count: u64,
+    // The first bucket is the one containing this timestamp;
+    // it is written from its start, so that all buckets are full
+    pub start_nanos: u64,
+}
+
+impl TimeBucketConfig {
+    fn validate(&self) -> Result<()> {
+        anyhow::ensure!(self.series > 0, "Invalid series count");
+        anyhow::ensure!(self.bucket_count > 0, "Invalid bucket count");
+        anyhow::ensure!(
+            self.rows_per_bucket > 0 && self.bucket_nanos >= self.rows_per_bucket,
+            "The time bucket must be at least as many nanoseconds long \
+            as the number of rows in it",
+        );
+        Ok(())
+    }
+
+    fn first_bucket(&self) -> u64 {
+        self.start_nanos / self.bucket_nanos
+    }
+
+    fn pk(&self, series: u64, bucket_idx: u64) -> i64 {
+        let bucket = (self.first_bucket() + bucket_idx) & 0xffff_ffff;
+        ((series << 32) | bucket) as i64
+    }
+
+    fn ck(&self, bucket_idx: u64, row_idx: u64) -> i64 {
+        let row_interval = self.bucket_nanos / self.rows_per_bucket;
+        ((self.first_bucket() + bucket_idx) * self.bucket_nanos + row_idx * row_interval) as i64
+    }
+}
+
+/// Creates workloads which write the buckets one after another,
+/// the same bucket of all series at a time.
+pub struct TimeBucketWriteFactory {
+    config: TimeBucketConfig,
+    counter: Arc<AtomicU64>,
+}
+
+struct TimeBucketWrite {
+    config: TimeBucketConfig,
+    counter: Arc<AtomicU64>,
+}
+
+impl TimeBucketWriteFactory {
+    pub fn new(config: TimeBucketConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            counter: Arc::new(AtomicU64::new(0)),
+        })
+    }
+}
+
+impl WorkloadFactory for TimeBucketWriteFactory {
+    fn create(&self) -> Box<dyn Workload> {
+        Box::new(TimeBucketWrite {
+            config: self.config.clone(),
+            counter: Arc::clone(&self.counter),
+        })
+    }
+}
+
+impl Workload for TimeBucketWrite {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        // Each operation writes a chunk of consecutive rows of a bucket
+        let chunk_size = (ck_count as u64).max(1);
+        let chunks_per_bucket = self.config.rows_per_bucket.div_ceil(chunk_size);
+
+        let x = self.counter.fetch_add(1, Ordering::Relaxed);
+        let series = x % self.config.series;
+        let chunk_seq = x / self.config.series;
+        let bucket_idx = chunk_seq / chunks_per_bucket;
+        if bucket_idx >= self.config.bucket_count {
+            return None;
+        }
+
+        let first_row = (chunk_seq % chunks_per_bucket) * chunk_size;
+        let last_row = (first_row + chunk_size).min(self.config.rows_per_bucket);
+        let cks = (first_row..last_row)
+            .map(|row_idx| self.config.ck(bucket_idx, row_idx))
+            .collect();
+
+        Some((self.config.pk(series, bucket_idx), cks))
+    }
+}
+
+/// Creates workloads which read the rows of random buckets of random series.
+pub struct TimeBucketReadFactory {
+    config: TimeBucketConfig,
+}
+
+struct TimeBucketRead {
+    config: TimeBucketConfig,
+    gen: RngGen,
+}
+
+impl TimeBucketReadFactory {
+    pub fn new(config: TimeBucketConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+}
+
+impl WorkloadFactory for TimeBucketReadFactory {
+    fn create(&self) -> Box<dyn Workload> {
+        Box::new(TimeBucketRead {
+            config: self.config.clone(),
+            gen: RngGen::new(rand::thread_rng().gen()),
+        })
+    }
+}
+
+impl Workload for TimeBucketRead {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        let series = self.gen.gen_range(0..self.config.series);
+        let bucket_idx = self.gen.gen_range(0..self.config.bucket_count);
+        let first_row = self.gen.gen_range(0..self.config.rows_per_bucket);
+        let last_row = (first_row + ck_count as u64).min(self.config.rows_per_bucket);
+        let cks = (first_row..last_row)
+            .map(|row_idx| self.config.ck(bucket_idx, row_idx))
+            .collect();
+
+        Some((self.config.pk(series, bucket_idx), cks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const HOUR_NANOS: u64 = 3_600_000_000_000;
+
+    fn config() -> TimeBucketConfig {
+        TimeBucketConfig {
+            series: 2,
+            rows_per_bucket: 4,
+            bucket_nanos: HOUR_NANOS,
+            bucket_count: 3,
+            start_nanos: 10 * HOUR_NANOS,
+        }
+    }
+
+    #[test]
+    fn test_time_bucket_write() {
+        let factory = TimeBucketWriteFactory::new(config()).unwrap();
+        let mut workload = factory.create();
+
+        let mut partitions: HashMap<i64, Vec<i64>> = HashMap::new();
+        let mut pk_order = Vec::new();
+        while let Some((pk, cks)) = workload.generate_keys(3) {
+            pk_order.push(pk);
+            partitions.entry(pk).or_default().extend(cks);
+        }
+
+        // Two chunks per bucket of each series, bucket after bucket
+        let pk = |series: i64, bucket: i64| (series << 32) | bucket;
+        assert_eq!(
+            pk_order,
+            [
+                pk(0, 10),
+                pk(1, 10),
+                pk(0, 10),
+                pk(1, 10),
+                pk(0, 11),
+                pk(1, 11),
+                pk(0, 11),
+                pk(1, 11),
+                pk(0, 12),
+                pk(1, 12),
+                pk(0, 12),
+                pk(1, 12),
+            ]
+        );
+
+        // Each partition holds the timestamps of its bucket
+        let quarter = (HOUR_NANOS / 4) as i64;
+        let bucket_start = 11 * HOUR_NANOS as i64;
+        assert_eq!(
+            partitions[&pk(1, 11)],
+            (0..4)
+                .map(|i| bucket_start + i * quarter)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(partitions.len(), 6);
+        assert!(partitions.values().all(|cks| cks.len() == 4));
+    }
+
+    #[test]
+    fn test_time_bucket_start_alignment() {
+        let mut config = config();
+        config.start_nanos += HOUR_NANOS / 2;
+        let mut workload = TimeBucketWriteFactory::new(config).unwrap().create();
+        let (pk, cks) = workload.generate_keys(1).unwrap();
+        assert_eq!(pk, 10);
+        assert_eq!(cks, [10 * HOUR_NANOS as i64]);
+    }
+
+    #[test]
+    fn test_time_bucket_read() {
+        let config = config();
+        let mut written = HashMap::new();
+        let mut write = TimeBucketWriteFactory::new(config.clone())
+            .unwrap()
+            .create();
+        while let Some((pk, cks)) = write.generate_keys(4) {
+            written.insert(pk, cks);
+        }
+
+        let mut read = TimeBucketReadFactory::new(config).unwrap().create();
+        for _ in 0..1000 {
+            let (pk, cks) = read.generate_keys(2).unwrap();
+            let row_cks = &written[&pk];
+            assert!(!cks.is_empty() && cks.len() <= 2);
+            assert!(cks.iter().all(|ck| row_cks.contains(ck)));
+        }
+    }
+
+    #[test]
+    fn test_invalid_time_bucket_config() {
+        let mut config = config();
+        config.bucket_nanos = 3;
+        assert!(TimeBucketWriteFactory::new(config).is_err());
+    }
+}
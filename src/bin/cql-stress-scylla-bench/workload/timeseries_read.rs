@@ -71,24 +71,34 @@ impl TimeseriesRead {
 
 impl Workload for TimeseriesRead {
     fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        let now_nanos = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() as u64;
+        Some(self.generate_keys_at(now_nanos, ck_count))
+    }
+}
+
+impl TimeseriesRead {
+    // Picks the keys among those which the timeseries write
+    // at the configured rate would have written until `now_nanos`
+    fn generate_keys_at(&mut self, now_nanos: u64, ck_count: usize) -> (i64, Vec<i64>) {
         let x = self.shared_state.counter.fetch_add(1, Ordering::Relaxed);
         let pk_position = x % self.config.pks_per_generation;
 
-        let now_nanos = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() as u64;
-        let max_generation = (now_nanos - self.config.start_nanos)
-            / (self.config.period_nanos * self.config.cks_per_pk)
-            + 1;
+        // Number of the clustering rows written to each partition position so far
+        let written_positions =
+            now_nanos.saturating_sub(self.config.start_nanos) / self.config.period_nanos + 1;
+        let max_generation = (written_positions - 1) / self.config.cks_per_pk + 1;
         let pk_generation = self.random_int(max_generation);
 
         let pk = (pk_position << 32) | pk_generation;
 
+        // Only the latest generation may be partially written
+        let max_range = std::cmp::min(
+            self.config.cks_per_pk,
+            written_positions - pk_generation * self.config.cks_per_pk,
+        );
         // We are OK with ck duplicates - at least scylla-bench is
         let cks = (0..ck_count)
             .map(|_| {
-                let max_range = std::cmp::min(
-                    self.config.cks_per_pk,
-                    (now_nanos - self.config.start_nanos) / self.config.period_nanos + 1,
-                );
                 let ck_position =
                     pk_generation * self.config.cks_per_pk + self.random_int(max_range);
 
@@ -96,11 +106,11 @@ impl Workload for TimeseriesRead {
             })
             .collect();
 
-        Some((pk as i64, cks))
+        (pk as i64, cks)
     }
-}
 
-impl TimeseriesRead {
+    // Returns an integer in 0..max_value, skewed towards the larger,
+    // i.e. more recent, values by the half-normal distribution
     fn random_int(&mut self, max_value: u64) -> u64 {
         match self.config.distribution {
             TimeseriesDistribution::HalfNormal => {
@@ -110,9 +120,55 @@ impl TimeseriesRead {
                 if base > 4.0 {
                     base = 4.0;
                 }
-                ((1.0 - base * 0.25) * max_value as f64) as u64
+                // The base of zero would give max_value itself
+                (((1.0 - base * 0.25) * max_value as f64) as u64).min(max_value - 1)
             }
             TimeseriesDistribution::Uniform => self.gen.gen_range(0..max_value),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_only_written_rows() {
+        for distribution in [
+            TimeseriesDistribution::Uniform,
+            TimeseriesDistribution::HalfNormal,
+        ] {
+            let config = TimeseriesReadConfig {
+                _partition_offset: 0,
+                pks_per_generation: 3,
+                cks_per_pk: 4,
+                start_nanos: 1000,
+                period_nanos: 10,
+                distribution,
+            };
+            let shared_state = Arc::new(SharedState {
+                counter: AtomicU64::new(0),
+            });
+            let mut read = TimeseriesRead::new(config, shared_state);
+
+            // By then, the rows at positions 0..6 were written to each partition
+            // position, filling up the generation 0 and half of the generation 1
+            let now = 1000 + 55;
+            for _ in 0..1000 {
+                let (pk, cks) = read.generate_keys_at(now, 3);
+                let generation = pk as u64 & 0xffff_ffff;
+                assert!(generation < 2);
+                for ck in cks {
+                    let position = (-ck as u64 - 1000) / 10;
+                    assert!(position < 6);
+                    assert_eq!(position / 4, generation);
+                }
+            }
+
+            // Before the start, only the first row can be read
+            let (pk, cks) = read.generate_keys_at(0, 1);
+            assert_eq!(pk as u64 & 0xffff_ffff, 0);
+            assert_eq!(cks, [-1000]);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use scylla::transport::partitioner::{Murmur3Partitioner, Partitioner};
+use tracing::error;
+
+use super::{Workload, WorkloadFactory};
+
+// Gives up after this many consecutive keys outside of the token range,
+// which happens when hardly any key of the workload falls into it
+const MAX_REJECTED_KEYS: u64 = 1_000_000;
+
+/// A range of Murmur3 tokens. Like the token ranges of the cluster,
+/// it is open on the left: `(start, end]`, and wraps around the ring
+/// if `start >= end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl TokenRange {
+    pub fn contains(&self, token: i64) -> bool {
+        if self.start < self.end {
+            self.start < token && token <= self.end
+        } else {
+            self.start < token || token <= self.end
+        }
+    }
+}
+
+/// Returns the Murmur3 token of the bigint partition key.
+pub fn pk_token(pk: i64) -> i64 {
+    Murmur3Partitioner.hash_one(&pk.to_be_bytes()).value()
+}
+
+/// Creates workloads which skip the keys generated by the inner workloads
+/// whose tokens don't fall into the token range.
+pub struct TokenRangeFactory {
+    inner: Box<dyn WorkloadFactory>,
+    range: TokenRange,
+}
+
+struct TokenRangeWorkload {
+    inner: Box<dyn Workload>,
+    range: TokenRange,
+}
+
+impl TokenRangeFactory {
+    pub fn new(inner: Box<dyn WorkloadFactory>, range: TokenRange) -> Self {
+        Self { inner, range }
+    }
+}
+
+impl WorkloadFactory for TokenRangeFactory {
+    fn create(&self) -> Box<dyn Workload> {
+        Box::new(TokenRangeWorkload {
+            inner: self.inner.create(),
+            range: self.range,
+        })
+    }
+}
+
+impl Workload for TokenRangeWorkload {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        for _ in 0..MAX_REJECTED_KEYS {
+            let (pk, cks) = self.inner.generate_keys(ck_count)?;
+            if self.range.contains(pk_token(pk)) {
+                return Some((pk, cks));
+            }
+        }
+        error!(
+            range = ?self.range,
+            "no generated partition key falls into the token range, stopping",
+        );
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::{AccessOrder, CkOrder, LoadMode};
+    use crate::workload::{SequentialConfig, SequentialFactory};
+
+    use super::*;
+
+    #[test]
+    fn test_token_range_contains() {
+        let range = TokenRange {
+            start: -10,
+            end: 10,
+        };
+        assert!(!range.contains(-10));
+        assert!(range.contains(-9));
+        assert!(range.contains(10));
+        assert!(!range.contains(11));
+
+        let wrapping = TokenRange {
+            start: 10,
+            end: -10,
+        };
+        assert!(wrapping.contains(i64::MAX));
+        assert!(wrapping.contains(i64::MIN));
+        assert!(wrapping.contains(-10));
+        assert!(!wrapping.contains(0));
+        assert!(!wrapping.contains(10));
+
+        let full_ring = TokenRange { start: 0, end: 0 };
+        assert!(full_ring.contains(0));
+        assert!(full_ring.contains(i64::MIN));
+    }
+
+    #[test]
+    fn test_token_range_workload() {
+        let config = SequentialConfig {
+            iterations: 1,
+            partition_offset: 0,
+            pks: 1000,
+            cks_per_pk: 2,
+            load_mode: LoadMode::Interleaved,
+            access_order: AccessOrder::Sequential,
+            ck_order: CkOrder::Ascending,
+            workers: 1,
+            resume_from: 0,
+        };
+        // The first quarter of the ring
+        let range = TokenRange {
+            start: i64::MIN,
+            end: i64::MIN / 2,
+        };
+        let factory =
+            TokenRangeFactory::new(Box::new(SequentialFactory::new(config).unwrap()), range);
+        let mut workload = factory.create();
+
+        let mut pks = Vec::new();
+        while let Some((pk, cks)) = workload.generate_keys(2) {
+            assert!(range.contains(pk_token(pk)));
+            assert_eq!(cks, vec![0, 1]);
+            pks.push(pk);
+        }
+        // All of the keys in the range are generated
+        let expected: Vec<_> = (0..1000)
+            .filter(|pk| range.contains(pk_token(*pk)))
+            .collect();
+        assert_eq!(pks, expected);
+        assert!((150..350).contains(&pks.len()));
+    }
+}
@@ -1,3 +1,4 @@
+use std::ops::ControlFlow;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -17,6 +18,17 @@ pub struct Configuration {
     /// If `None`, the test duration is unlimited.
     pub max_duration: Option<Duration>,
 
+    /// The duration of the warmup at the beginning of the run.
+    ///
+    /// The operations are performed normally during the warmup, but those
+    /// started before it ends are marked with [`OperationContext::warmup`],
+    /// so that their statistics can be discarded, e.g. with
+    /// [`ShardedStats::get_shard_mut_for`](crate::sharded_stats::ShardedStats::get_shard_mut_for).
+    /// The `max_duration` limit counts from the end of the warmup.
+    ///
+    /// If `None`, there is no warmup.
+    pub warmup: Option<Duration>,
+
     /// The concurrency with which the benchmark operations will be performed.
     ///
     /// The tool will spawn as many tokio tasks as this number specifies,
@@ -29,6 +41,22 @@ pub struct Configuration {
     /// If `None`, then there is no rate limit imposed.
     pub rate_limit_per_second: Option<f64>,
 
+    /// The fraction, between 0 and 1, by which each interval between
+    /// the operations scheduled by the rate limit is randomly varied.
+    /// The intervals are still 1 / `rate_limit_per_second` on average.
+    ///
+    /// If `None`, the operations are scheduled at uniform intervals.
+    pub rate_jitter: Option<f64>,
+
+    /// The start times of consecutive operations, relative to the start
+    /// of the run, e.g. recorded during a previous run.
+    ///
+    /// If set, it takes precedence over `rate_limit_per_second`: operation
+    /// with ID `i` is scheduled to start at `arrival_schedule[i]`, and no more
+    /// operations are issued after the schedule is exhausted. Retries of
+    /// an operation are not scheduled, they start immediately.
+    pub arrival_schedule: Option<Arc<[Duration]>>,
+
     /// A factory which creates operations that will be executed'
     /// during the stress.
     pub operation_factory: Arc<dyn OperationFactory>,
@@ -36,6 +64,60 @@ pub struct Configuration {
     /// The maximum number of attempts an operation should be retried
     /// before giving up.
     pub max_retries_per_op: usize,
+
+    /// The window over which the start of the workers is spread.
+    ///
+    /// Worker `i` out of `concurrency` starts its first operation after
+    /// `i * worker_start_stagger / concurrency` from the start of the run.
+    /// This avoids a spike of connections and requests at the very beginning
+    /// of the run.
+    ///
+    /// If `None`, all workers start immediately.
+    pub worker_start_stagger: Option<Duration>,
+
+    /// A hook notified before and after each operation.
+    ///
+    /// If `None`, no hook is invoked.
+    pub operation_hook: Option<Arc<dyn OperationHook>>,
+
+    /// Aborts the run if too large a fraction of the recent operations fail.
+    ///
+    /// If `None`, the run is not aborted regardless of the error ratio.
+    pub error_ratio_limit: Option<ErrorRatioLimit>,
+
+    /// Aborts the run if more than this many operation attempts fail
+    /// in a row, e.g. because the cluster went down.
+    ///
+    /// The streak is counted across all workers, in the order in which
+    /// the attempts finish, and a successful attempt in any worker resets it:
+    /// as long as some requests succeed, the cluster is still serving them.
+    /// Counting the streaks per worker would only repeat `max_retries_per_op`,
+    /// as a worker retries the failed operation until it succeeds.
+    ///
+    /// If `None`, the run is not aborted regardless of the errors in a row.
+    pub max_consecutive_errors: Option<u64>,
+
+    /// Decides which of the failed operations are retried, still within
+    /// the limit of `max_retries_per_op`. An operation which isn't retried
+    /// fails just like one which ran out of retries.
+    ///
+    /// If `None`, all of the failed operations are retried.
+    pub retry_filter: Option<Arc<dyn RetryFilter>>,
+}
+
+/// Limits the ratio of failed operations over a sliding window.
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorRatioLimit {
+    /// The maximum allowed ratio of failed operation attempts,
+    /// between 0 and 1.
+    pub max_ratio: f64,
+
+    /// The number of most recent operation attempts over which
+    /// the ratio is computed. The limit is only enforced after
+    /// at least this many attempts have finished.
+    ///
+    /// Must not be zero.
+    pub window: usize,
 }
 
 /// Contains all necessary context needed to execute an Operation.
@@ -66,6 +148,41 @@ pub struct OperationContext {
     /// with configured rate, this will be either equal or close
     /// to `scheduled_start_time`.
     pub actual_start_time: Instant,
+
+    /// Whether the operation was started during the warmup.
+    ///
+    /// Each operation, including each of its retries, is either
+    /// entirely within the warmup or entirely after it, so the operation
+    /// in flight when the warmup ends is neither lost nor counted twice.
+    pub warmup: bool,
+}
+
+/// Allows observing the operations executed during the stress,
+/// e.g. in order to feed their timings to a custom metrics system.
+///
+/// The methods are called from within the worker tasks, concurrently,
+/// so they should be cheap and must not block. Every attempt
+/// of an operation, including retries, is reported separately.
+pub trait OperationHook: Send + Sync {
+    /// Called right before the operation is executed.
+    fn before_operation(&self, _ctx: &OperationContext) {}
+
+    /// Called right after the operation has finished, with the time
+    /// elapsed since its actual start and its result.
+    fn after_operation(
+        &self,
+        _ctx: &OperationContext,
+        _duration: Duration,
+        _result: &Result<ControlFlow<()>>,
+    ) {
+    }
+}
+
+/// Decides whether an operation which failed with the given error
+/// is worth retrying, e.g. in order not to retry the errors which
+/// are going to repeat, like invalid queries.
+pub trait RetryFilter: Send + Sync {
+    fn should_retry(&self, err: &anyhow::Error) -> bool;
 }
 
 /// Creates operations which can later be used by workers during the stress.
@@ -1,42 +1,151 @@
 use std::future::Future;
 use std::ops::ControlFlow;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::Result;
 use futures::future::{AbortHandle, Abortable, Fuse, FutureExt};
 use futures::stream::{FuturesUnordered, StreamExt};
-use tokio::sync::oneshot;
+use rand::Rng;
+use tokio::sync::{oneshot, Notify};
 use tokio::time::Instant;
 
-use crate::configuration::{Configuration, OperationContext};
+use crate::configuration::{
+    Configuration, ErrorRatioLimit, OperationContext, OperationHook, RetryFilter,
+};
 
 // Rate limits operations by issuing timestamps indicating when the next
 // operation should happen. Uses atomics, can be shared between threads.
+// With jitter, each interval between the timestamps is randomly varied
+// by up to the given fraction, which keeps the mean rate.
+//
+// A single limiter is shared by all workers, so the rate is the total one
+// regardless of the concurrency. Each worker sleeps until its own timestamp,
+// hence the wakeups are spread evenly instead of happening all at once.
+// If the cluster can't keep up, the issued timestamps fall behind the current
+// time and the workers start the operations right away, never having more than
+// one operation in flight each; the lag is kept, because the latency with the
+// coordinated omission fix is measured from the scheduled start times.
 struct RateLimiter {
     base: Instant,
-    increment_nanos: u64,
+    increment_nanos: AtomicU64,
+    jitter: f64,
     nanos_counter: AtomicU64,
 }
 
 impl RateLimiter {
-    pub fn new(base: Instant, ops_per_second: f64) -> Self {
-        let increment_nanos = (1_000_000_000f64 / ops_per_second) as u64;
+    pub fn new(base: Instant, ops_per_second: f64, jitter: f64) -> Self {
         Self {
             base,
-            increment_nanos,
+            increment_nanos: AtomicU64::new(Self::increment_nanos(ops_per_second)),
+            jitter,
             nanos_counter: AtomicU64::new(0),
         }
     }
 
+    fn increment_nanos(ops_per_second: f64) -> u64 {
+        (1_000_000_000f64 / ops_per_second) as u64
+    }
+
     pub fn issue_next_start_time(&self) -> Instant {
+        let increment_nanos = self.increment_nanos.load(Ordering::Relaxed);
+        let increment_nanos = if self.jitter > 0.0 {
+            let factor = 1.0 + rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+            (increment_nanos as f64 * factor) as u64
+        } else {
+            increment_nanos
+        };
         let nanos = self
             .nanos_counter
-            .fetch_add(self.increment_nanos, Ordering::Relaxed);
+            .fetch_add(increment_nanos, Ordering::Relaxed);
 
         self.base + Duration::from_nanos(nanos)
     }
+
+    // Changes the rate of the subsequently issued timestamps.
+    pub fn set_rate(&self, ops_per_second: f64) {
+        self.increment_nanos
+            .store(Self::increment_nanos(ops_per_second), Ordering::Relaxed);
+        self.skip_to_now();
+    }
+
+    // Moves the schedule forward to the current time, if it's behind it,
+    // so that the operations which weren't issued in the meantime,
+    // e.g. during a pause, are not issued all at once.
+    pub fn skip_to_now(&self) {
+        let now_nanos = (Instant::now() - self.base).as_nanos() as u64;
+        self.nanos_counter.fetch_max(now_nanos, Ordering::Relaxed);
+    }
+}
+
+// Tracks the outcomes of the most recent operation attempts in a ring buffer.
+// Uses atomics, can be shared between threads. Concurrent updates may make
+// the error count momentarily inaccurate, which is fine for a stop condition.
+struct ErrorRatioTracker {
+    max_ratio: f64,
+    outcomes: Vec<AtomicBool>,
+    next_idx: AtomicU64,
+    error_count: AtomicI64,
+}
+
+impl ErrorRatioTracker {
+    pub fn new(limit: ErrorRatioLimit) -> Self {
+        Self {
+            max_ratio: limit.max_ratio,
+            outcomes: (0..limit.window).map(|_| AtomicBool::new(false)).collect(),
+            next_idx: AtomicU64::new(0),
+            error_count: AtomicI64::new(0),
+        }
+    }
+
+    // Records the outcome of an attempt. Returns the error ratio
+    // over the window if it exceeds the limit.
+    pub fn record(&self, failed: bool) -> Option<f64> {
+        let idx = self.next_idx.fetch_add(1, Ordering::Relaxed);
+        let window = self.outcomes.len() as u64;
+        let previously_failed =
+            self.outcomes[(idx % window) as usize].swap(failed, Ordering::Relaxed);
+        let error_count = match (previously_failed, failed) {
+            (false, true) => self.error_count.fetch_add(1, Ordering::Relaxed) + 1,
+            (true, false) => self.error_count.fetch_sub(1, Ordering::Relaxed) - 1,
+            _ => self.error_count.load(Ordering::Relaxed),
+        };
+
+        if idx + 1 < window {
+            // Not enough samples yet
+            return None;
+        }
+        let ratio = error_count as f64 / window as f64;
+        (ratio > self.max_ratio).then_some(ratio)
+    }
+}
+
+// Counts the operation attempts which failed in a row, across all workers.
+// Attempts which finish concurrently are ordered by when they are recorded.
+struct ErrorStreakTracker {
+    limit: u64,
+    streak: AtomicU64,
+}
+
+impl ErrorStreakTracker {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            streak: AtomicU64::new(0),
+        }
+    }
+
+    // Records the outcome of an attempt. Returns the number of errors
+    // in a row if it exceeds the limit.
+    pub fn record(&self, failed: bool) -> Option<u64> {
+        if !failed {
+            self.streak.store(0, Ordering::Relaxed);
+            return None;
+        }
+        let streak = self.streak.fetch_add(1, Ordering::Relaxed) + 1;
+        (streak > self.limit).then_some(streak)
+    }
 }
 
 // When an operation ID equal or larger to this value is issued, the worker
@@ -52,7 +161,16 @@ struct WorkerContext {
     operation_counter: AtomicU64,
 
     rate_limiter: Option<RateLimiter>,
+    arrival_schedule: Option<(Instant, Arc<[Duration]>)>,
     max_retries_per_op: usize,
+    warmup_end: Option<Instant>,
+    operation_hook: Option<Arc<dyn OperationHook>>,
+    error_ratio_tracker: Option<ErrorRatioTracker>,
+    error_streak_tracker: Option<ErrorStreakTracker>,
+    retry_filter: Option<Arc<dyn RetryFilter>>,
+
+    paused: AtomicBool,
+    resumed: Notify,
 }
 
 impl WorkerContext {
@@ -62,8 +180,28 @@ impl WorkerContext {
 
             rate_limiter: config
                 .rate_limit_per_second
-                .map(|rate| RateLimiter::new(now, rate)),
+                .map(|rate| RateLimiter::new(now, rate, config.rate_jitter.unwrap_or(0.0))),
+            arrival_schedule: config
+                .arrival_schedule
+                .as_ref()
+                .map(|schedule| (now, Arc::clone(schedule))),
             max_retries_per_op: config.max_retries_per_op,
+            warmup_end: config.warmup.map(|warmup| now + warmup),
+            operation_hook: config.operation_hook.clone(),
+            error_ratio_tracker: config.error_ratio_limit.map(ErrorRatioTracker::new),
+            error_streak_tracker: config.max_consecutive_errors.map(ErrorStreakTracker::new),
+            retry_filter: config.retry_filter.clone(),
+
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
+        }
+    }
+
+    // Whether the operation which failed with the given error may be retried
+    fn should_retry(&self, err: &anyhow::Error) -> bool {
+        match &self.retry_filter {
+            Some(filter) => filter.should_retry(err),
+            None => true,
         }
     }
 
@@ -71,6 +209,35 @@ impl WorkerContext {
     pub fn ask_to_stop(&self) {
         self.operation_counter
             .store(INVALID_OP_ID_THRESHOLD, Ordering::Relaxed);
+        // Let the paused workers notice that they should stop
+        self.resumed.notify_waiters();
+    }
+
+    // Prevents the workers from starting new operations until resumed
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.skip_to_now();
+            }
+            self.resumed.notify_waiters();
+        }
+    }
+
+    // Waits until the run is resumed or asked to stop, if it's paused
+    async fn wait_while_paused(&self) {
+        loop {
+            // Register for the notification before checking the state,
+            // so that a resume in between isn't missed
+            let resumed = self.resumed.notified();
+            if !self.paused.load(Ordering::SeqCst) || self.should_stop() {
+                return;
+            }
+            resumed.await;
+        }
     }
 
     // Was the worker asked to stop?
@@ -79,9 +246,15 @@ impl WorkerContext {
     }
 
     // Issues the next operation id. If the context got a signal to stop
-    // the stress operation, it will return `None`.
+    // the stress operation, or the arrival schedule is exhausted,
+    // it will return `None`.
     fn issue_operation_id(&self) -> Option<u64> {
         let id = self.operation_counter.fetch_add(1, Ordering::Relaxed);
+        if let Some((_, schedule)) = &self.arrival_schedule {
+            if id >= schedule.len() as u64 {
+                return None;
+            }
+        }
         (id < INVALID_OP_ID_THRESHOLD).then_some(id)
     }
 }
@@ -90,6 +263,11 @@ pub struct WorkerSession {
     context: Arc<WorkerContext>,
     op_id: u64,
     trial_idx: usize,
+
+    // Start times of the current operation, reported to the operation hook
+    scheduled_start_time: Instant,
+    actual_start_time: Instant,
+    warmup: bool,
 }
 
 // Not the most beautiful interface, but it works - unlike async callbacks,
@@ -100,17 +278,30 @@ impl WorkerSession {
             context,
             op_id: 0,
             trial_idx: 0,
+
+            scheduled_start_time: Instant::now(),
+            actual_start_time: Instant::now(),
+            warmup: false,
         }
     }
 
     // Should be called before starting an operation.
     pub async fn start_operation(&mut self) -> Option<OperationContext> {
+        self.context.wait_while_paused().await;
         if self.trial_idx == 0 {
             let next_op_id = self.context.issue_operation_id()?;
             self.op_id = next_op_id;
         }
 
-        let scheduled_start_time = if let Some(rate_limiter) = &self.context.rate_limiter {
+        let scheduled_start_time = if let Some((base, schedule)) = &self.context.arrival_schedule {
+            if self.trial_idx == 0 {
+                let start_time = *base + schedule[self.op_id as usize];
+                tokio::time::sleep_until(start_time).await;
+                start_time
+            } else {
+                Instant::now()
+            }
+        } else if let Some(rate_limiter) = &self.context.rate_limiter {
             let start_time = rate_limiter.issue_next_start_time();
             tokio::time::sleep_until(start_time).await;
             start_time
@@ -118,22 +309,76 @@ impl WorkerSession {
             Instant::now()
         };
         let actual_start_time = Instant::now();
+        let warmup = self
+            .context
+            .warmup_end
+            .is_some_and(|end| actual_start_time < end);
 
-        Some(OperationContext {
+        let ctx = OperationContext {
             operation_id: self.op_id,
             scheduled_start_time,
             actual_start_time,
-        })
+            warmup,
+        };
+        if let Some(hook) = &self.context.operation_hook {
+            self.scheduled_start_time = scheduled_start_time;
+            self.actual_start_time = actual_start_time;
+            self.warmup = warmup;
+            hook.before_operation(&ctx);
+        }
+        Some(ctx)
     }
 
     // Should be called after ending an operation.
     pub fn end_operation(&mut self, result: Result<ControlFlow<()>>) -> Result<ControlFlow<()>> {
+        if let Some(hook) = &self.context.operation_hook {
+            let ctx = OperationContext {
+                operation_id: self.op_id,
+                scheduled_start_time: self.scheduled_start_time,
+                actual_start_time: self.actual_start_time,
+                warmup: self.warmup,
+            };
+            hook.after_operation(&ctx, self.actual_start_time.elapsed(), &result);
+        }
+
+        if let Some(tracker) = &self.context.error_ratio_tracker {
+            if let Some(ratio) = tracker.record(result.is_err()) {
+                let msg = format!(
+                    "Error ratio of {:.3} over the last {} attempts exceeded the limit of {}",
+                    ratio,
+                    tracker.outcomes.len(),
+                    tracker.max_ratio,
+                );
+                return Err(match result {
+                    Err(err) => err.context(msg),
+                    Ok(_) => anyhow::anyhow!(msg),
+                });
+            }
+        }
+
+        if let Some(tracker) = &self.context.error_streak_tracker {
+            if let Some(streak) = tracker.record(result.is_err()) {
+                let msg = format!(
+                    "{} operation attempts failed in a row, exceeding the limit of {}",
+                    streak, tracker.limit,
+                );
+                return Err(match result {
+                    Err(err) => err.context(msg),
+                    Ok(_) => anyhow::anyhow!(msg),
+                });
+            }
+        }
+
         match result {
             Ok(flow) => {
                 self.trial_idx = 0;
                 Ok(flow)
             }
             Err(err) if self.trial_idx >= self.context.max_retries_per_op => Err(err),
+            Err(err) if !self.context.should_retry(&err) => Err(err),
+            // An operation which didn't succeed before the run was asked to stop
+            // is not retried and its error is reported, even if it's retryable:
+            // the run didn't perform all of its operations successfully.
             Err(err) if self.context.should_stop() => Err(err),
             Err(_) => {
                 self.trial_idx += 1;
@@ -145,9 +390,10 @@ impl WorkerSession {
 
 /// Allows controlling the state of the run.
 ///
-/// Currently, the `RunController` is only able to either gracefully stop
-/// or abort the run.
+/// The `RunController` is able to pause and resume the run, change its rate,
+/// and either gracefully stop or abort it.
 pub struct RunController {
+    context: Arc<WorkerContext>,
     stop_sender: Mutex<Option<oneshot::Sender<()>>>,
     abort_handle: AbortHandle,
 }
@@ -172,6 +418,39 @@ impl RunController {
     pub fn abort(&self) {
         self.abort_handle.abort();
     }
+
+    /// Pauses the run.
+    ///
+    /// The workers finish their current operations and don't start new ones
+    /// until the run is resumed. The time limit of the run keeps running.
+    pub fn pause(&self) {
+        self.context.pause();
+    }
+
+    /// Resumes the paused run.
+    ///
+    /// The operations which would have been started during the pause
+    /// according to the rate limit are skipped, not issued all at once.
+    pub fn resume(&self) {
+        self.context.resume();
+    }
+
+    /// Changes the maximum number of operations performed per second.
+    ///
+    /// Fails if the run was started without a rate limit, or is following
+    /// an arrival schedule.
+    pub fn set_rate(&self, ops_per_second: f64) -> Result<()> {
+        anyhow::ensure!(ops_per_second > 0.0, "The rate must be greater than zero",);
+        match &self.context.rate_limiter {
+            Some(rate_limiter) if self.context.arrival_schedule.is_none() => {
+                rate_limiter.set_rate(ops_per_second);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!(
+                "The rate can only be changed if the run was started with a rate limit"
+            )),
+        }
+    }
 }
 
 /// Runs an operation multiple times in parallel, according to config.
@@ -184,8 +463,12 @@ pub fn run(config: Configuration) -> (RunController, impl Future<Output = Result
     let (stop_sender, stop_receiver) = oneshot::channel();
     let (result_sender, result_receiver) = oneshot::channel();
 
+    let start_time = Instant::now();
+    let ctx = Arc::new(WorkerContext::new(&config, start_time));
+
+    let ctx_clone = Arc::clone(&ctx);
     let fut = async move {
-        let res = do_run(config, stop_receiver).await;
+        let res = do_run(config, ctx_clone, start_time, stop_receiver).await;
         let _ = result_sender.send(res);
     };
 
@@ -194,6 +477,7 @@ pub fn run(config: Configuration) -> (RunController, impl Future<Output = Result
     tokio::task::spawn(fut);
 
     let controller = RunController {
+        context: ctx,
         stop_sender: Mutex::new(Some(stop_sender)),
         abort_handle,
     };
@@ -208,17 +492,28 @@ pub fn run(config: Configuration) -> (RunController, impl Future<Output = Result
     (controller, result_fut)
 }
 
-async fn do_run(config: Configuration, stop_receiver: oneshot::Receiver<()>) -> Result<()> {
-    let start_time = Instant::now();
-    let ctx = Arc::new(WorkerContext::new(&config, start_time));
-
+async fn do_run(
+    config: Configuration,
+    ctx: Arc<WorkerContext>,
+    start_time: Instant,
+    stop_receiver: oneshot::Receiver<()>,
+) -> Result<()> {
     // Spawn as many worker tasks as the concurrency allows
     let mut worker_handles = (0..config.concurrency)
-        .map(|_| {
+        .map(|worker_idx| {
             let ctx_clone = Arc::clone(&ctx);
             let session = WorkerSession::new(ctx_clone);
             let mut operation = config.operation_factory.create();
-            let (fut, handle) = async move { operation.run(session).await }.remote_handle();
+            let worker_start = config.worker_start_stagger.map(|stagger| {
+                start_time + stagger_offset(stagger, worker_idx, config.concurrency)
+            });
+            let (fut, handle) = async move {
+                if let Some(worker_start) = worker_start {
+                    tokio::time::sleep_until(worker_start).await;
+                }
+                operation.run(session).await
+            }
+            .remote_handle();
             tokio::task::spawn(fut);
             handle
         })
@@ -227,7 +522,10 @@ async fn do_run(config: Configuration, stop_receiver: oneshot::Receiver<()>) ->
     // If there is a time limit, stop the run after the defined duration
     let ctx_clone = Arc::clone(&ctx);
     let sleeper = match config.max_duration {
-        Some(duration) => tokio::time::sleep_until(start_time + duration).fuse(),
+        Some(duration) => {
+            let warmup = config.warmup.unwrap_or(Duration::ZERO);
+            tokio::time::sleep_until(start_time + warmup + duration).fuse()
+        }
         None => Fuse::terminated(),
     };
     let _stopper_handle = {
@@ -254,6 +552,12 @@ async fn do_run(config: Configuration, stop_receiver: oneshot::Receiver<()>) ->
     result
 }
 
+// Computes how long after the start of the run the given worker should start.
+fn stagger_offset(stagger: Duration, worker_idx: u64, concurrency: u64) -> Duration {
+    let nanos = stagger.as_nanos() * worker_idx as u128 / concurrency as u128;
+    Duration::from_nanos(nanos as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -286,7 +590,7 @@ mod tests {
         let count_in_period = |ops: f64, period: Duration| -> usize {
             let start = Instant::now();
             let end = start + period;
-            let limiter = RateLimiter::new(start, ops);
+            let limiter = RateLimiter::new(start, ops, 0.0);
 
             let mut count = 0;
             while limiter.issue_next_start_time() < end {
@@ -303,6 +607,86 @@ mod tests {
         assert_eq!(count_in_period(2.0, 10 * sec), 20);
     }
 
+    #[tokio::test]
+    async fn test_run_with_rate_limit() {
+        let ops = Arc::new(AtomicU64::new(0));
+
+        struct Op(Arc<AtomicU64>);
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, _ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                // Without the limit, the workers could do 12800 ops/s
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let mut cfg = {
+            let ops = ops.clone();
+            make_test_cfg(move || Op(ops.clone()))
+        };
+        cfg.concurrency = 64;
+        cfg.rate_limit_per_second = Some(2000.0);
+        cfg.max_duration = Some(Duration::from_secs(2));
+        // Keep the controller, dropping it would ask the run to stop right away
+        let (_ctrl, fut) = run(cfg);
+        fut.await.unwrap();
+
+        // The rate limit is shared by all the workers
+        let ops = ops.load(Ordering::Relaxed);
+        assert!((3600..=4400).contains(&ops), "ops: {}", ops);
+    }
+
+    #[test]
+    fn test_rate_limiter_jitter() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(start, 10.0, 0.5);
+        let times: Vec<_> = (0..10_000)
+            .map(|_| limiter.issue_next_start_time())
+            .collect();
+
+        let intervals: Vec<_> = std::iter::once(start)
+            .chain(times.iter().copied())
+            .zip(times.iter())
+            .map(|(prev, next)| *next - prev)
+            .collect();
+        // The first timestamp is the base, the subsequent ones are jittered
+        assert!(intervals[1..]
+            .iter()
+            .all(|i| (Duration::from_millis(50)..=Duration::from_millis(150)).contains(i)));
+        assert!(intervals[1..].iter().any(|i| *i != intervals[1]));
+
+        // The mean rate is kept
+        let elapsed = (*times.last().unwrap() - start).as_secs_f64();
+        assert!((980.0..1020.0).contains(&elapsed), "elapsed: {}", elapsed);
+    }
+
+    #[test]
+    fn test_rate_limiter_set_rate() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(start, 10.0, 0.0);
+        assert_eq!(limiter.issue_next_start_time(), start);
+        let before_change = limiter.issue_next_start_time();
+        assert_eq!(before_change - start, Duration::from_millis(100));
+
+        // The schedule is ahead of the current time, so it's not moved
+        limiter.set_rate(100.0);
+        let first = limiter.issue_next_start_time();
+        assert_eq!(first - start, Duration::from_millis(200));
+        let second = limiter.issue_next_start_time();
+        assert_eq!(second - first, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_rate_limiter_skip_to_now() {
+        let start = Instant::now() - Duration::from_secs(10);
+        let limiter = RateLimiter::new(start, 1.0, 0.0);
+        limiter.skip_to_now();
+        assert!(limiter.issue_next_start_time() - start >= Duration::from_secs(10));
+    }
+
     fn make_test_cfg<T, F>(f: F) -> Configuration
     where
         T: Operation + 'static,
@@ -310,13 +694,77 @@ mod tests {
     {
         Configuration {
             max_duration: None,
+            warmup: None,
             concurrency: 10,
             rate_limit_per_second: None,
+            rate_jitter: None,
+            arrival_schedule: None,
             operation_factory: Arc::new(FnOperationFactory(f)),
             max_retries_per_op: 0,
+            worker_start_stagger: None,
+            operation_hook: None,
+            error_ratio_limit: None,
+            max_consecutive_errors: None,
+            retry_filter: None,
         }
     }
 
+    #[test]
+    fn test_stagger_offset() {
+        let stagger = Duration::from_secs(10);
+
+        assert_eq!(stagger_offset(stagger, 0, 4), Duration::ZERO);
+        assert_eq!(stagger_offset(stagger, 1, 4), Duration::from_millis(2500));
+        assert_eq!(stagger_offset(stagger, 3, 4), Duration::from_millis(7500));
+        assert_eq!(stagger_offset(Duration::ZERO, 3, 4), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_run_with_staggered_start() {
+        let first_op_times = Arc::new(Mutex::new(Vec::new()));
+
+        struct Op {
+            first_op_times: Arc<Mutex<Vec<Instant>>>,
+            started: bool,
+        }
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                if !self.started {
+                    self.started = true;
+                    self.first_op_times.lock().unwrap().push(Instant::now());
+                }
+                if ctx.operation_id >= 1000 {
+                    return Ok(ControlFlow::Break(()));
+                }
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let start = Instant::now();
+        let mut cfg = {
+            let first_op_times = first_op_times.clone();
+            make_test_cfg(move || Op {
+                first_op_times: first_op_times.clone(),
+                started: false,
+            })
+        };
+        cfg.worker_start_stagger = Some(Duration::from_millis(200));
+
+        // Keep the controller, dropping it would ask the run to stop
+        // before the staggered workers start
+        let (_ctrl, fut) = run(cfg);
+        fut.await.unwrap();
+
+        // The last worker is expected to start after 9/10 of the window
+        let times = first_op_times.lock().unwrap();
+        assert_eq!(times.len(), 10);
+        let last_start = times.iter().max().unwrap();
+        assert!(*last_start - start >= Duration::from_millis(180));
+    }
+
     #[tokio::test]
     async fn test_run_to_completion() {
         let counter = Arc::new(AtomicU64::new(0));
@@ -344,6 +792,131 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 499500);
     }
 
+    #[tokio::test]
+    async fn test_run_with_operation_hook() {
+        #[derive(Default)]
+        struct CountingHook {
+            started: AtomicU64,
+            succeeded: AtomicU64,
+            failed: AtomicU64,
+        }
+
+        impl OperationHook for CountingHook {
+            fn before_operation(&self, _ctx: &OperationContext) {
+                self.started.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn after_operation(
+                &self,
+                _ctx: &OperationContext,
+                _duration: Duration,
+                result: &Result<ControlFlow<()>>,
+            ) {
+                match result {
+                    Ok(_) => self.succeeded.fetch_add(1, Ordering::SeqCst),
+                    Err(_) => self.failed.fetch_add(1, Ordering::SeqCst),
+                };
+            }
+        }
+
+        let hook = Arc::new(CountingHook::default());
+        let mut cfg = make_test_cfg(AlternatingSuccessFailOp::new);
+        cfg.max_retries_per_op = 1;
+        cfg.operation_hook = Some(hook.clone());
+
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+
+        // Each of the 100 operations fails once and succeeds on retry,
+        // and each of the 10 workers finishes with an operation that breaks.
+        assert_eq!(hook.failed.load(Ordering::SeqCst), 100);
+        assert_eq!(hook.succeeded.load(Ordering::SeqCst), 110);
+        assert_eq!(hook.started.load(Ordering::SeqCst), 210);
+    }
+
+    #[test]
+    fn test_error_ratio_tracker() {
+        let tracker = ErrorRatioTracker::new(ErrorRatioLimit {
+            max_ratio: 0.5,
+            window: 4,
+        });
+
+        // The limit is not enforced until the window fills up
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(false), Some(0.75));
+
+        // Older outcomes fall out of the window
+        assert_eq!(tracker.record(false), None);
+        assert_eq!(tracker.record(false), None);
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), Some(0.75));
+    }
+
+    #[tokio::test]
+    async fn test_run_with_error_ratio_limit() {
+        // Half of the attempts fail, as each operation fails once
+        // and succeeds on retry.
+        let run_with_limit = |max_ratio| {
+            let mut cfg = make_test_cfg(AlternatingSuccessFailOp::new);
+            cfg.max_retries_per_op = 1;
+            cfg.error_ratio_limit = Some(ErrorRatioLimit {
+                max_ratio,
+                window: 100,
+            });
+            run(cfg).1
+        };
+
+        run_with_limit(0.9).await.unwrap();
+        run_with_limit(0.3).await.unwrap_err();
+    }
+
+    #[test]
+    fn test_error_streak_tracker() {
+        let tracker = ErrorStreakTracker::new(2);
+
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(false), None);
+
+        // A success resets the streak
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), None);
+        assert_eq!(tracker.record(true), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_run_with_max_consecutive_errors() {
+        // Each operation fails once and succeeds on retry, so at most
+        // one failure per worker happens between successes.
+        let mut cfg = make_test_cfg(AlternatingSuccessFailOp::new);
+        cfg.max_retries_per_op = 1;
+        cfg.max_consecutive_errors = Some(cfg.concurrency);
+        run(cfg).1.await.unwrap();
+
+        // Operations fail forever after some point, so only the limit
+        // stops the retries.
+        struct Op;
+
+        make_runnable!(Op);
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                if ctx.operation_id >= 100 {
+                    return Err(anyhow::anyhow!("failure"));
+                }
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let mut cfg = make_test_cfg(|| Op);
+        cfg.max_retries_per_op = usize::MAX;
+        cfg.max_consecutive_errors = Some(50);
+        let err = run(cfg).1.await.unwrap_err();
+        assert!(format!("{:#}", err).contains("in a row"), "{:#}", err);
+    }
+
     #[tokio::test]
     async fn test_run_to_error() {
         let counter = Arc::new(AtomicU64::new(0));
@@ -390,6 +963,145 @@ mod tests {
         fut.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_run_with_warmup() {
+        let warmup_ops = Arc::new(AtomicU64::new(0));
+        let measured_ops = Arc::new(AtomicU64::new(0));
+
+        struct Op(Arc<AtomicU64>, Arc<AtomicU64>);
+
+        make_runnable!(Op);
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                let counter = if ctx.warmup { &self.0 } else { &self.1 };
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let mut cfg = {
+            let (warmup_ops, measured_ops) = (warmup_ops.clone(), measured_ops.clone());
+            make_test_cfg(move || Op(warmup_ops.clone(), measured_ops.clone()))
+        };
+        cfg.warmup = Some(Duration::from_millis(100));
+        cfg.max_duration = Some(Duration::from_millis(100));
+
+        let start = Instant::now();
+        run(cfg).1.await.unwrap();
+
+        // The measured window starts after the warmup
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert!(warmup_ops.load(Ordering::SeqCst) > 0);
+        assert!(measured_ops.load(Ordering::SeqCst) > 0);
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let counter = Arc::new(AtomicU64::new(0));
+
+        struct Op(Arc<AtomicU64>);
+
+        make_runnable!(Op);
+        impl Op {
+            async fn execute(&mut self, _ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let cfg = {
+            let counter = counter.clone();
+            make_test_cfg(move || Op(counter.clone()))
+        };
+
+        let (ctrl, fut) = run(cfg);
+        let fut = tokio::spawn(fut);
+
+        ctrl.pause();
+        // Let the operations which were in progress finish
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let paused_count = counter.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), paused_count);
+
+        ctrl.resume();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(counter.load(Ordering::SeqCst) > paused_count);
+
+        // A paused run can still be stopped
+        ctrl.pause();
+        ctrl.ask_to_stop();
+        fut.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_rate_requires_rate_limit() {
+        let mut cfg = make_test_cfg(|| IdleOp);
+        cfg.max_duration = Some(Duration::from_millis(100));
+        let (ctrl, fut) = run(cfg);
+        assert!(ctrl.set_rate(100.0).is_err());
+        fut.await.unwrap();
+
+        let mut cfg = make_test_cfg(|| IdleOp);
+        cfg.max_duration = Some(Duration::from_millis(100));
+        cfg.rate_limit_per_second = Some(10.0);
+        let (ctrl, fut) = run(cfg);
+        ctrl.set_rate(100.0).unwrap();
+        assert!(ctrl.set_rate(0.0).is_err());
+        fut.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_with_arrival_schedule() {
+        let start_offsets = Arc::new(Mutex::new(Vec::new()));
+
+        struct Op {
+            start: Instant,
+            start_offsets: Arc<Mutex<Vec<(u64, Duration)>>>,
+        }
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                let offset = ctx.actual_start_time - self.start;
+                self.start_offsets
+                    .lock()
+                    .unwrap()
+                    .push((ctx.operation_id, offset));
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let ms = Duration::from_millis;
+        let start = Instant::now();
+        let mut cfg = {
+            let start_offsets = start_offsets.clone();
+            make_test_cfg(move || Op {
+                start,
+                start_offsets: start_offsets.clone(),
+            })
+        };
+        cfg.arrival_schedule = Some(vec![ms(0), ms(0), ms(50), ms(50), ms(100)].into());
+
+        // The run finishes on its own once the schedule is exhausted
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+
+        let mut start_offsets = start_offsets.lock().unwrap().clone();
+        start_offsets.sort();
+        assert_eq!(start_offsets.len(), 5);
+        for ((op_id, offset), expected) in start_offsets.into_iter().zip([0, 0, 50, 50, 100]) {
+            assert!(
+                offset >= ms(expected),
+                "op {} started at {:?}",
+                op_id,
+                offset
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_run_until_asked_to_stop() {
         let cfg = make_test_cfg(|| IdleOp);
@@ -471,6 +1183,82 @@ mod tests {
         fut.await.unwrap(); // Expect success as each op was retried
     }
 
+    struct NoRetries;
+
+    impl RetryFilter for NoRetries {
+        fn should_retry(&self, _err: &anyhow::Error) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_filter() {
+        let mut cfg = make_test_cfg(AlternatingSuccessFailOp::new);
+        cfg.max_retries_per_op = 1;
+        cfg.retry_filter = Some(Arc::new(NoRetries));
+        let (_, fut) = run(cfg);
+        fut.await.unwrap_err(); // Expect error as the filter rejected the retries
+    }
+
+    #[tokio::test]
+    async fn test_retrying_with_rate_limit() {
+        // Operation id and scheduled start time of each attempt
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let failed_ops = Arc::new(Mutex::new(HashSet::new()));
+
+        struct Op {
+            attempts: Arc<Mutex<Vec<(u64, Instant)>>>,
+            failed_ops: Arc<Mutex<HashSet<u64>>>,
+        }
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                if ctx.operation_id >= 20 {
+                    return Ok(ControlFlow::Break(()));
+                }
+                self.attempts
+                    .lock()
+                    .unwrap()
+                    .push((ctx.operation_id, ctx.scheduled_start_time));
+                if self.failed_ops.lock().unwrap().insert(ctx.operation_id) {
+                    Err(anyhow::anyhow!("oops"))
+                } else {
+                    Ok(ControlFlow::Continue(()))
+                }
+            }
+        }
+
+        let mut cfg = {
+            let attempts = attempts.clone();
+            make_test_cfg(move || Op {
+                attempts: attempts.clone(),
+                failed_ops: failed_ops.clone(),
+            })
+        };
+        cfg.rate_limit_per_second = Some(1000.0);
+        cfg.max_retries_per_op = 1;
+        // Keep the controller, dropping it would ask the run to stop,
+        // which would make the failed attempts end the run instead of retrying
+        let (_ctrl, fut) = run(cfg);
+        fut.await.unwrap();
+
+        let attempts = attempts.lock().unwrap().clone();
+
+        // The retries don't issue new operation ids
+        let mut op_ids: Vec<_> = attempts.iter().map(|(op_id, _)| *op_id).collect();
+        op_ids.sort();
+        let expected: Vec<_> = (0..20).flat_map(|op_id| [op_id, op_id]).collect();
+        assert_eq!(op_ids, expected);
+
+        // Each attempt, including the retries, takes its own slot of the rate limit
+        let mut start_times: Vec<_> = attempts.iter().map(|(_, start)| *start).collect();
+        start_times.sort();
+        assert!(start_times
+            .windows(2)
+            .all(|w| w[1] - w[0] >= Duration::from_millis(1)));
+    }
+
     struct AlwaysFailsOp(pub Option<Arc<Semaphore>>);
 
     make_runnable!(AlwaysFailsOp);
@@ -15,6 +15,8 @@ pub trait Stats: Sync + Send {
 
 pub struct ShardedStats<F: StatsFactory> {
     shards: ThreadLocal<Arc<Mutex<F::Stats>>>,
+    // Scratch shards for the warmup operations, never combined
+    warmup_shards: ThreadLocal<Mutex<F::Stats>>,
     all: Mutex<Vec<Arc<Mutex<F::Stats>>>>,
     factory: Arc<F>,
 }
@@ -36,6 +38,7 @@ impl<F: StatsFactory> ShardedStats<F> {
     pub fn new(factory: Arc<F>) -> Self {
         Self {
             shards: ThreadLocal::new(),
+            warmup_shards: ThreadLocal::new(),
             all: Mutex::new(Vec::new()),
             factory,
         }
@@ -52,6 +55,21 @@ impl<F: StatsFactory> ShardedStats<F> {
             .lock()
     }
 
+    /// Gets and locks access to this thread's stats structure, unless
+    /// the operation is a part of the warmup. Then, a scratch structure
+    /// is returned instead, whose statistics are discarded.
+    pub fn get_shard_mut_for(&self, warmup: bool) -> MutexGuard<'_, F::Stats> {
+        if !warmup {
+            return self.get_shard_mut();
+        }
+        let mut shard = self
+            .warmup_shards
+            .get_or(|| Mutex::new(self.factory.create()))
+            .lock();
+        shard.clear();
+        shard
+    }
+
     /// Combines statistics from all threads and clears all threads' stats.
     pub fn get_combined_and_clear(&self) -> F::Stats {
         let mut hist = self.factory.create();